@@ -0,0 +1,385 @@
+//! Validation of names pasted into generated shader source.
+//!
+//! Property names become uniform names in every export target, so a name
+//! must be an identifier in WGSL, GLSL, Godot shading language and HLSL, and
+//! must not collide with a keyword, type or builtin of any of them.
+
+/// Keywords and type names of WGSL, GLSL, Godot shading language and HLSL.
+const RESERVED_WORDS: &[&str] = &[
+    // Shared keywords
+    "break",
+    "case",
+    "const",
+    "continue",
+    "default",
+    "discard",
+    "do",
+    "else",
+    "false",
+    "for",
+    "if",
+    "in",
+    "inout",
+    "out",
+    "return",
+    "struct",
+    "switch",
+    "true",
+    "uniform",
+    "void",
+    "while",
+    // WGSL
+    "alias",
+    "const_assert",
+    "continuing",
+    "diagnostic",
+    "enable",
+    "fn",
+    "let",
+    "loop",
+    "override",
+    "requires",
+    "var",
+    "f16",
+    "f32",
+    "i32",
+    "u32",
+    "bool",
+    "vec2",
+    "vec3",
+    "vec4",
+    "mat2x2",
+    "mat2x3",
+    "mat2x4",
+    "mat3x2",
+    "mat3x3",
+    "mat3x4",
+    "mat4x2",
+    "mat4x3",
+    "mat4x4",
+    "array",
+    "atomic",
+    "ptr",
+    "sampler",
+    "sampler_comparison",
+    "texture_2d",
+    "texture_3d",
+    "texture_cube",
+    "function",
+    "private",
+    "storage",
+    "workgroup",
+    "read",
+    "write",
+    "read_write",
+    "vertex",
+    "fragment",
+    "compute",
+    // GLSL and Godot
+    "attribute",
+    "varying",
+    "layout",
+    "centroid",
+    "flat",
+    "smooth",
+    "noperspective",
+    "patch",
+    "sample",
+    "invariant",
+    "precise",
+    "precision",
+    "lowp",
+    "mediump",
+    "highp",
+    "float",
+    "double",
+    "int",
+    "uint",
+    "mat2",
+    "mat3",
+    "mat4",
+    "ivec2",
+    "ivec3",
+    "ivec4",
+    "uvec2",
+    "uvec3",
+    "uvec4",
+    "bvec2",
+    "bvec3",
+    "bvec4",
+    "sampler2D",
+    "sampler3D",
+    "samplerCube",
+    "buffer",
+    "shared",
+    "coherent",
+    "volatile",
+    "restrict",
+    "readonly",
+    "writeonly",
+    "subroutine",
+    "shader_type",
+    "render_mode",
+    "instance",
+    "global",
+    "group_uniforms",
+    "source_color",
+    "hint_range",
+    "light",
+    "sky",
+    "fog",
+    // HLSL and Unity
+    "half",
+    "half2",
+    "half3",
+    "half4",
+    "float2",
+    "float3",
+    "float4",
+    "int2",
+    "int3",
+    "int4",
+    "float3x3",
+    "float4x4",
+    "matrix",
+    "vector",
+    "cbuffer",
+    "tbuffer",
+    "register",
+    "packoffset",
+    "static",
+    "extern",
+    "groupshared",
+    "linear",
+    "nointerpolation",
+    "technique",
+    "pass",
+    "Texture2D",
+    "SamplerState",
+    "Properties",
+    "SubShader",
+    "Shader",
+    "Input",
+    "SurfaceOutputStandard",
+    "appdata_full",
+    "surf",
+    "vert",
+    "main",
+];
+
+/// Builtin functions of the targets. A uniform of the same name would
+/// shadow the function in the code the nodes emit.
+const BUILTIN_FUNCTIONS: &[&str] = &[
+    "abs",
+    "acos",
+    "all",
+    "any",
+    "asin",
+    "atan",
+    "atan2",
+    "ceil",
+    "clamp",
+    "cos",
+    "cosh",
+    "cross",
+    "ddx",
+    "ddy",
+    "degrees",
+    "determinant",
+    "dFdx",
+    "dFdy",
+    "distance",
+    "dot",
+    "dpdx",
+    "dpdy",
+    "exp",
+    "exp2",
+    "faceforward",
+    "floor",
+    "fma",
+    "fmod",
+    "frac",
+    "fract",
+    "fwidth",
+    "inverse",
+    "inversesqrt",
+    "inverseSqrt",
+    "length",
+    "lerp",
+    "log",
+    "log2",
+    "max",
+    "min",
+    "mix",
+    "mod",
+    "mul",
+    "normalize",
+    "pow",
+    "radians",
+    "reflect",
+    "refract",
+    "round",
+    "rsqrt",
+    "saturate",
+    "select",
+    "sign",
+    "sin",
+    "sinh",
+    "smoothstep",
+    "sqrt",
+    "step",
+    "tan",
+    "tanh",
+    "tex2D",
+    "texture",
+    "textureSample",
+    "transpose",
+    "trunc",
+];
+
+/// Names the generated code declares itself, e.g. the builtin uniforms.
+const GENERATED_NAMES: &[&str] = &[
+    "uniforms",
+    "time",
+    "delta_time",
+    "frame_count",
+    "resolution",
+    "mouse",
+    "camera_position",
+    "light_direction",
+    "light_intensity",
+    "light_color",
+    "ambient_intensity",
+    "view_proj",
+    "view",
+    "inverse_view",
+    "model",
+    "inverse_model",
+    "projection",
+    "inverse_projection",
+    "p3",
+    "p4",
+    "surface_alpha",
+    "lit",
+    // Parameters of the Unity vertex and surface functions
+    "o",
+    "v",
+];
+
+/// Prefixes reserved by a target or by generated variables. Unity declares
+/// its builtin values with a leading `_`, e.g. `_Time`.
+const RESERVED_PREFIXES: &[&str] = &["gl_", "_", "umbra_", "node_", "v_", "unity_", "UNITY_"];
+
+/// Checks that `name` can be used as a uniform name in every target.
+pub fn validate_identifier(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return Err("Name is empty".to_string());
+    };
+    if !(first.is_ascii_alphabetic() || first == '_') {
+        return Err(format!("'{}' must start with a letter or '_'", name));
+    }
+    if let Some(c) = chars.find(|c| !(c.is_ascii_alphanumeric() || *c == '_')) {
+        return Err(format!("'{}' contains '{}'", name, c));
+    }
+    // Godot builtins such as ALBEDO or TIME are upper case
+    let is_upper_case = name.chars().any(|c| c.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c == '_' || c.is_ascii_digit());
+    if is_upper_case {
+        return Err(format!("'{}' may clash with an engine builtin", name));
+    }
+    if RESERVED_WORDS.contains(&name)
+        || BUILTIN_FUNCTIONS.contains(&name)
+        || GENERATED_NAMES.contains(&name)
+    {
+        return Err(format!("'{}' is reserved", name));
+    }
+    if let Some(prefix) = RESERVED_PREFIXES.iter().find(|p| name.starts_with(*p)) {
+        return Err(format!("Names starting with '{}' are reserved", prefix));
+    }
+    Ok(())
+}
+
+/// Turns arbitrary text into an identifier: invalid characters become `_`
+/// and a leading digit is prefixed with `p_`. The result may still be
+/// reserved; check it with [`validate_identifier`].
+pub fn sanitize_identifier(name: &str) -> String {
+    let mut sanitized: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert_str(0, "p_");
+    }
+    sanitized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_plain_names() {
+        for name in ["tint", "base_color", "speed2", "Roughness", "p_sin"] {
+            assert_eq!(validate_identifier(name), Ok(()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_names() {
+        for name in ["", "2d", "my name", "tint-2", "café"] {
+            assert!(validate_identifier(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_names_the_targets_use() {
+        for name in [
+            "_",
+            "_Time",
+            "_MainTex",
+            "__x",
+            "gl_Position",
+            "unity_ObjectToWorld",
+            "float",
+            "vec3",
+            "float3",
+            "uniform",
+            "sin",
+            "dot",
+            "mix",
+            "lerp",
+            "saturate",
+            "normalize",
+            "clamp",
+            "pow",
+            "o",
+            "v",
+            "time",
+            "node_3",
+            "ALBEDO",
+        ] {
+            assert!(validate_identifier(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn sanitize_makes_identifiers() {
+        assert_eq!(sanitize_identifier(" base color "), "base_color");
+        assert_eq!(sanitize_identifier("tint-2"), "tint_2");
+        assert_eq!(sanitize_identifier("2d scale"), "p_2d_scale");
+        assert_eq!(
+            validate_identifier(&sanitize_identifier("2d scale")),
+            Ok(())
+        );
+    }
+}
@@ -2,6 +2,10 @@
 //!
 //! This module centralizes type definitions to avoid duplication.
 
+mod identifier;
 mod types;
+mod uniform_layout;
 
+pub use identifier::{sanitize_identifier, validate_identifier};
 pub use types::{Property, PropertyValue, ShaderIR};
+pub use uniform_layout::{UniformField, UniformKind, UniformLayout};
@@ -1,17 +1,29 @@
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 /// Unified property value type used across the entire application.
 /// This consolidates the previously separate PropertyValue enums.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PropertyValue {
     Float(f32),
     Vec2([f32; 2]),
     Vec3([f32; 3]),
     Vec4([f32; 4]),
     Color([f32; 4]),
+    /// Column-major 3x3 matrix.
+    Mat3([[f32; 3]; 3]),
+    /// Column-major 4x4 matrix.
+    Mat4([[f32; 4]; 4]),
     Int(i32),
     Bool(bool),
     Texture(Option<PathBuf>),
+    /// One of a fixed set of named options, e.g. a coordinate space.
+    Enum {
+        selected: usize,
+        options: Vec<String>,
+    },
+    /// Free-form text, e.g. a swizzle mask.
+    Text(String),
 }
 
 impl Default for PropertyValue {
@@ -29,6 +41,62 @@ impl PropertyValue {
         }
     }
 
+    /// Helper to get the components of a numeric value, reading integers
+    /// and booleans as floats
+    pub fn components(&self) -> Option<Vec<f32>> {
+        match self {
+            PropertyValue::Float(v) => Some(vec![*v]),
+            PropertyValue::Vec2(v) => Some(v.to_vec()),
+            PropertyValue::Vec3(v) => Some(v.to_vec()),
+            PropertyValue::Vec4(v) | PropertyValue::Color(v) => Some(v.to_vec()),
+            PropertyValue::Int(v) => Some(vec![*v as f32]),
+            PropertyValue::Bool(v) => Some(vec![if *v { 1.0 } else { 0.0 }]),
+            _ => None,
+        }
+    }
+
+    /// Helper to get the pin type a value is shown with, e.g. `Vec3`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PropertyValue::Float(_) => "Float",
+            PropertyValue::Vec2(_) => "Vec2",
+            PropertyValue::Vec3(_) => "Vec3",
+            PropertyValue::Vec4(_) => "Vec4",
+            PropertyValue::Color(_) => "Color",
+            PropertyValue::Mat3(_) => "Mat3",
+            PropertyValue::Mat4(_) => "Mat4",
+            PropertyValue::Int(_) => "Int",
+            PropertyValue::Bool(_) => "Bool",
+            PropertyValue::Texture(_) => "Texture",
+            PropertyValue::Enum { .. } => "Enum",
+            PropertyValue::Text(_) => "Text",
+        }
+    }
+
+    /// Helper to build an enum value from its option names
+    pub fn enumeration(selected: usize, options: &[&str]) -> Self {
+        PropertyValue::Enum {
+            selected,
+            options: options.iter().map(|o| o.to_string()).collect(),
+        }
+    }
+
+    /// Helper to get the name of the selected enum option
+    pub fn selected_option(&self) -> Option<&str> {
+        match self {
+            PropertyValue::Enum { selected, options } => options.get(*selected).map(|o| o.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Helper to get text value
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            PropertyValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
     /// Helper to get color as tuple (r, g, b, a)
     pub fn as_color_tuple(&self) -> Option<(f32, f32, f32, f32)> {
         match self {
@@ -0,0 +1,290 @@
+//! std140 layout of the project properties in the uniform buffer.
+//!
+//! Properties follow the builtin `Uniforms` block, whose size is a multiple
+//! of 16 bytes, so offsets here are relative to the start of the property
+//! block. The same layout generates the WGSL struct fields, sizes the buffer
+//! and packs the property values, keeping the three in agreement.
+
+use super::PropertyValue;
+use crate::file::project::ShaderProperty;
+
+/// Shader type of a property stored in the uniform buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UniformKind {
+    Float,
+    Vec2,
+    Vec3,
+    Vec4,
+    Mat3,
+    Mat4,
+    Int,
+    /// WGSL has no host-shareable `bool`; stored as a `u32` of 0 or 1.
+    Bool,
+}
+
+impl UniformKind {
+    /// Kind of a property value, or `None` for values that are not stored in
+    /// the uniform buffer (textures and node-only values).
+    pub fn of(value: &PropertyValue) -> Option<Self> {
+        match value {
+            PropertyValue::Float(_) => Some(Self::Float),
+            PropertyValue::Vec2(_) => Some(Self::Vec2),
+            PropertyValue::Vec3(_) => Some(Self::Vec3),
+            PropertyValue::Vec4(_) | PropertyValue::Color(_) => Some(Self::Vec4),
+            PropertyValue::Mat3(_) => Some(Self::Mat3),
+            PropertyValue::Mat4(_) => Some(Self::Mat4),
+            PropertyValue::Int(_) => Some(Self::Int),
+            PropertyValue::Bool(_) => Some(Self::Bool),
+            PropertyValue::Texture(_) | PropertyValue::Enum { .. } | PropertyValue::Text(_) => None,
+        }
+    }
+
+    /// Size in bytes. Matrix columns are padded to 16 bytes.
+    pub fn size(self) -> usize {
+        match self {
+            Self::Float | Self::Int | Self::Bool => 4,
+            Self::Vec2 => 8,
+            Self::Vec3 => 12,
+            Self::Vec4 => 16,
+            Self::Mat3 => 48,
+            Self::Mat4 => 64,
+        }
+    }
+
+    /// Required alignment in bytes.
+    pub fn align(self) -> usize {
+        match self {
+            Self::Float | Self::Int | Self::Bool => 4,
+            Self::Vec2 => 8,
+            Self::Vec3 | Self::Vec4 | Self::Mat3 | Self::Mat4 => 16,
+        }
+    }
+
+    pub fn wgsl_type(self) -> &'static str {
+        match self {
+            Self::Float => "f32",
+            Self::Vec2 => "vec2<f32>",
+            Self::Vec3 => "vec3<f32>",
+            Self::Vec4 => "vec4<f32>",
+            Self::Mat3 => "mat3x3<f32>",
+            Self::Mat4 => "mat4x4<f32>",
+            Self::Int => "i32",
+            Self::Bool => "u32",
+        }
+    }
+}
+
+/// A property's place in the uniform buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformField {
+    pub name: String,
+    pub kind: UniformKind,
+    /// Byte offset from the start of the property block.
+    pub offset: usize,
+}
+
+/// Offsets of the project properties stored in the uniform buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformLayout {
+    pub fields: Vec<UniformField>,
+    /// Size of the property block in bytes, padded to 16.
+    pub size: usize,
+}
+
+impl UniformLayout {
+    pub fn new(properties: &[ShaderProperty]) -> Self {
+        let mut fields = Vec::new();
+        let mut offset = 0;
+        for property in properties {
+            let Some(kind) = UniformKind::of(&property.value) else {
+                continue;
+            };
+            offset = align_to(offset, kind.align());
+            fields.push(UniformField {
+                name: property.name.clone(),
+                kind,
+                offset,
+            });
+            offset += kind.size();
+        }
+        Self {
+            fields,
+            size: align_to(offset, 16),
+        }
+    }
+
+    /// WGSL struct members for the properties, one per line, each preceded
+    /// by `docs(name)`. WGSL lays them out at the same offsets without
+    /// explicit padding.
+    pub fn wgsl_fields(&self, docs: impl Fn(&str) -> String) -> String {
+        self.fields
+            .iter()
+            .map(|field| {
+                format!(
+                    "{}  {}: {},\n",
+                    docs(&field.name),
+                    field.name,
+                    field.kind.wgsl_type()
+                )
+            })
+            .collect()
+    }
+
+    /// Packs the property values into the bytes of the property block.
+    /// `properties` must be the list the layout was built from.
+    pub fn pack(&self, properties: &[ShaderProperty]) -> Vec<u8> {
+        let mut data = vec![0u8; self.size];
+        let values = properties
+            .iter()
+            .filter_map(|p| UniformKind::of(&p.value).map(|_| &p.value));
+        for (field, value) in self.fields.iter().zip(values) {
+            let bytes = value_bytes(value);
+            data[field.offset..field.offset + bytes.len()].copy_from_slice(&bytes);
+        }
+        data
+    }
+}
+
+/// Bytes of a uniform value as laid out in the buffer.
+fn value_bytes(value: &PropertyValue) -> Vec<u8> {
+    match value {
+        PropertyValue::Float(v) => v.to_le_bytes().to_vec(),
+        PropertyValue::Vec2(v) => bytemuck::cast_slice(v).to_vec(),
+        PropertyValue::Vec3(v) => bytemuck::cast_slice(v).to_vec(),
+        PropertyValue::Vec4(v) | PropertyValue::Color(v) => bytemuck::cast_slice(v).to_vec(),
+        PropertyValue::Mat3(columns) => columns
+            .iter()
+            .flat_map(|column| {
+                let mut padded = bytemuck::cast_slice::<f32, u8>(column).to_vec();
+                padded.extend_from_slice(&[0u8; 4]);
+                padded
+            })
+            .collect(),
+        PropertyValue::Mat4(columns) => bytemuck::cast_slice(columns).to_vec(),
+        PropertyValue::Int(v) => v.to_le_bytes().to_vec(),
+        PropertyValue::Bool(v) => u32::from(*v).to_le_bytes().to_vec(),
+        PropertyValue::Texture(_) | PropertyValue::Enum { .. } | PropertyValue::Text(_) => {
+            Vec::new()
+        }
+    }
+}
+
+fn align_to(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property(name: &str, value: PropertyValue) -> ShaderProperty {
+        ShaderProperty {
+            name: name.to_string(),
+            value,
+            metadata: Default::default(),
+        }
+    }
+
+    fn mixed() -> Vec<ShaderProperty> {
+        vec![
+            property("f", PropertyValue::Float(1.5)),
+            property("v3", PropertyValue::Vec3([1.0, 2.0, 3.0])),
+            property("i", PropertyValue::Int(-7)),
+            property("tex", PropertyValue::Texture(None)),
+            property("b", PropertyValue::Bool(true)),
+            property("v4", PropertyValue::Vec4([4.0, 5.0, 6.0, 7.0])),
+            property(
+                "m3",
+                PropertyValue::Mat3([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]),
+            ),
+            property("m4", PropertyValue::Mat4([[1.0; 4]; 4])),
+        ]
+    }
+
+    fn f32_at(data: &[u8], offset: usize) -> f32 {
+        f32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn std140_offsets_and_size() {
+        let layout = UniformLayout::new(&mixed());
+        let offsets: Vec<_> = layout
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.offset))
+            .collect();
+        assert_eq!(
+            offsets,
+            [
+                ("f", 0),
+                // Float then Vec3 aligns the Vec3 to 16
+                ("v3", 16),
+                // A scalar fills the Vec3's last 4 bytes
+                ("i", 28),
+                ("b", 32),
+                ("v4", 48),
+                ("m3", 64),
+                ("m4", 112),
+            ]
+        );
+        assert_eq!(layout.size, 176);
+    }
+
+    #[test]
+    fn size_is_padded_to_16() {
+        let layout = UniformLayout::new(&[property("f", PropertyValue::Float(0.0))]);
+        assert_eq!(layout.size, 16);
+        assert_eq!(UniformLayout::new(&[]).size, 0);
+    }
+
+    #[test]
+    fn pack_writes_at_the_offsets() {
+        let properties = mixed();
+        let layout = UniformLayout::new(&properties);
+        let data = layout.pack(&properties);
+        assert_eq!(data.len(), layout.size);
+        assert_eq!(f32_at(&data, 0), 1.5);
+        assert_eq!(data[4..16], [0; 12]);
+        assert_eq!(
+            [f32_at(&data, 16), f32_at(&data, 20), f32_at(&data, 24)],
+            [1.0, 2.0, 3.0]
+        );
+        assert_eq!(i32::from_le_bytes(data[28..32].try_into().unwrap()), -7);
+        assert_eq!(u32::from_le_bytes(data[32..36].try_into().unwrap()), 1);
+        assert_eq!(f32_at(&data, 48), 4.0);
+        assert_eq!(f32_at(&data, 60), 7.0);
+        // Mat3 columns are padded to 16
+        for (column, offset) in [64, 80, 96].into_iter().enumerate() {
+            let first = (column * 3 + 1) as f32;
+            assert_eq!(
+                [
+                    f32_at(&data, offset),
+                    f32_at(&data, offset + 4),
+                    f32_at(&data, offset + 8)
+                ],
+                [first, first + 1.0, first + 2.0]
+            );
+            assert_eq!(data[offset + 12..offset + 16], [0; 4]);
+        }
+        assert!((112..176).step_by(4).all(|o| f32_at(&data, o) == 1.0));
+    }
+
+    #[test]
+    fn wgsl_fields_match_the_layout() {
+        let layout = UniformLayout::new(&mixed());
+        let fields = layout.wgsl_fields(|name| format!("  // {}\n", name));
+        let expected: String = [
+            ("f", "f32"),
+            ("v3", "vec3<f32>"),
+            ("i", "i32"),
+            ("b", "u32"),
+            ("v4", "vec4<f32>"),
+            ("m3", "mat3x3<f32>"),
+            ("m4", "mat4x4<f32>"),
+        ]
+        .iter()
+        .map(|(name, ty)| format!("  // {}\n  {}: {},\n", name, name, ty))
+        .collect();
+        assert_eq!(fields, expected);
+    }
+}
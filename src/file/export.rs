@@ -5,6 +5,7 @@ use std::path::PathBuf;
 pub enum TargetFormat {
     Wgsl,
     Godot,
+    Unity,
     Generic,
 }
 
@@ -13,6 +14,7 @@ impl TargetFormat {
         match self {
             TargetFormat::Wgsl => "wgsl",
             TargetFormat::Godot => "gdshader",
+            TargetFormat::Unity => "shader",
             TargetFormat::Generic => "shader",
         }
     }
@@ -21,6 +23,7 @@ impl TargetFormat {
         match self {
             TargetFormat::Wgsl => "WGSL",
             TargetFormat::Godot => "Godot Shader",
+            TargetFormat::Unity => "Unity Shader",
             TargetFormat::Generic => "Generic Shader",
         }
     }
@@ -34,13 +37,13 @@ pub fn export_shader_dialog(
     let ext = format.extension();
     let path = rfd::FileDialog::new()
         .add_filter(format.name(), &[ext])
-        .set_file_name(&format!("{}.{}", project_name, ext))
+        .set_file_name(format!("{}.{}", project_name, ext))
         .save_file();
 
-    if let Some(path) = &path {
-        if let Err(e) = fs::write(path, shader_code) {
-            eprintln!("Failed to export shader: {}", e);
-        }
+    if let Some(path) = &path
+        && let Err(e) = fs::write(path, shader_code)
+    {
+        eprintln!("Failed to export shader: {}", e);
     }
     path
 }
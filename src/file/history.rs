@@ -0,0 +1,537 @@
+//! Undo and redo of project edits.
+//!
+//! Every change is recorded where it is made, as [`Edit`] values holding
+//! what is needed to revert it, and pushed to the [`History`] as a named
+//! step, e.g. "Move Node" or "Rename Property". Reverting an edit returns
+//! the edit that reverts it back, so undo and redo share the same code.
+//!
+//! Node edits refer to nodes by ID. Steps are reverted in reverse order and
+//! the graph hands out the ID freed last first, so a removed node that is
+//! inserted again gets its ID back.
+
+use super::UmbraProject;
+use super::project::ShaderProperty;
+use crate::graph::UmbraNode;
+use egui_snarl::{InPinId, NodeId, OutPinId, Snarl};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Number of steps that can be undone.
+const HISTORY_LIMIT: usize = 100;
+/// Edits of the same value closer together than this are merged into one
+/// step, so typing a number or dragging a slider is undone at once.
+const MERGE_WINDOW: Duration = Duration::from_secs(1);
+
+pub const UNDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+pub const REDO_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::Z,
+);
+
+/// A change made to the project.
+pub enum Edit {
+    /// A node was inserted.
+    AddNode(NodeId),
+    /// A node was removed, together with its wires.
+    RemoveNode {
+        node: NodeId,
+        pos: egui::Pos2,
+        value: UmbraNode,
+        wires: Vec<(OutPinId, InPinId)>,
+    },
+    /// Nodes were dragged, each from and to a position.
+    MoveNodes(Vec<(NodeId, egui::Pos2, egui::Pos2)>),
+    Connect(OutPinId, InPinId),
+    Disconnect(OutPinId, InPinId),
+    /// A node's values were edited or the node was replaced.
+    ChangeNode {
+        node: NodeId,
+        before: UmbraNode,
+    },
+    /// A property was inserted at an index.
+    AddProperty(usize),
+    RemoveProperty {
+        index: usize,
+        property: ShaderProperty,
+    },
+    /// A property's name, value or metadata was edited.
+    ChangeProperty {
+        index: usize,
+        before: ShaderProperty,
+    },
+    MoveProperty {
+        from: usize,
+        to: usize,
+    },
+    /// The project was replaced, e.g. by opening another.
+    ReplaceProject(Box<UmbraProject>),
+}
+
+impl Edit {
+    /// Removes a node and returns the edit recording it.
+    pub fn remove_node(snarl: &mut Snarl<UmbraNode>, node: NodeId) -> Option<Self> {
+        let pos = snarl.get_node_info(node)?.pos;
+        let wires = snarl
+            .wires()
+            .filter(|(from, to)| from.node == node || to.node == node)
+            .collect();
+        let value = snarl.remove_node(node);
+        Some(Edit::RemoveNode {
+            node,
+            pos,
+            value,
+            wires,
+        })
+    }
+
+    /// Name of a step made of this edit.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Edit::AddNode(_) => "Add Node",
+            Edit::RemoveNode { .. } => "Delete Node",
+            Edit::MoveNodes(_) => "Move Node",
+            Edit::Connect(..) => "Connect",
+            Edit::Disconnect(..) => "Disconnect",
+            Edit::ChangeNode { .. } => "Edit Value",
+            Edit::AddProperty(_) => "Add Property",
+            Edit::RemoveProperty { .. } => "Delete Property",
+            Edit::ChangeProperty { .. } => "Edit Property",
+            Edit::MoveProperty { .. } => "Reorder Properties",
+            Edit::ReplaceProject(_) => "Open Project",
+        }
+    }
+
+    /// Reverts the edit and returns the edit reverting that. `None` if the
+    /// project no longer holds what the edit refers to.
+    fn revert(self, project: &mut UmbraProject) -> Option<Edit> {
+        let snarl = &mut project.graph.snarl;
+        let properties = &mut project.properties;
+        let reverted = match self {
+            Edit::AddNode(node) => Edit::remove_node(snarl, node)?,
+            Edit::RemoveNode {
+                node,
+                pos,
+                value,
+                wires,
+            } => {
+                let restored = snarl.insert_node(pos, value);
+                if restored != node {
+                    log::warn!("Node {:?} was restored as {:?}", node, restored);
+                }
+                let rebind = |id: NodeId| if id == node { restored } else { id };
+                for (from, to) in wires {
+                    let from = OutPinId {
+                        node: rebind(from.node),
+                        ..from
+                    };
+                    let to = InPinId {
+                        node: rebind(to.node),
+                        ..to
+                    };
+                    if snarl.get_node(from.node).is_some() && snarl.get_node(to.node).is_some() {
+                        snarl.connect(from, to);
+                    }
+                }
+                Edit::AddNode(restored)
+            }
+            Edit::MoveNodes(moves) => {
+                for &(node, from, _) in &moves {
+                    if let Some(info) = snarl.get_node_info_mut(node) {
+                        info.pos = from;
+                    }
+                }
+                Edit::MoveNodes(
+                    moves
+                        .into_iter()
+                        .map(|(n, from, to)| (n, to, from))
+                        .collect(),
+                )
+            }
+            Edit::Connect(from, to) => {
+                snarl.get_node(from.node)?;
+                snarl.get_node(to.node)?;
+                snarl.disconnect(from, to);
+                Edit::Disconnect(from, to)
+            }
+            Edit::Disconnect(from, to) => {
+                snarl.get_node(from.node)?;
+                snarl.get_node(to.node)?;
+                snarl.connect(from, to);
+                Edit::Connect(from, to)
+            }
+            Edit::ChangeNode { node, before } => {
+                let after = std::mem::replace(snarl.get_node_mut(node)?, before);
+                Edit::ChangeNode {
+                    node,
+                    before: after,
+                }
+            }
+            Edit::AddProperty(index) => {
+                if index >= properties.len() {
+                    return None;
+                }
+                let property = properties.remove(index);
+                Edit::RemoveProperty { index, property }
+            }
+            Edit::RemoveProperty { index, property } => {
+                let index = index.min(properties.len());
+                properties.insert(index, property);
+                Edit::AddProperty(index)
+            }
+            Edit::ChangeProperty { index, before } => {
+                let after = std::mem::replace(properties.get_mut(index)?, before);
+                Edit::ChangeProperty {
+                    index,
+                    before: after,
+                }
+            }
+            Edit::MoveProperty { from, to } => {
+                if from >= properties.len() || to >= properties.len() {
+                    return None;
+                }
+                let property = properties.remove(to);
+                properties.insert(from, property);
+                Edit::MoveProperty { from: to, to: from }
+            }
+            Edit::ReplaceProject(before) => {
+                let after = std::mem::replace(project, *before);
+                Edit::ReplaceProject(Box::new(after))
+            }
+        };
+        Some(reverted)
+    }
+
+    /// Returns `true` if a later edit of the same value can be merged into
+    /// this one.
+    fn merges_with(&self, later: &Edit) -> bool {
+        match (self, later) {
+            (Edit::ChangeNode { node, .. }, Edit::ChangeNode { node: later, .. }) => node == later,
+            (Edit::ChangeProperty { index, .. }, Edit::ChangeProperty { index: later, .. }) => {
+                index == later
+            }
+            _ => false,
+        }
+    }
+}
+
+struct Step {
+    name: &'static str,
+    /// Edits in the order they were made: on the undo stack the edits made
+    /// by the step, on the redo stack the edits made by undoing it.
+    edits: Vec<Edit>,
+    /// When the step was last pushed to; `None` once undone or redone, as
+    /// later edits are not merged into those.
+    time: Option<Instant>,
+}
+
+impl Step {
+    /// Reverts the step and returns the step reverting that.
+    fn revert(self, project: &mut UmbraProject) -> Step {
+        let edits = self
+            .edits
+            .into_iter()
+            .rev()
+            .filter_map(|edit| edit.revert(project))
+            .collect();
+        Step {
+            name: self.name,
+            edits,
+            time: None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: VecDeque<Step>,
+    redo: Vec<Step>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name of the step undo reverts, if any.
+    pub fn undo_name(&self) -> Option<&'static str> {
+        self.undo.back().map(|s| s.name)
+    }
+
+    /// Name of the step redo applies again, if any.
+    pub fn redo_name(&self) -> Option<&'static str> {
+        self.redo.last().map(|s| s.name)
+    }
+
+    /// Records edits just made to the project as one step. A single value
+    /// edit merges into the previous step if that edited the same value
+    /// within [`MERGE_WINDOW`].
+    pub fn push(&mut self, name: &'static str, edits: Vec<Edit>) {
+        if edits.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        let now = Instant::now();
+        if let Some(last) = self.undo.back_mut()
+            && let ([previous], [edit]) = (&last.edits[..], &edits[..])
+            && last.name == name
+            && previous.merges_with(edit)
+            && last
+                .time
+                .is_some_and(|time| now.duration_since(time) < MERGE_WINDOW)
+        {
+            // Keep the value before the first edit
+            last.time = Some(now);
+            return;
+        }
+        self.undo.push_back(Step {
+            name,
+            edits,
+            time: Some(now),
+        });
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Reverts the last step.
+    pub fn undo(&mut self, project: &mut UmbraProject) {
+        if let Some(step) = self.undo.pop_back() {
+            self.redo.push(step.revert(project));
+        }
+    }
+
+    /// Applies the last undone step again.
+    pub fn redo(&mut self, project: &mut UmbraProject) {
+        if let Some(step) = self.redo.pop() {
+            self.undo.push_back(step.revert(project));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::PropertyValue;
+    use crate::nodes::registry::NodeRole;
+    use std::collections::HashSet;
+
+    fn wires(project: &UmbraProject) -> HashSet<(OutPinId, InPinId)> {
+        project.graph.snarl.wires().collect()
+    }
+
+    /// Project with a Color node connected to the surface output's first
+    /// input, and the wire between them.
+    fn project_with_color() -> (UmbraProject, NodeId, (OutPinId, InPinId)) {
+        crate::nodes::register_builtins();
+        let mut project = UmbraProject::new();
+        let snarl = &mut project.graph.snarl;
+        let (output, _) = snarl
+            .node_ids()
+            .find(|(_, node)| node.role() == NodeRole::SurfaceOutput)
+            .unwrap();
+        let node = snarl.insert_node(egui::pos2(0.0, 0.0), UmbraNode::new("umbra.input.color"));
+        let wire = (
+            OutPinId { node, output: 0 },
+            InPinId {
+                node: output,
+                input: 0,
+            },
+        );
+        snarl.connect(wire.0, wire.1);
+        (project, node, wire)
+    }
+
+    #[test]
+    fn removed_nodes_come_back_with_their_wires() {
+        let (mut project, node, wire) = project_with_color();
+        let mut history = History::new();
+        let edit = Edit::remove_node(&mut project.graph.snarl, node).unwrap();
+        history.push(edit.name(), vec![edit]);
+        assert!(project.graph.snarl.get_node(node).is_none());
+        assert!(wires(&project).is_empty());
+
+        history.undo(&mut project);
+        assert!(project.graph.snarl.get_node(node).is_some());
+        assert_eq!(wires(&project), HashSet::from([wire]));
+        assert_eq!(history.redo_name(), Some("Delete Node"));
+
+        history.redo(&mut project);
+        assert!(project.graph.snarl.get_node(node).is_none());
+        assert!(wires(&project).is_empty());
+    }
+
+    #[test]
+    fn graph_edits_round_trip() {
+        let (mut project, node, wire) = project_with_color();
+        let mut history = History::new();
+        project.graph.snarl.disconnect(wire.0, wire.1);
+        history.push("Disconnect", vec![Edit::Disconnect(wire.0, wire.1)]);
+        let to = egui::pos2(40.0, 20.0);
+        project.graph.snarl.get_node_info_mut(node).unwrap().pos = to;
+        history.push(
+            "Move Node",
+            vec![Edit::MoveNodes(vec![(node, egui::pos2(0.0, 0.0), to)])],
+        );
+        let added = project
+            .graph
+            .snarl
+            .insert_node(egui::pos2(0.0, 0.0), UmbraNode::new("umbra.input.color"));
+        history.push("Add Node", vec![Edit::AddNode(added)]);
+
+        history.undo(&mut project);
+        assert!(project.graph.snarl.get_node(added).is_none());
+        history.undo(&mut project);
+        let pos = project.graph.snarl.get_node_info(node).unwrap().pos;
+        assert_eq!(pos, egui::pos2(0.0, 0.0));
+        history.undo(&mut project);
+        assert_eq!(wires(&project), HashSet::from([wire]));
+        assert_eq!(history.undo_name(), None);
+
+        history.redo(&mut project);
+        history.redo(&mut project);
+        history.redo(&mut project);
+        assert!(wires(&project).is_empty());
+        assert_eq!(project.graph.snarl.get_node_info(node).unwrap().pos, to);
+        assert!(project.graph.snarl.get_node(added).is_some());
+        assert_eq!(history.redo_name(), None);
+    }
+
+    #[test]
+    fn property_edits_round_trip() {
+        crate::nodes::register_builtins();
+        let mut project = UmbraProject::new();
+        let mut history = History::new();
+        let names = |project: &UmbraProject| {
+            project
+                .properties
+                .iter()
+                .map(|p| p.name.clone())
+                .collect::<Vec<_>>()
+        };
+        let edit = project.add_property("tint", PropertyValue::Float(0.0));
+        history.push("Add Property", vec![edit]);
+        let edit = project.add_property("gloss", PropertyValue::Float(0.0));
+        history.push("Add Property", vec![edit]);
+        let node = project
+            .graph
+            .snarl
+            .insert_node(egui::pos2(0.0, 0.0), UmbraNode::property("tint"));
+        let edits = project.rename_property("tint", "color");
+        history.push("Rename Property", edits);
+        let edit = project.move_property(0, 1).unwrap();
+        history.push("Reorder Properties", vec![edit]);
+        let edit = project.remove_property(0).unwrap();
+        history.push("Delete Property", vec![edit]);
+        assert_eq!(names(&project), ["color"]);
+
+        history.undo(&mut project);
+        assert_eq!(names(&project), ["gloss", "color"]);
+        history.undo(&mut project);
+        assert_eq!(names(&project), ["color", "gloss"]);
+        history.undo(&mut project);
+        assert_eq!(names(&project), ["tint", "gloss"]);
+        assert_eq!(project.graph.snarl[node].property_name(), Some("tint"));
+        history.undo(&mut project);
+        history.undo(&mut project);
+        assert!(project.properties.is_empty());
+
+        for _ in 0..5 {
+            history.redo(&mut project);
+        }
+        assert_eq!(names(&project), ["color"]);
+        assert_eq!(project.graph.snarl[node].property_name(), Some("color"));
+    }
+
+    #[test]
+    fn replaced_projects_are_restored() {
+        let (mut project, node, _) = project_with_color();
+        let mut history = History::new();
+        let before = std::mem::replace(&mut project, UmbraProject::new());
+        history.push("Open Project", vec![Edit::ReplaceProject(Box::new(before))]);
+        assert!(project.graph.snarl.get_node(node).is_none());
+
+        history.undo(&mut project);
+        assert!(project.graph.snarl.get_node(node).is_some());
+        history.redo(&mut project);
+        assert!(project.graph.snarl.get_node(node).is_none());
+    }
+
+    /// Sets a Float property to `value`, recording the edit.
+    fn edit_value(project: &mut UmbraProject, history: &mut History, index: usize, value: f32) {
+        let before = project.properties[index].clone();
+        project.properties[index].value = PropertyValue::Float(value);
+        history.push(
+            "Edit Property",
+            vec![Edit::ChangeProperty { index, before }],
+        );
+    }
+
+    #[test]
+    fn edits_of_one_value_merge() {
+        let mut project = UmbraProject::new();
+        let mut history = History::new();
+        project.add_property("a", PropertyValue::Float(0.0));
+        project.add_property("b", PropertyValue::Float(0.0));
+        for value in [1.0, 2.0, 3.0] {
+            edit_value(&mut project, &mut history, 0, value);
+        }
+        edit_value(&mut project, &mut history, 1, 4.0);
+
+        history.undo(&mut project);
+        assert_eq!(project.properties[1].value, PropertyValue::Float(0.0));
+        assert_eq!(project.properties[0].value, PropertyValue::Float(3.0));
+        history.undo(&mut project);
+        assert_eq!(project.properties[0].value, PropertyValue::Float(0.0));
+        assert_eq!(history.undo_name(), None);
+    }
+
+    #[test]
+    fn undone_steps_are_not_merged_into() {
+        let mut project = UmbraProject::new();
+        let mut history = History::new();
+        project.add_property("a", PropertyValue::Float(0.0));
+        edit_value(&mut project, &mut history, 0, 1.0);
+        edit_value(&mut project, &mut history, 0, 2.0);
+        history.undo(&mut project);
+        history.redo(&mut project);
+        edit_value(&mut project, &mut history, 0, 3.0);
+
+        history.undo(&mut project);
+        assert_eq!(project.properties[0].value, PropertyValue::Float(2.0));
+        history.undo(&mut project);
+        assert_eq!(project.properties[0].value, PropertyValue::Float(0.0));
+    }
+
+    #[test]
+    fn pushing_clears_redo() {
+        let mut project = UmbraProject::new();
+        let mut history = History::new();
+        let edit = project.add_property("a", PropertyValue::Float(0.0));
+        history.push("Add Property", vec![edit]);
+        history.undo(&mut project);
+        assert_eq!(history.redo_name(), Some("Add Property"));
+        let edit = project.add_property("b", PropertyValue::Float(0.0));
+        history.push("Add Property", vec![edit]);
+        assert_eq!(history.redo_name(), None);
+        history.push("Nothing", Vec::new());
+        assert_eq!(history.undo_name(), Some("Add Property"));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut project = UmbraProject::new();
+        let mut history = History::new();
+        for i in 0..HISTORY_LIMIT + 10 {
+            let edit = project.add_property(&format!("p{i}"), PropertyValue::Float(0.0));
+            history.push("Add Property", vec![edit]);
+        }
+        let mut undone = 0;
+        while history.undo_name().is_some() {
+            history.undo(&mut project);
+            undone += 1;
+        }
+        assert_eq!(undone, HISTORY_LIMIT);
+        assert_eq!(project.properties.len(), 10);
+    }
+}
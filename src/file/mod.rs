@@ -1,4 +1,6 @@
 pub mod export;
+pub mod history;
 pub mod project;
 
+pub use history::History;
 pub use project::UmbraProject;
@@ -1,11 +1,53 @@
-use crate::common::PropertyValue;
-use crate::graph::GraphEditor;
+use super::history::Edit;
+use crate::common::{PropertyValue, sanitize_identifier, validate_identifier};
+use crate::graph::{GraphAction, GraphEditor, UmbraNode};
+use egui_snarl::{InPinId, NodeId, OutPinId};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ShaderProperty {
     pub name: String,
     pub value: PropertyValue,
+    #[serde(default)]
+    pub metadata: PropertyMetadata,
+}
+
+/// Optional presentation details of a property, used by the Properties
+/// panel and carried into the exports as hints and documentation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PropertyMetadata {
+    /// Name shown instead of the identifier; empty for none.
+    pub display_name: String,
+    pub description: String,
+    /// Range of Float and Int values.
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    pub step: Option<f32>,
+    /// Edit with a slider instead of a drag value; needs a range.
+    pub slider: bool,
+    /// Allow Color components above 1.
+    pub hdr: bool,
+    /// Group the property is listed under; empty for none.
+    pub group: String,
+}
+
+impl PropertyMetadata {
+    /// The value range, if both bounds are set.
+    pub fn range(&self) -> Option<(f32, f32)> {
+        self.min.zip(self.max)
+    }
+}
+
+impl ShaderProperty {
+    /// Name shown to users: the display name, or the identifier if unset.
+    pub fn label(&self) -> &str {
+        if self.metadata.display_name.is_empty() {
+            &self.name
+        } else {
+            &self.metadata.display_name
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -13,6 +55,9 @@ pub struct UmbraProject {
     pub name: String,
     pub properties: Vec<ShaderProperty>,
     pub graph: GraphEditor,
+    /// File the project was loaded from or last saved to.
+    #[serde(skip)]
+    pub path: Option<std::path::PathBuf>,
 }
 
 impl UmbraProject {
@@ -21,14 +66,222 @@ impl UmbraProject {
             name: "Untitled Project".to_string(),
             properties: Vec::new(),
             graph: GraphEditor::new(),
+            path: None,
         }
     }
 
-    pub fn add_property(&mut self, name: &str, value: PropertyValue) {
+    /// Appends a property, numbering its name if it is already taken.
+    pub fn add_property(&mut self, name: &str, value: PropertyValue) -> Edit {
+        let name = self.unique_property_name(name);
         self.properties.push(ShaderProperty {
-            name: name.to_string(),
+            name,
             value,
+            metadata: PropertyMetadata::default(),
         });
+        Edit::AddProperty(self.properties.len() - 1)
+    }
+
+    /// Returns `base`, or `base_1`, `base_2`... if a property already has
+    /// that name.
+    pub fn unique_property_name(&self, base: &str) -> String {
+        let taken = |name: &str| self.properties.iter().any(|p| p.name == name);
+        if !taken(base) {
+            return base.to_string();
+        }
+        (1..)
+            .map(|i| format!("{}_{}", base, i))
+            .find(|name| !taken(name))
+            .unwrap_or_default()
+    }
+
+    /// Checks a new name for the property at `index`: it must be a valid
+    /// identifier in every target and not used by another property.
+    pub fn check_property_name(&self, index: usize, name: &str) -> Result<(), String> {
+        validate_identifier(name)?;
+        let taken = self
+            .properties
+            .iter()
+            .enumerate()
+            .any(|(i, p)| i != index && p.name == name);
+        if taken {
+            return Err(format!("A property named '{}' already exists", name));
+        }
+        Ok(())
+    }
+
+    /// Applies an edit requested from the graph and returns the edits made.
+    pub fn apply_graph_action(&mut self, action: GraphAction) -> Vec<Edit> {
+        match action {
+            GraphAction::PromoteNode(node_id) => self.promote_node(node_id),
+            GraphAction::PromoteInput(pin) => self.promote_input(pin),
+            GraphAction::InlineProperty(node_id) => self.inline_property(node_id),
+        }
+    }
+
+    /// Moves a constant node's value into a new property and turns the node
+    /// into a Property node bound to it, keeping its connections.
+    fn promote_node(&mut self, node_id: NodeId) -> Vec<Edit> {
+        let Some(node) = self.graph.snarl.get_node(node_id) else {
+            return Vec::new();
+        };
+        let Some(value) = node.constant_value() else {
+            return Vec::new();
+        };
+        let name = self.new_property_name(&format!("{}_value", node.name()));
+        let add = self.add_property(&name, value);
+        let before = std::mem::replace(&mut self.graph.snarl[node_id], UmbraNode::property(&name));
+        vec![
+            add,
+            Edit::ChangeNode {
+                node: node_id,
+                before,
+            },
+        ]
+    }
+
+    /// Creates a property from an unconnected input's default and connects
+    /// a Property node bound to it.
+    fn promote_input(&mut self, pin: InPinId) -> Vec<Edit> {
+        let Some(node) = self.graph.snarl.get_node(pin.node) else {
+            return Vec::new();
+        };
+        let Some(input) = node
+            .implementation()
+            .and_then(|n| n.inputs().into_iter().nth(pin.input))
+        else {
+            return Vec::new();
+        };
+        let Some(value) = node.input_value(&input).or_else(|| input.property_value()) else {
+            return Vec::new();
+        };
+        let name = self.new_property_name(&format!("{}_{}", node.name(), input.name));
+        let add = self.add_property(&name, value);
+
+        let node_pos = self
+            .graph
+            .snarl
+            .get_node_info(pin.node)
+            .map(|n| n.pos)
+            .unwrap_or_default();
+        let pos = node_pos + egui::vec2(-200.0, pin.input as f32 * 24.0);
+        let property_node = self
+            .graph
+            .snarl
+            .insert_node(pos, UmbraNode::property(&name));
+        let from = OutPinId {
+            node: property_node,
+            output: 0,
+        };
+        self.graph.snarl.connect(from, pin);
+        vec![add, Edit::AddNode(property_node), Edit::Connect(from, pin)]
+    }
+
+    /// Turns a Property node into a constant node holding the property's
+    /// current value. The property itself is kept.
+    fn inline_property(&mut self, node_id: NodeId) -> Vec<Edit> {
+        let Some(name) = self
+            .graph
+            .snarl
+            .get_node(node_id)
+            .and_then(|n| n.property_name())
+        else {
+            return Vec::new();
+        };
+        let constant = self
+            .properties
+            .iter()
+            .find(|p| p.name == name)
+            .and_then(|p| UmbraNode::from_constant(&p.value));
+        let Some(constant) = constant else {
+            return Vec::new();
+        };
+        let before = std::mem::replace(&mut self.graph.snarl[node_id], constant);
+        vec![Edit::ChangeNode {
+            node: node_id,
+            before,
+        }]
+    }
+
+    /// Unique property name derived from `base`, e.g. a node or input name.
+    fn new_property_name(&self, base: &str) -> String {
+        let name = sanitize_identifier(&base.to_lowercase());
+        let name = if validate_identifier(&name).is_ok() {
+            name
+        } else {
+            format!("p_{}", name)
+        };
+        self.unique_property_name(&name)
+    }
+
+    /// Property indices by group: ungrouped properties first, then each
+    /// group in order of first appearance.
+    pub fn property_groups(&self) -> Vec<(&str, Vec<usize>)> {
+        let mut groups: Vec<(&str, Vec<usize>)> = vec![("", Vec::new())];
+        for (index, property) in self.properties.iter().enumerate() {
+            let group = property.metadata.group.as_str();
+            match groups.iter_mut().find(|(name, _)| *name == group) {
+                Some((_, indices)) => indices.push(index),
+                None => groups.push((group, vec![index])),
+            }
+        }
+        groups.retain(|(_, indices)| !indices.is_empty());
+        groups
+    }
+
+    /// Renames a property and rebinds the Property nodes that read it.
+    pub fn rename_property(&mut self, old_name: &str, new_name: &str) -> Vec<Edit> {
+        let mut edits = Vec::new();
+        for (index, property) in self.properties.iter_mut().enumerate() {
+            if property.name == old_name {
+                edits.push(Edit::ChangeProperty {
+                    index,
+                    before: property.clone(),
+                });
+                property.name = new_name.to_string();
+            }
+        }
+        for (node_id, node) in self.graph.snarl.nodes_ids_mut() {
+            if node.property_name() == Some(old_name) {
+                let before = std::mem::replace(node, UmbraNode::property(new_name));
+                edits.push(Edit::ChangeNode {
+                    node: node_id,
+                    before,
+                });
+            }
+        }
+        edits
+    }
+
+    /// Removes a property. Property nodes reading it stay in the graph and
+    /// show a warning until they are bound to another property.
+    pub fn remove_property(&mut self, index: usize) -> Option<Edit> {
+        if index >= self.properties.len() {
+            return None;
+        }
+        let property = self.properties.remove(index);
+        Some(Edit::RemoveProperty { index, property })
+    }
+
+    /// Inserts a copy of a property after it, under a new name.
+    pub fn duplicate_property(&mut self, index: usize) -> Option<Edit> {
+        let property = self.properties.get(index)?;
+        let copy = ShaderProperty {
+            name: self.unique_property_name(&property.name),
+            value: property.value.clone(),
+            metadata: property.metadata.clone(),
+        };
+        self.properties.insert(index + 1, copy);
+        Some(Edit::AddProperty(index + 1))
+    }
+
+    /// Moves a property to another position in the list.
+    pub fn move_property(&mut self, from: usize, to: usize) -> Option<Edit> {
+        if from >= self.properties.len() || to >= self.properties.len() {
+            return None;
+        }
+        let property = self.properties.remove(from);
+        self.properties.insert(to, property);
+        Some(Edit::MoveProperty { from, to })
     }
 
     pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
@@ -39,20 +292,60 @@ impl UmbraProject {
 
     pub fn load(path: &std::path::Path) -> std::io::Result<Self> {
         let data = std::fs::read_to_string(path)?;
-        let project: Self = serde_json::from_str(&data)?;
+        let mut project: Self = serde_json::from_str(&data)?;
+        project.fix_property_names();
+        project.path = Some(path.to_path_buf());
         Ok(project)
     }
 
+    /// Renames loaded properties whose names are not valid identifiers or
+    /// are used by an earlier property, as projects written by hand or by
+    /// older versions may break either rule. Property nodes follow a renamed
+    /// property; nodes reading a duplicate name keep reading the first.
+    fn fix_property_names(&mut self) {
+        let original: Vec<String> = self.properties.iter().map(|p| p.name.clone()).collect();
+        for index in 0..original.len() {
+            let name = &original[index];
+            let duplicate = original[..index].contains(name);
+            if !duplicate && validate_identifier(name).is_ok() {
+                continue;
+            }
+            let mut base = sanitize_identifier(name);
+            if validate_identifier(&base).is_err() {
+                base = format!("p_{}", base);
+            }
+            // Names later properties keep are taken too
+            let taken = |candidate: &str| {
+                original.iter().any(|n| n == candidate)
+                    || self.properties[..index].iter().any(|p| p.name == candidate)
+            };
+            let new_name = if taken(&base) {
+                (1..)
+                    .map(|i| format!("{}_{}", base, i))
+                    .find(|name| !taken(name))
+                    .unwrap_or_default()
+            } else {
+                base
+            };
+            log::warn!("Renamed property '{}' to '{}'", name, new_name);
+            if duplicate {
+                self.properties[index].name = new_name;
+            } else {
+                self.rename_property(name, &new_name);
+            }
+        }
+    }
+
     pub fn save_as_dialog(&self) -> Option<std::path::PathBuf> {
         let path = rfd::FileDialog::new()
             .add_filter("Umbra Project", &["umbra"])
-            .set_file_name(&format!("{}.umbra", self.name))
+            .set_file_name(format!("{}.umbra", self.name))
             .save_file();
 
-        if let Some(path) = &path {
-            if let Err(e) = self.save(path) {
-                eprintln!("Failed to save project: {}", e);
-            }
+        if let Some(path) = &path
+            && let Err(e) = self.save(path)
+        {
+            eprintln!("Failed to save project: {}", e);
         }
         path
     }
@@ -71,3 +364,158 @@ impl UmbraProject {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project_with(names: &[&str]) -> UmbraProject {
+        let mut project = UmbraProject::new();
+        for name in names {
+            project.properties.push(ShaderProperty {
+                name: name.to_string(),
+                value: PropertyValue::Float(0.0),
+                metadata: PropertyMetadata::default(),
+            });
+        }
+        project
+    }
+
+    fn names(project: &UmbraProject) -> Vec<&str> {
+        project.properties.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[test]
+    fn loaded_names_become_valid_and_unique() {
+        crate::nodes::register_builtins();
+        let mut project = project_with(&["tint", "sin", "tint", "base color", "_Time", "p_sin"]);
+        let node = project
+            .graph
+            .snarl
+            .insert_node(egui::pos2(0.0, 0.0), UmbraNode::property("sin"));
+        project.fix_property_names();
+        assert_eq!(
+            names(&project),
+            [
+                "tint",
+                "p_sin_1",
+                "tint_1",
+                "base_color",
+                "p__Time",
+                "p_sin"
+            ]
+        );
+        assert_eq!(project.graph.snarl[node].property_name(), Some("p_sin_1"));
+    }
+
+    #[test]
+    fn valid_names_are_kept() {
+        let mut project = project_with(&["tint", "speed"]);
+        project.fix_property_names();
+        assert_eq!(names(&project), ["tint", "speed"]);
+    }
+
+    #[test]
+    fn renames_rebind_property_nodes() {
+        crate::nodes::register_builtins();
+        let mut project = project_with(&["speed", "tint"]);
+        let snarl = &mut project.graph.snarl;
+        let speed = snarl.insert_node(egui::pos2(0.0, 0.0), UmbraNode::property("speed"));
+        let other = snarl.insert_node(egui::pos2(0.0, 0.0), UmbraNode::property("speed"));
+        let tint = snarl.insert_node(egui::pos2(0.0, 0.0), UmbraNode::property("tint"));
+        let add = snarl.insert_node(egui::pos2(0.0, 0.0), UmbraNode::new("umbra.math.add"));
+        let to_add = InPinId {
+            node: add,
+            input: 0,
+        };
+        snarl.connect(
+            OutPinId {
+                node: speed,
+                output: 0,
+            },
+            to_add,
+        );
+
+        let edits = project.rename_property("speed", "rate");
+        assert_eq!(names(&project), ["rate", "tint"]);
+        // The property and both nodes reading it changed
+        assert_eq!(edits.len(), 3);
+        let snarl = &project.graph.snarl;
+        assert_eq!(snarl[speed].property_name(), Some("rate"));
+        assert_eq!(snarl[other].property_name(), Some("rate"));
+        assert_eq!(snarl[tint].property_name(), Some("tint"));
+        assert_eq!(
+            snarl.in_pin(to_add).remotes,
+            [OutPinId {
+                node: speed,
+                output: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn promoted_constants_inline_back_with_their_wires() {
+        crate::nodes::register_builtins();
+        let mut project = UmbraProject::new();
+        let snarl = &mut project.graph.snarl;
+        let color = snarl.insert_node(
+            egui::pos2(0.0, 0.0),
+            UmbraNode::from_constant(&PropertyValue::Color([1.0, 0.5, 0.0, 1.0])).unwrap(),
+        );
+        let add = snarl.insert_node(egui::pos2(0.0, 0.0), UmbraNode::new("umbra.math.add"));
+        let from = OutPinId {
+            node: color,
+            output: 0,
+        };
+        let to = InPinId {
+            node: add,
+            input: 0,
+        };
+        snarl.connect(from, to);
+
+        project.apply_graph_action(GraphAction::PromoteNode(color));
+        assert_eq!(names(&project), ["color_value"]);
+        assert_eq!(
+            project.properties[0].value,
+            PropertyValue::Color([1.0, 0.5, 0.0, 1.0])
+        );
+        let snarl = &project.graph.snarl;
+        assert_eq!(snarl[color].property_name(), Some("color_value"));
+        assert_eq!(snarl.in_pin(to).remotes, [from]);
+
+        project.apply_graph_action(GraphAction::InlineProperty(color));
+        let snarl = &project.graph.snarl;
+        assert_eq!(snarl[color].property_name(), None);
+        assert_eq!(
+            snarl[color].constant_value(),
+            Some(PropertyValue::Color([1.0, 0.5, 0.0, 1.0]))
+        );
+        assert_eq!(snarl.in_pin(to).remotes, [from]);
+        // Inlining keeps the property for other nodes
+        assert_eq!(names(&project), ["color_value"]);
+    }
+
+    #[test]
+    fn promoted_inputs_are_wired_to_a_property_node() {
+        crate::nodes::register_builtins();
+        let mut project = UmbraProject::new();
+        let mut lerp = UmbraNode::new("umbra.math.lerp");
+        lerp.input_values
+            .insert("T".to_string(), PropertyValue::Float(0.25));
+        let lerp = project.graph.snarl.insert_node(egui::pos2(0.0, 0.0), lerp);
+        let pin = InPinId {
+            node: lerp,
+            input: 2,
+        };
+
+        let edits = project.apply_graph_action(GraphAction::PromoteInput(pin));
+        assert_eq!(edits.len(), 3);
+        assert_eq!(names(&project), ["lerp_t"]);
+        assert_eq!(project.properties[0].value, PropertyValue::Float(0.25));
+        let snarl = &project.graph.snarl;
+        let [remote] = snarl.in_pin(pin).remotes[..] else {
+            panic!("T is not connected");
+        };
+        assert_eq!(snarl[remote.node].property_name(), Some("lerp_t"));
+    }
+}
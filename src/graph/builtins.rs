@@ -0,0 +1,629 @@
+//! Mapping of builtin shader values onto each target and stage.
+//!
+//! Nodes name builtins by their fragment-stage WGSL (`in.uv`,
+//! `uniforms.time`, ...) and the codegen context looks up the expression for
+//! its target and stage in this table. Library templates and default input
+//! expressions are whole WGSL expressions; they are translated with the same
+//! table, and [`translate_syntax`] then renames types and functions.
+
+use crate::file::export::TargetFormat;
+
+/// A builtin expression and its equivalent on each target and stage.
+///
+/// Every target has its own expression, ready to use without further
+/// translation. `None` is only used for the WGSL vertex stage, where the
+/// expression is left for the later, smaller entries of [`BUILTINS`] to
+/// translate piecewise.
+pub struct Builtin {
+    /// Fragment-stage WGSL expression emitted by nodes.
+    pub wgsl: &'static str,
+    pub wgsl_vertex: Option<&'static str>,
+    pub godot: Option<&'static str>,
+    pub godot_vertex: Option<&'static str>,
+    pub glsl: Option<&'static str>,
+    /// Unity surface shader: `IN` in `surf`, `v` in the vertex modifier.
+    pub hlsl: Option<&'static str>,
+    pub hlsl_vertex: Option<&'static str>,
+}
+
+/// Translation table, applied in order. Composite expressions come before
+/// the builtins they are made of.
+pub const BUILTINS: &[Builtin] = &[
+    // Screen position, normalized to [0, 1] with the origin at the top left
+    Builtin {
+        wgsl: "(in.clip_position.xy / uniforms.resolution)",
+        wgsl_vertex: Some(
+            "((uniforms.view_proj * uniforms.model * vec4<f32>(model.position, 1.0)).xy / (uniforms.view_proj * uniforms.model * vec4<f32>(model.position, 1.0)).w * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5))",
+        ),
+        godot: Some("SCREEN_UV"),
+        godot_vertex: Some(
+            "((PROJECTION_MATRIX * MODELVIEW_MATRIX * vec4(VERTEX, 1.0)).xy / (PROJECTION_MATRIX * MODELVIEW_MATRIX * vec4(VERTEX, 1.0)).w * vec2(0.5, -0.5) + vec2(0.5))",
+        ),
+        glsl: Some("(gl_FragCoord.xy / resolution)"),
+        hlsl: Some(
+            "float2(IN.screenPos.x / IN.screenPos.w, 1.0 - IN.screenPos.y / IN.screenPos.w)",
+        ),
+        hlsl_vertex: Some(
+            "(ComputeScreenPos(UnityObjectToClipPos(v.vertex)).xy / ComputeScreenPos(UnityObjectToClipPos(v.vertex)).w * float2(1.0, -1.0) + float2(0.0, 1.0))",
+        ),
+    },
+    // Pixel coordinate
+    Builtin {
+        wgsl: "in.clip_position.xy",
+        wgsl_vertex: Some(
+            "(((uniforms.view_proj * uniforms.model * vec4<f32>(model.position, 1.0)).xy / (uniforms.view_proj * uniforms.model * vec4<f32>(model.position, 1.0)).w * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5)) * uniforms.resolution)",
+        ),
+        godot: Some("FRAGCOORD.xy"),
+        godot_vertex: Some(
+            "(((PROJECTION_MATRIX * MODELVIEW_MATRIX * vec4(VERTEX, 1.0)).xy / (PROJECTION_MATRIX * MODELVIEW_MATRIX * vec4(VERTEX, 1.0)).w * vec2(0.5, -0.5) + vec2(0.5)) * VIEWPORT_SIZE)",
+        ),
+        glsl: Some("gl_FragCoord.xy"),
+        hlsl: Some(
+            "(float2(IN.screenPos.x / IN.screenPos.w, 1.0 - IN.screenPos.y / IN.screenPos.w) * _ScreenParams.xy)",
+        ),
+        hlsl_vertex: Some(
+            "((ComputeScreenPos(UnityObjectToClipPos(v.vertex)).xy / ComputeScreenPos(UnityObjectToClipPos(v.vertex)).w * float2(1.0, -1.0) + float2(0.0, 1.0)) * _ScreenParams.xy)",
+        ),
+    },
+    // Normal in view space. Unity needs `mul` for matrix products.
+    Builtin {
+        wgsl: "normalize((uniforms.view * vec4<f32>(in.world_normal, 0.0)).xyz)",
+        wgsl_vertex: None,
+        godot: Some("normalize(NORMAL)"),
+        godot_vertex: Some("normalize((MODELVIEW_MATRIX * vec4(NORMAL, 0.0)).xyz)"),
+        glsl: Some("normalize((view * vec4(v_world_normal, 0.0)).xyz)"),
+        hlsl: Some("normalize(mul((float3x3)UNITY_MATRIX_V, IN.world_normal))"),
+        hlsl_vertex: Some(
+            "normalize(mul((float3x3)UNITY_MATRIX_V, UnityObjectToWorldNormal(v.normal)))",
+        ),
+    },
+    // View direction (surface to camera) in view space
+    Builtin {
+        wgsl: "normalize((uniforms.view * vec4<f32>(uniforms.camera_position - in.world_position, 0.0)).xyz)",
+        wgsl_vertex: None,
+        godot: Some("VIEW"),
+        godot_vertex: Some(
+            "normalize((VIEW_MATRIX * vec4(CAMERA_POSITION_WORLD - (MODEL_MATRIX * vec4(VERTEX, 1.0)).xyz, 0.0)).xyz)",
+        ),
+        glsl: Some("normalize((view * vec4(camera_position - v_world_position, 0.0)).xyz)"),
+        hlsl: Some("normalize(mul((float3x3)UNITY_MATRIX_V, _WorldSpaceCameraPos - IN.worldPos))"),
+        hlsl_vertex: Some(
+            "normalize(mul((float3x3)UNITY_MATRIX_V, _WorldSpaceCameraPos - mul(unity_ObjectToWorld, v.vertex).xyz))",
+        ),
+    },
+    // View direction (surface to camera) in world space
+    Builtin {
+        wgsl: "normalize(uniforms.camera_position - in.world_position)",
+        wgsl_vertex: None,
+        godot: Some("(INV_VIEW_MATRIX * vec4(VIEW, 0.0)).xyz"),
+        godot_vertex: Some(
+            "normalize(CAMERA_POSITION_WORLD - (MODEL_MATRIX * vec4(VERTEX, 1.0)).xyz)",
+        ),
+        glsl: Some("normalize(camera_position - v_world_position)"),
+        hlsl: Some("normalize(_WorldSpaceCameraPos - IN.worldPos)"),
+        hlsl_vertex: Some(
+            "normalize(_WorldSpaceCameraPos - mul(unity_ObjectToWorld, v.vertex).xyz)",
+        ),
+    },
+    Builtin {
+        wgsl: "in.uv",
+        wgsl_vertex: Some("model.uv"),
+        godot: Some("UV"),
+        godot_vertex: Some("UV"),
+        glsl: Some("v_uv"),
+        hlsl: Some("IN.texcoord0"),
+        hlsl_vertex: Some("v.texcoord.xy"),
+    },
+    Builtin {
+        wgsl: "in.world_position",
+        wgsl_vertex: Some("(uniforms.model * vec4<f32>(model.position, 1.0)).xyz"),
+        godot: Some("(INV_VIEW_MATRIX * vec4(VERTEX, 1.0)).xyz"),
+        godot_vertex: Some("(MODEL_MATRIX * vec4(VERTEX, 1.0)).xyz"),
+        glsl: Some("v_world_position"),
+        hlsl: Some("IN.worldPos"),
+        hlsl_vertex: Some("mul(unity_ObjectToWorld, v.vertex).xyz"),
+    },
+    Builtin {
+        wgsl: "in.object_normal",
+        wgsl_vertex: Some("model.normal"),
+        godot: Some("(inverse(MODEL_MATRIX) * INV_VIEW_MATRIX * vec4(NORMAL, 0.0)).xyz"),
+        godot_vertex: Some("NORMAL"),
+        glsl: Some("v_object_normal"),
+        hlsl: Some("IN.object_normal"),
+        hlsl_vertex: Some("v.normal"),
+    },
+    Builtin {
+        wgsl: "in.world_normal",
+        wgsl_vertex: Some("(vec4<f32>(model.normal, 0.0) * uniforms.inverse_model).xyz"),
+        godot: Some("(INV_VIEW_MATRIX * vec4(NORMAL, 0.0)).xyz"),
+        godot_vertex: Some("(MODEL_MATRIX * vec4(NORMAL, 0.0)).xyz"),
+        glsl: Some("v_world_normal"),
+        hlsl: Some("IN.world_normal"),
+        hlsl_vertex: Some("UnityObjectToWorldNormal(v.normal)"),
+    },
+    Builtin {
+        wgsl: "in.world_tangent",
+        wgsl_vertex: Some("(uniforms.model * vec4<f32>(model.tangent.xyz, 0.0)).xyz"),
+        godot: Some("(INV_VIEW_MATRIX * vec4(TANGENT, 0.0)).xyz"),
+        godot_vertex: Some("(MODEL_MATRIX * vec4(TANGENT, 0.0)).xyz"),
+        glsl: Some("v_world_tangent"),
+        hlsl: Some("IN.world_tangent"),
+        hlsl_vertex: Some("UnityObjectToWorldDir(v.tangent.xyz)"),
+    },
+    Builtin {
+        wgsl: "in.world_bitangent",
+        wgsl_vertex: Some(
+            "(cross((vec4<f32>(model.normal, 0.0) * uniforms.inverse_model).xyz, (uniforms.model * vec4<f32>(model.tangent.xyz, 0.0)).xyz) * model.tangent.w)",
+        ),
+        godot: Some("(INV_VIEW_MATRIX * vec4(BINORMAL, 0.0)).xyz"),
+        godot_vertex: Some("(MODEL_MATRIX * vec4(BINORMAL, 0.0)).xyz"),
+        glsl: Some("v_world_bitangent"),
+        hlsl: Some("IN.world_bitangent"),
+        hlsl_vertex: Some(
+            "(cross(UnityObjectToWorldNormal(v.normal), UnityObjectToWorldDir(v.tangent.xyz)) * v.tangent.w * unity_WorldTransformParams.w)",
+        ),
+    },
+    Builtin {
+        wgsl: "uniforms.view_proj",
+        wgsl_vertex: None,
+        godot: Some("(PROJECTION_MATRIX * VIEW_MATRIX)"),
+        godot_vertex: Some("(PROJECTION_MATRIX * VIEW_MATRIX)"),
+        glsl: Some("view_proj"),
+        hlsl: Some("UNITY_MATRIX_VP"),
+        hlsl_vertex: Some("UNITY_MATRIX_VP"),
+    },
+    Builtin {
+        wgsl: "uniforms.inverse_view",
+        wgsl_vertex: None,
+        godot: Some("INV_VIEW_MATRIX"),
+        godot_vertex: Some("INV_VIEW_MATRIX"),
+        glsl: Some("inverse_view"),
+        hlsl: Some("UNITY_MATRIX_I_V"),
+        hlsl_vertex: Some("UNITY_MATRIX_I_V"),
+    },
+    Builtin {
+        wgsl: "uniforms.model",
+        wgsl_vertex: None,
+        godot: Some("MODEL_MATRIX"),
+        godot_vertex: Some("MODEL_MATRIX"),
+        glsl: Some("model"),
+        hlsl: Some("unity_ObjectToWorld"),
+        hlsl_vertex: Some("unity_ObjectToWorld"),
+    },
+    Builtin {
+        wgsl: "uniforms.inverse_model",
+        wgsl_vertex: None,
+        godot: Some("inverse(MODEL_MATRIX)"),
+        godot_vertex: Some("inverse(MODEL_MATRIX)"),
+        glsl: Some("inverse_model"),
+        hlsl: Some("unity_WorldToObject"),
+        hlsl_vertex: Some("unity_WorldToObject"),
+    },
+    Builtin {
+        wgsl: "uniforms.projection",
+        wgsl_vertex: None,
+        godot: Some("PROJECTION_MATRIX"),
+        godot_vertex: Some("PROJECTION_MATRIX"),
+        glsl: Some("projection"),
+        hlsl: Some("UNITY_MATRIX_P"),
+        hlsl_vertex: Some("UNITY_MATRIX_P"),
+    },
+    Builtin {
+        wgsl: "uniforms.inverse_projection",
+        wgsl_vertex: None,
+        godot: Some("INV_PROJECTION_MATRIX"),
+        godot_vertex: Some("INV_PROJECTION_MATRIX"),
+        glsl: Some("inverse_projection"),
+        hlsl: Some("unity_CameraInvProjection"),
+        hlsl_vertex: Some("unity_CameraInvProjection"),
+    },
+    Builtin {
+        wgsl: "uniforms.view",
+        wgsl_vertex: None,
+        godot: Some("VIEW_MATRIX"),
+        godot_vertex: Some("VIEW_MATRIX"),
+        glsl: Some("view"),
+        hlsl: Some("UNITY_MATRIX_V"),
+        hlsl_vertex: Some("UNITY_MATRIX_V"),
+    },
+    Builtin {
+        wgsl: "uniforms.camera_position",
+        wgsl_vertex: None,
+        godot: Some("CAMERA_POSITION_WORLD"),
+        godot_vertex: Some("CAMERA_POSITION_WORLD"),
+        glsl: Some("camera_position"),
+        hlsl: Some("_WorldSpaceCameraPos"),
+        hlsl_vertex: Some("_WorldSpaceCameraPos"),
+    },
+    Builtin {
+        wgsl: "uniforms.resolution",
+        wgsl_vertex: None,
+        godot: Some("VIEWPORT_SIZE"),
+        godot_vertex: Some("VIEWPORT_SIZE"),
+        glsl: Some("resolution"),
+        hlsl: Some("_ScreenParams.xy"),
+        hlsl_vertex: Some("_ScreenParams.xy"),
+    },
+    Builtin {
+        wgsl: "uniforms.mouse",
+        wgsl_vertex: None,
+        godot: Some("mouse"),
+        godot_vertex: Some("mouse"),
+        glsl: Some("mouse"),
+        hlsl: Some("mouse"),
+        hlsl_vertex: Some("mouse"),
+    },
+    Builtin {
+        wgsl: "uniforms.time",
+        wgsl_vertex: None,
+        godot: Some("TIME"),
+        godot_vertex: Some("TIME"),
+        glsl: Some("time"),
+        hlsl: Some("_Time.y"),
+        hlsl_vertex: Some("_Time.y"),
+    },
+    Builtin {
+        wgsl: "uniforms.delta_time",
+        wgsl_vertex: None,
+        godot: Some("delta_time"),
+        godot_vertex: Some("delta_time"),
+        glsl: Some("delta_time"),
+        hlsl: Some("unity_DeltaTime.x"),
+        hlsl_vertex: Some("unity_DeltaTime.x"),
+    },
+    Builtin {
+        wgsl: "uniforms.frame_count",
+        wgsl_vertex: None,
+        godot: Some("frame_count"),
+        godot_vertex: Some("frame_count"),
+        glsl: Some("frame_count"),
+        hlsl: Some("frame_count"),
+        hlsl_vertex: Some("frame_count"),
+    },
+];
+
+/// Varyings and uniforms the generic GLSL fragment shader expects.
+pub const GLSL_DECLARATIONS: &str = "varying vec2 v_uv;
+varying vec3 v_world_position;
+varying vec3 v_object_normal;
+varying vec3 v_world_normal;
+varying vec3 v_world_tangent;
+varying vec3 v_world_bitangent;
+uniform mat4 view;
+uniform mat4 inverse_view;
+uniform mat4 view_proj;
+uniform mat4 model;
+uniform mat4 inverse_model;
+uniform mat4 projection;
+uniform mat4 inverse_projection;
+uniform vec3 camera_position;
+uniform vec2 resolution;
+uniform vec2 mouse;
+uniform float time;
+uniform float delta_time;
+uniform int frame_count;
+";
+
+/// Values Godot has no builtin for, by the builtin's WGSL. They are declared
+/// as uniforms, to be set from a script, only when the shader reads them.
+pub const GODOT_UNIFORMS: &[(&str, &str)] = &[
+    ("uniforms.mouse", "uniform vec2 mouse;"),
+    ("uniforms.delta_time", "uniform float delta_time;"),
+    ("uniforms.frame_count", "uniform int frame_count;"),
+];
+
+/// Values Unity has no builtin for, declared like [`GODOT_UNIFORMS`].
+pub const UNITY_UNIFORMS: &[(&str, &str)] = &[
+    ("uniforms.mouse", "float2 mouse;"),
+    ("uniforms.frame_count", "int frame_count;"),
+];
+
+/// Rewrites the builtin expressions in a node's WGSL for a target and stage,
+/// and adds the WGSL of every builtin it reads to `used`.
+pub fn translate(
+    expr: &str,
+    format: TargetFormat,
+    is_vertex: bool,
+    used: &mut Vec<&'static str>,
+) -> String {
+    let mut result = expr.to_string();
+    for builtin in BUILTINS {
+        if !contains_token(&result, builtin.wgsl) {
+            continue;
+        }
+        if !used.contains(&builtin.wgsl) {
+            used.push(builtin.wgsl);
+        }
+        let replacement = match (format, is_vertex) {
+            (TargetFormat::Wgsl, false) => None,
+            (TargetFormat::Wgsl, true) => builtin.wgsl_vertex,
+            (TargetFormat::Godot, false) => builtin.godot,
+            (TargetFormat::Godot, true) => builtin.godot_vertex,
+            (TargetFormat::Unity, false) => builtin.hlsl,
+            (TargetFormat::Unity, true) => builtin.hlsl_vertex,
+            (TargetFormat::Generic, _) => builtin.glsl,
+        };
+        if let Some(replacement) = replacement {
+            result = replace_token(&result, builtin.wgsl, replacement);
+        }
+    }
+    result
+}
+
+/// Rewrites WGSL types and function names that differ on the target.
+pub fn translate_syntax(expr: &str, format: TargetFormat) -> String {
+    let expr = match format {
+        // HLSL constructors need every component; a cast splats instead
+        TargetFormat::Unity => (2..=4).fold(expr.to_string(), |acc, width| {
+            rewrite_calls(&acc, &format!("vec{}<f32>", width), &|args| {
+                if has_single_argument(args) {
+                    format!("((float{})({}))", width, args)
+                } else {
+                    format!("vec{}<f32>({})", width, args)
+                }
+            })
+        }),
+        _ => expr.to_string(),
+    };
+    let types: &[(&str, &str)] = match format {
+        TargetFormat::Wgsl => return expr,
+        TargetFormat::Godot | TargetFormat::Generic => &[
+            ("vec2<f32>", "vec2"),
+            ("vec3<f32>", "vec3"),
+            ("vec4<f32>", "vec4"),
+            ("f32", "float"),
+            ("i32", "int"),
+        ],
+        TargetFormat::Unity => &[
+            ("vec2<f32>", "float2"),
+            ("vec3<f32>", "float3"),
+            ("vec4<f32>", "float4"),
+            ("f32", "float"),
+            ("i32", "int"),
+        ],
+    };
+    let renamed = types
+        .iter()
+        .fold(expr, |acc, (from, to)| replace_token(&acc, from, to));
+    let renamed = FUNCTIONS.iter().fold(renamed, |acc, &(wgsl, ..)| {
+        replace_token(&acc, wgsl, function_name(wgsl, format))
+    });
+    match format {
+        // GLSL has no saturate; its clamp accepts scalar bounds for vectors
+        TargetFormat::Godot | TargetFormat::Generic => {
+            rewrite_calls(&renamed, "saturate", &|args| {
+                format!("clamp({}, 0.0, 1.0)", args)
+            })
+        }
+        _ => renamed,
+    }
+}
+
+/// WGSL functions named differently on other targets: the WGSL, GLSL
+/// (Godot and generic) and HLSL names.
+const FUNCTIONS: &[(&str, &str, &str)] = &[
+    ("fract", "fract", "frac"),
+    ("mix", "mix", "lerp"),
+    ("atan2", "atan", "atan2"),
+    ("inverseSqrt", "inversesqrt", "rsqrt"),
+];
+
+/// Name of a WGSL function on a target, e.g. `lerp` for `mix` on Unity.
+pub fn function_name(wgsl: &str, format: TargetFormat) -> &str {
+    let Some(&(_, glsl, hlsl)) = FUNCTIONS.iter().find(|(name, ..)| *name == wgsl) else {
+        return wgsl;
+    };
+    match format {
+        TargetFormat::Wgsl => wgsl,
+        TargetFormat::Godot | TargetFormat::Generic => glsl,
+        TargetFormat::Unity => hlsl,
+    }
+}
+
+/// Rewrites every call `name(args)` to `rewrite(args)`, including calls
+/// nested in the arguments.
+fn rewrite_calls(source: &str, name: &str, rewrite: &dyn Fn(&str) -> String) -> String {
+    let pattern = format!("{}(", name);
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(found) = rest.find(&pattern) {
+        let args_start = found + pattern.len();
+        let joined = rest[..found]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.');
+        let mut depth = 1;
+        let args_end = rest[args_start..].char_indices().find_map(|(i, c)| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            (depth == 0).then_some(args_start + i)
+        });
+        match args_end {
+            Some(args_end) if !joined => {
+                result.push_str(&rest[..found]);
+                let args = rewrite_calls(&rest[args_start..args_end], name, rewrite);
+                result.push_str(&rewrite(&args));
+                rest = &rest[args_end + 1..];
+            }
+            _ => {
+                result.push_str(&rest[..args_start]);
+                rest = &rest[args_start..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Returns `true` if call arguments hold a single expression, i.e. no comma
+/// outside nested parentheses.
+fn has_single_argument(args: &str) -> bool {
+    let mut depth = 0;
+    !args.chars().any(|c| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        c == ',' && depth == 0
+    })
+}
+
+/// Returns `true` if `token` appears in `source` on its own, as matched by
+/// [`replace_token`].
+fn contains_token(source: &str, token: &str) -> bool {
+    replace_token(source, token, "") != source
+}
+
+/// Replaces `from` only where it is not part of a longer identifier or
+/// field access, so `in.uv` does not match inside `in.uv2` or `min.uv`.
+fn replace_token(source: &str, from: &str, to: &str) -> String {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let check_before = from.chars().next().is_some_and(is_ident);
+    let check_after = from.chars().next_back().is_some_and(is_ident);
+
+    let mut result = String::with_capacity(source.len());
+    let mut copied = 0;
+    let mut search = 0;
+    while let Some(found) = source[search..].find(from) {
+        let start = search + found;
+        let end = start + from.len();
+        let before = source[..start].chars().next_back();
+        let after = source[end..].chars().next();
+        let joined_before = check_before && before.is_some_and(|c| is_ident(c) || c == '.');
+        let joined_after = check_after && after.is_some_and(is_ident);
+        if !joined_before && !joined_after {
+            result.push_str(&source[copied..start]);
+            result.push_str(to);
+            copied = end;
+            search = end;
+        } else {
+            search = start + from.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    result.push_str(&source[copied..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_token_skips_longer_identifiers() {
+        assert_eq!(
+            replace_token("in.uv + in.uv2", "in.uv", "UV"),
+            "UV + in.uv2"
+        );
+        assert_eq!(
+            replace_token("min.uv * in.uv", "in.uv", "UV"),
+            "min.uv * UV"
+        );
+        assert_eq!(replace_token("a.in.uv", "in.uv", "UV"), "a.in.uv");
+        assert_eq!(replace_token("in.uv.x", "in.uv", "UV"), "UV.x");
+    }
+
+    #[test]
+    fn translate_keeps_fragment_wgsl() {
+        let expr = "in.world_tangent + in.world_bitangent";
+        assert_eq!(
+            translate(expr, TargetFormat::Wgsl, false, &mut Vec::new()),
+            expr
+        );
+        assert_eq!(
+            translate("in.uv", TargetFormat::Wgsl, true, &mut Vec::new()),
+            "model.uv"
+        );
+        assert_eq!(
+            translate(
+                "in.world_tangent",
+                TargetFormat::Godot,
+                false,
+                &mut Vec::new()
+            ),
+            "(INV_VIEW_MATRIX * vec4(TANGENT, 0.0)).xyz"
+        );
+    }
+
+    #[test]
+    fn translate_records_the_builtins_read() {
+        let mut used = Vec::new();
+        let godot = translate(
+            "uniforms.mouse / uniforms.resolution + in.uv2",
+            TargetFormat::Godot,
+            false,
+            &mut used,
+        );
+        assert_eq!(godot, "mouse / VIEWPORT_SIZE + in.uv2");
+        assert_eq!(used, ["uniforms.resolution", "uniforms.mouse"]);
+
+        // A composite builtin is read as a whole, not through its parts
+        let mut used = Vec::new();
+        translate(
+            "normalize(uniforms.camera_position - in.world_position)",
+            TargetFormat::Godot,
+            false,
+            &mut used,
+        );
+        assert_eq!(
+            used,
+            ["normalize(uniforms.camera_position - in.world_position)"]
+        );
+    }
+
+    #[test]
+    fn translate_syntax_renames_wgsl_for_glsl() {
+        let wgsl = "vec3<f32>(f32(i), atan2(y, x), inverseSqrt(vec2<f32>(1.0).x))";
+        assert_eq!(translate_syntax(wgsl, TargetFormat::Wgsl), wgsl);
+        assert_eq!(
+            translate_syntax(wgsl, TargetFormat::Godot),
+            "vec3(float(i), atan(y, x), inversesqrt(vec2(1.0).x))"
+        );
+        // Only whole identifiers are renamed
+        assert_eq!(
+            translate_syntax("my_f32 + f32x", TargetFormat::Generic),
+            "my_f32 + f32x"
+        );
+    }
+
+    #[test]
+    fn saturate_becomes_clamp_in_glsl() {
+        assert_eq!(
+            translate_syntax("saturate(a + saturate(f(b)))", TargetFormat::Godot),
+            "clamp(a + clamp(f(b), 0.0, 1.0), 0.0, 1.0)"
+        );
+        assert_eq!(
+            translate_syntax("saturate(x)", TargetFormat::Wgsl),
+            "saturate(x)"
+        );
+    }
+
+    #[test]
+    fn rewrite_calls_skips_joined_names() {
+        let wrap = |args: &str| format!("[{}]", args);
+        assert_eq!(
+            rewrite_calls("my_saturate(x) + v.saturate(y)", "saturate", &wrap),
+            "my_saturate(x) + v.saturate(y)"
+        );
+        assert_eq!(rewrite_calls("saturate(x", "saturate", &wrap), "saturate(x");
+    }
+
+    #[test]
+    fn translate_targets_unity() {
+        assert_eq!(
+            translate_syntax("mix(vec3<f32>(0.0), fract(v), f32(t))", TargetFormat::Unity),
+            "lerp(((float3)(0.0)), frac(v), float(t))"
+        );
+        assert_eq!(
+            translate_syntax("vec4<f32>(vec3<f32>(max(a, b)), 1.0)", TargetFormat::Unity),
+            "float4(((float3)(max(a, b))), 1.0)"
+        );
+        assert_eq!(
+            translate("in.uv", TargetFormat::Unity, false, &mut Vec::new()),
+            "IN.texcoord0"
+        );
+    }
+}
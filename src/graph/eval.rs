@@ -1,51 +1,79 @@
+use crate::common::{UniformKind, UniformLayout};
 use crate::file::UmbraProject;
 use crate::file::export::TargetFormat;
+use crate::file::project::ShaderProperty;
 use crate::graph::UmbraNode;
-use crate::nodes::registry::global_registry;
-use egui_snarl::{InPinId, NodeId, Snarl};
+use crate::graph::builtins;
+use crate::graph::surface::{self, SURFACE_INPUTS};
+use crate::nodes::codegen::{CodegenContext, type_width, vector_literal, vector_type};
+use crate::nodes::registry::{InputDefinition, NodeRole, ShaderFunction, ShaderStage};
+use egui_snarl::{InPinId, NodeId, OutPinId, Snarl};
 use std::collections::HashMap;
 
 pub struct Evaluator;
 
+/// State for generating the code of a single shader stage.
+struct StageContext<'a> {
+    snarl: &'a Snarl<UmbraNode>,
+    properties: &'a [ShaderProperty],
+    resolved_vars: HashMap<NodeId, String>,
+    /// Number of components held by each resolved variable.
+    widths: HashMap<NodeId, usize>,
+    visit_stack: Vec<NodeId>,
+    code_lines: Vec<String>,
+    /// Helper functions required by the evaluated nodes, dependencies first.
+    helpers: Vec<&'static ShaderFunction>,
+    /// Builtins read by the evaluated nodes, by their WGSL; see
+    /// [`builtins::BUILTINS`].
+    builtins: Vec<&'static str>,
+    is_vertex: bool,
+    format: TargetFormat,
+}
+
 impl Evaluator {
     pub fn evaluate(project: &UmbraProject, format: TargetFormat) -> String {
         match format {
             TargetFormat::Wgsl => Self::evaluate_wgsl(project),
             TargetFormat::Godot => Self::evaluate_godot(project),
+            TargetFormat::Unity => Self::evaluate_unity(project),
             TargetFormat::Generic => Self::evaluate_generic(project),
         }
     }
 
     fn evaluate_wgsl(project: &UmbraProject) -> String {
-        let snarl = &project.graph.snarl;
         let mut shader = String::new();
 
         // 1. Uniforms
         shader.push_str("struct Uniforms {\n");
         shader.push_str("  view_proj: mat4x4<f32>,\n");
+        shader.push_str("  view: mat4x4<f32>,\n");
+        shader.push_str("  inverse_view: mat4x4<f32>,\n");
+        shader.push_str("  model: mat4x4<f32>,\n");
+        shader.push_str("  inverse_model: mat4x4<f32>,\n");
+        shader.push_str("  projection: mat4x4<f32>,\n");
+        shader.push_str("  inverse_projection: mat4x4<f32>,\n");
         shader.push_str("  time: f32,\n");
-        shader.push_str("  p1: f32,\n");
-        shader.push_str("  p2: f32,\n");
+        shader.push_str("  delta_time: f32,\n");
+        shader.push_str("  frame_count: u32,\n");
         shader.push_str("  p3: f32,\n");
         shader.push_str("  resolution: vec2<f32>,\n");
         shader.push_str("  mouse: vec2<f32>,\n");
+        shader.push_str("  camera_position: vec3<f32>,\n");
+        shader.push_str("  p4: f32,\n");
+        shader.push_str("  light_direction: vec3<f32>,\n");
+        shader.push_str("  light_intensity: f32,\n");
+        shader.push_str("  light_color: vec3<f32>,\n");
+        shader.push_str("  ambient_intensity: f32,\n");
 
-        for prop in &project.properties {
-            match prop.value {
-                crate::common::PropertyValue::Float(_) => {
-                    shader.push_str(&format!("  {}: f32,\n", prop.name));
-                    shader.push_str(&format!("  _pad_{}: vec3<f32>,\n", prop.name));
-                }
-                crate::common::PropertyValue::Vec2(_) => {
-                    shader.push_str(&format!("  {}: vec2<f32>,\n", prop.name));
-                    shader.push_str(&format!("  _pad_{}: vec2<f32>,\n", prop.name));
-                }
-                crate::common::PropertyValue::Color(_) | crate::common::PropertyValue::Vec4(_) => {
-                    shader.push_str(&format!("  {}: vec4<f32>,\n", prop.name));
-                }
-                _ => {}
-            }
-        }
+        let layout = UniformLayout::new(&project.properties);
+        shader.push_str(&layout.wgsl_fields(|name| {
+            project
+                .properties
+                .iter()
+                .find(|p| p.name == name)
+                .map(|p| doc_comment(p, "  "))
+                .unwrap_or_default()
+        }));
         shader.push_str("};\n\n");
         shader.push_str("@group(0) @binding(0) var<uniform> uniforms: Uniforms;\n\n");
 
@@ -54,160 +82,411 @@ impl Evaluator {
         shader.push_str("  @location(0) position: vec3<f32>,\n");
         shader.push_str("  @location(1) normal: vec3<f32>,\n");
         shader.push_str("  @location(2) uv: vec2<f32>,\n");
+        shader.push_str("  @location(3) tangent: vec4<f32>,\n");
         shader.push_str("};\n\n");
         shader.push_str("struct VertexOutput {\n");
         shader.push_str("  @builtin(position) clip_position: vec4<f32>,\n");
         shader.push_str("  @location(0) uv: vec2<f32>,\n");
         shader.push_str("  @location(1) world_position: vec3<f32>,\n");
+        shader.push_str("  @location(2) world_normal: vec3<f32>,\n");
+        shader.push_str("  @location(3) world_tangent: vec3<f32>,\n");
+        shader.push_str("  @location(4) world_bitangent: vec3<f32>,\n");
+        shader.push_str("  @location(5) object_normal: vec3<f32>,\n");
         shader.push_str("};\n\n");
 
-        let vs_node = snarl
-            .node_ids()
-            .find(|(_, n)| matches!(n, UmbraNode::VertexOutput));
-        let mut vs_code = Vec::new();
-        let vs_expr = if let Some((id, _)) = vs_node {
-            let mut vars = HashMap::new();
-            let mut visit_stack = Vec::new();
-            Self::evaluate_node(
-                snarl,
-                id,
-                &mut vars,
-                &mut visit_stack,
-                &mut vs_code,
-                true,
-                TargetFormat::Wgsl,
-            )
-        } else {
-            "vec3<f32>(0.0)".to_string()
-        };
+        shader.push_str(surface::PBR_WGSL);
+        shader.push('\n');
+
+        let mut vs = StageContext::new(project, true, TargetFormat::Wgsl);
+        let vs_expr = Self::evaluate_vertex_offset(&mut vs);
+        let mut fs = StageContext::new(project, false, TargetFormat::Wgsl);
+        let surface_exprs = Self::evaluate_surface(&mut fs);
+
+        shader.push_str(&helper_source(&[&vs, &fs], TargetFormat::Wgsl));
 
         shader.push_str("@vertex\n");
         shader.push_str("fn vs_main(model: VertexInput) -> VertexOutput {\n");
         shader.push_str("  var out: VertexOutput;\n");
-        for line in vs_code {
+        for line in &vs.code_lines {
             shader.push_str(&format!("  {}\n", line));
         }
         shader.push_str(&format!("  let offset = {};\n", vs_expr));
-        shader.push_str("  out.clip_position = uniforms.view_proj * vec4<f32>(model.position + offset.xyz, 1.0);\n");
+        shader.push_str("  let displaced = model.position + offset;\n");
+        shader
+            .push_str("  let world_position = (uniforms.model * vec4<f32>(displaced, 1.0)).xyz;\n");
+        shader.push_str(
+            "  out.clip_position = uniforms.view_proj * vec4<f32>(world_position, 1.0);\n",
+        );
         shader.push_str("  out.uv = model.uv;\n");
-        shader.push_str("  out.world_position = model.position;\n");
+        shader.push_str("  out.world_position = world_position;\n");
+        // Normals use the inverse transpose of the model matrix
+        shader.push_str(
+            "  out.world_normal = (vec4<f32>(model.normal, 0.0) * uniforms.inverse_model).xyz;\n",
+        );
+        shader.push_str(
+            "  out.world_tangent = (uniforms.model * vec4<f32>(model.tangent.xyz, 0.0)).xyz;\n",
+        );
+        shader.push_str(
+            "  out.world_bitangent = cross(out.world_normal, out.world_tangent) * model.tangent.w;\n",
+        );
+        shader.push_str("  out.object_normal = model.normal;\n");
         shader.push_str("  return out;\n");
         shader.push_str("}\n\n");
 
         // 3. Fragment Shader
         shader.push_str("@fragment\n");
         shader.push_str("fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {\n");
-
-        let fs_node = snarl
-            .node_ids()
-            .find(|(_, n)| matches!(n, UmbraNode::FragmentOutput));
-        let mut fs_code = Vec::new();
-        let fs_expr = if let Some((id, _)) = fs_node {
-            let mut vars = HashMap::new();
-            let mut visit_stack = Vec::new();
-            Self::evaluate_node(
-                snarl,
-                id,
-                &mut vars,
-                &mut visit_stack,
-                &mut fs_code,
-                false,
-                TargetFormat::Wgsl,
-            )
-        } else {
-            "vec4<f32>(1.0, 0.0, 1.0, 1.0)".to_string()
-        };
-
-        for line in fs_code {
+        for line in &fs.code_lines {
             shader.push_str(&format!("  {}\n", line));
         }
-        shader.push_str(&format!("  let color_final = {};\n", fs_expr));
-        shader.push_str("  return vec4<f32>(color_final.rgba);\n");
+        for (input, expr) in SURFACE_INPUTS.iter().zip(&surface_exprs) {
+            let value = expr
+                .clone()
+                .unwrap_or_else(|| vector_literal(input.default, TargetFormat::Wgsl));
+            shader.push_str(&format!("  let surface_{} = {};\n", input.ident, value));
+        }
+
+        shader.push_str("  var n = normalize(in.world_normal);\n");
+        if surface_exprs[surface::NORMAL].is_some() {
+            shader.push_str(
+                "  n = apply_normal_map(n, normalize(in.world_tangent), normalize(in.world_bitangent), normalize(surface_normal_ts));\n",
+            );
+        }
+        if surface_exprs[surface::ALPHA_CLIP].is_some() {
+            shader.push_str("  if (surface_alpha < surface_alpha_clip) {\n");
+            shader.push_str("    discard;\n");
+            shader.push_str("  }\n");
+        }
+        shader.push_str(
+            "  let lit = shade_surface(surface_albedo, n, surface_metallic, surface_roughness, surface_ao, in.world_position);\n",
+        );
+        shader.push_str("  return vec4<f32>(lit + surface_emission, surface_alpha);\n");
         shader.push_str("}\n");
 
         shader
     }
 
     fn evaluate_godot(project: &UmbraProject) -> String {
-        let snarl = &project.graph.snarl;
         let mut shader = String::new();
 
         shader.push_str("shader_type spatial;\n\n");
-        shader.push_str("uniform float time;\n");
+        let declarations_start = shader.len();
+
+        for (group, indices) in project.property_groups() {
+            if !group.is_empty() {
+                shader.push_str(&format!(
+                    "group_uniforms {};\n",
+                    crate::common::sanitize_identifier(group)
+                ));
+            }
+            for prop in indices.iter().map(|&i| &project.properties[i]) {
+                let (type_name, hint) = match prop.value {
+                    crate::common::PropertyValue::Float(_) => ("float", range_hint(prop, false)),
+                    crate::common::PropertyValue::Int(_) => ("int", range_hint(prop, true)),
+                    crate::common::PropertyValue::Vec2(_) => ("vec2", None),
+                    crate::common::PropertyValue::Vec3(_) => ("vec3", None),
+                    crate::common::PropertyValue::Vec4(_) => ("vec4", None),
+                    crate::common::PropertyValue::Color(_) => {
+                        ("vec4", Some("source_color".to_string()))
+                    }
+                    crate::common::PropertyValue::Bool(_) => ("bool", None),
+                    crate::common::PropertyValue::Mat3(_) => ("mat3", None),
+                    crate::common::PropertyValue::Mat4(_) => ("mat4", None),
+                    _ => continue,
+                };
+                shader.push_str(&doc_comment(prop, ""));
+                match hint {
+                    Some(hint) => shader.push_str(&format!(
+                        "uniform {} {} : {};\n",
+                        type_name, prop.name, hint
+                    )),
+                    None => shader.push_str(&format!("uniform {} {};\n", type_name, prop.name)),
+                }
+            }
+            if !group.is_empty() {
+                shader.push_str("group_uniforms;\n");
+            }
+        }
+
+        let mut body = String::new();
 
+        // Vertex
+        let mut vs = StageContext::new(project, true, TargetFormat::Godot);
+        let vs_expr = Self::evaluate_vertex_offset(&mut vs);
+
+        body.push_str("void vertex() {\n");
+        for line in &vs.code_lines {
+            body.push_str(&format!("  {}\n", line));
+        }
+        body.push_str(&format!("  VERTEX += {};\n", vs_expr));
+        body.push_str("}\n\n");
+
+        // Fragment
+        let mut fs = StageContext::new(project, false, TargetFormat::Godot);
+        let surface_exprs = Self::evaluate_surface(&mut fs);
+
+        body.push_str("void fragment() {\n");
+        for line in &fs.code_lines {
+            body.push_str(&format!("  {}\n", line));
+        }
+
+        // Albedo, metallic and roughness are always written so the export
+        // matches the preview defaults. The remaining builtins change how
+        // Godot renders the material, so they are only set when connected.
+        let value = |index: usize| {
+            surface_exprs[index].clone().unwrap_or_else(|| {
+                vector_literal(SURFACE_INPUTS[index].default, TargetFormat::Godot)
+            })
+        };
+        body.push_str(&format!("  ALBEDO = {};\n", value(surface::ALBEDO)));
+        body.push_str(&format!("  METALLIC = {};\n", value(surface::METALLIC)));
+        body.push_str(&format!("  ROUGHNESS = {};\n", value(surface::ROUGHNESS)));
+        let optional = [
+            (surface::NORMAL, "NORMAL_MAP"),
+            (surface::EMISSION, "EMISSION"),
+            (surface::AO, "AO"),
+            (surface::ALPHA, "ALPHA"),
+            (surface::ALPHA_CLIP, "ALPHA_SCISSOR_THRESHOLD"),
+        ];
+        for (index, builtin) in optional {
+            if let Some(expr) = &surface_exprs[index] {
+                if index == surface::NORMAL {
+                    // NORMAL_MAP expects the texture encoding, not a unit vector.
+                    body.push_str(&format!(
+                        "  {} = normalize({}) * 0.5 + 0.5;\n",
+                        builtin, expr
+                    ));
+                } else {
+                    body.push_str(&format!("  {} = {};\n", builtin, expr));
+                }
+            }
+        }
+        body.push_str("}\n");
+
+        // Values without a Godot builtin become script-driven uniforms
+        for (builtin, decl) in builtins::GODOT_UNIFORMS {
+            if reads_builtin(&[&vs, &fs], builtin) {
+                shader.push_str(decl);
+                shader.push('\n');
+            }
+        }
+        if shader.len() > declarations_start {
+            shader.push('\n');
+        }
+        shader.push_str(&helper_source(&[&vs, &fs], TargetFormat::Godot));
+        shader.push_str(&body);
+
+        shader
+    }
+
+    fn evaluate_unity(project: &UmbraProject) -> String {
+        // Built-in pipeline surface shader: Unity's Standard lighting model
+        // matches the PBR inputs of the surface output.
+        let mut vs = StageContext::new(project, true, TargetFormat::Unity);
+        let vs_expr = Self::evaluate_vertex_offset(&mut vs);
+        let mut fs = StageContext::new(project, false, TargetFormat::Unity);
+        let surface_exprs = Self::evaluate_surface(&mut fs);
+        let transparent = surface_exprs[surface::ALPHA].is_some();
+
+        let mut shader = String::new();
+        shader.push_str(&format!("Shader \"Umbra/{}\"\n{{\n", project.name));
+
+        shader.push_str("  Properties\n  {\n");
+        for (group, indices) in project.property_groups() {
+            let mut header = (!group.is_empty()).then_some(group);
+            for prop in indices.iter().map(|&i| &project.properties[i]) {
+                let range = prop.metadata.range();
+                let (attribute, kind, default) = match prop.value {
+                    crate::common::PropertyValue::Float(v) => match range {
+                        Some((min, max)) => (
+                            "",
+                            format!("Range({:?}, {:?})", min, max),
+                            format!("{:.3}", v),
+                        ),
+                        None => ("", "Float".to_string(), format!("{:.3}", v)),
+                    },
+                    crate::common::PropertyValue::Vec2([x, y]) => (
+                        "",
+                        "Vector".to_string(),
+                        format!("({:.3}, {:.3}, 0, 0)", x, y),
+                    ),
+                    crate::common::PropertyValue::Vec3([x, y, z]) => (
+                        "",
+                        "Vector".to_string(),
+                        format!("({:.3}, {:.3}, {:.3}, 0)", x, y, z),
+                    ),
+                    crate::common::PropertyValue::Color([r, g, b, a]) => (
+                        if prop.metadata.hdr { "[HDR] " } else { "" },
+                        "Color".to_string(),
+                        format!("({:.3}, {:.3}, {:.3}, {:.3})", r, g, b, a),
+                    ),
+                    crate::common::PropertyValue::Vec4([x, y, z, w]) => (
+                        "",
+                        "Vector".to_string(),
+                        format!("({:.3}, {:.3}, {:.3}, {:.3})", x, y, z, w),
+                    ),
+                    crate::common::PropertyValue::Int(v) => match range {
+                        Some((min, max)) => (
+                            "[IntRange] ",
+                            format!("Range({}, {})", min.round(), max.round()),
+                            v.to_string(),
+                        ),
+                        None => ("", "Int".to_string(), v.to_string()),
+                    },
+                    // Material toggles are floats holding 0 or 1
+                    crate::common::PropertyValue::Bool(v) => {
+                        ("[Toggle] ", "Float".to_string(), u8::from(v).to_string())
+                    }
+                    _ => continue,
+                };
+                if let Some(group) = header.take() {
+                    shader.push_str(&format!(
+                        "    [Header({})]\n",
+                        group.replace(['(', ')'], "")
+                    ));
+                }
+                for line in prop.metadata.description.lines() {
+                    shader.push_str(format!("    // {}", line).trim_end());
+                    shader.push('\n');
+                }
+                shader.push_str(&format!(
+                    "    {}{} (\"{}\", {}) = {}\n",
+                    attribute,
+                    prop.name,
+                    prop.label().replace('"', "'"),
+                    kind,
+                    default
+                ));
+            }
+        }
+        shader.push_str("  }\n\n");
+
+        shader.push_str("  SubShader\n  {\n");
+        if transparent {
+            shader.push_str(
+                "    Tags { \"RenderType\"=\"Transparent\" \"Queue\"=\"Transparent\" }\n",
+            );
+        } else {
+            shader.push_str("    Tags { \"RenderType\"=\"Opaque\" }\n");
+        }
+        shader.push_str("    CGPROGRAM\n");
+        shader.push_str(&format!(
+            "    #pragma surface surf Standard fullforwardshadows addshadow vertex:vert{}\n",
+            if transparent { " alpha:fade" } else { "" }
+        ));
+        shader.push_str("    #pragma target 3.5\n\n");
+
+        let mut declarations = String::new();
         for prop in &project.properties {
             match prop.value {
                 crate::common::PropertyValue::Float(_) => {
-                    shader.push_str(&format!("uniform float {};\n", prop.name));
+                    declarations.push_str(&format!("    float {};\n", prop.name));
                 }
                 crate::common::PropertyValue::Vec2(_) => {
-                    shader.push_str(&format!("uniform vec2 {};\n", prop.name));
+                    declarations.push_str(&format!("    float2 {};\n", prop.name));
+                }
+                crate::common::PropertyValue::Vec3(_) => {
+                    declarations.push_str(&format!("    float3 {};\n", prop.name));
                 }
                 crate::common::PropertyValue::Color(_) | crate::common::PropertyValue::Vec4(_) => {
-                    shader.push_str(&format!("uniform vec4 {} : source_color;\n", prop.name));
+                    declarations.push_str(&format!("    float4 {};\n", prop.name));
+                }
+                crate::common::PropertyValue::Int(_) => {
+                    declarations.push_str(&format!("    int {};\n", prop.name));
+                }
+                crate::common::PropertyValue::Bool(_) => {
+                    declarations.push_str(&format!("    float {};\n", prop.name));
+                }
+                // Matrices cannot be material properties; set them from a script
+                crate::common::PropertyValue::Mat3(_) => {
+                    declarations.push_str(&format!("    float3x3 {};\n", prop.name));
+                }
+                crate::common::PropertyValue::Mat4(_) => {
+                    declarations.push_str(&format!("    float4x4 {};\n", prop.name));
                 }
                 _ => {}
             }
         }
-        shader.push_str("\n");
 
-        // Vertex
-        let vs_node = snarl
-            .node_ids()
-            .find(|(_, n)| matches!(n, UmbraNode::VertexOutput));
-        let mut vs_code = Vec::new();
-        let vs_expr = if let Some((id, _)) = vs_node {
-            let mut vars = HashMap::new();
-            let mut visit_stack = Vec::new();
-            Self::evaluate_node(
-                snarl,
-                id,
-                &mut vars,
-                &mut visit_stack,
-                &mut vs_code,
-                true,
-                TargetFormat::Godot,
-            )
-        } else {
-            "vec3(0.0)".to_string()
-        };
+        let mut body = String::new();
+        body.push_str("    struct Input\n    {\n");
+        body.push_str("      float2 texcoord0;\n");
+        body.push_str("      float3 worldPos;\n");
+        body.push_str("      float4 screenPos;\n");
+        body.push_str("      float3 object_normal;\n");
+        body.push_str("      float3 world_normal;\n");
+        body.push_str("      float3 world_tangent;\n");
+        body.push_str("      float3 world_bitangent;\n");
+        body.push_str("    };\n\n");
 
-        shader.push_str("void vertex() {\n");
-        for line in vs_code {
-            shader.push_str(&format!("  {}\n", line));
+        body.push_str("    void vert(inout appdata_full v, out Input o)\n    {\n");
+        body.push_str("      UNITY_INITIALIZE_OUTPUT(Input, o);\n");
+        for line in &vs.code_lines {
+            body.push_str(&format!("      {}\n", line));
         }
-        shader.push_str(&format!("  VERTEX += ({}).xyz;\n", vs_expr));
-        shader.push_str("}\n\n");
+        body.push_str(&format!("      v.vertex.xyz += {};\n", vs_expr));
+        body.push_str("      o.texcoord0 = v.texcoord.xy;\n");
+        body.push_str("      o.object_normal = v.normal;\n");
+        body.push_str("      o.world_normal = UnityObjectToWorldNormal(v.normal);\n");
+        body.push_str("      o.world_tangent = UnityObjectToWorldDir(v.tangent.xyz);\n");
+        body.push_str(
+            "      o.world_bitangent = cross(o.world_normal, o.world_tangent) * v.tangent.w * unity_WorldTransformParams.w;\n",
+        );
+        body.push_str("    }\n\n");
 
-        // Fragment
-        let fs_node = snarl
-            .node_ids()
-            .find(|(_, n)| matches!(n, UmbraNode::FragmentOutput));
-        let mut fs_code = Vec::new();
-        let fs_expr = if let Some((id, _)) = fs_node {
-            let mut vars = HashMap::new();
-            let mut visit_stack = Vec::new();
-            Self::evaluate_node(
-                snarl,
-                id,
-                &mut vars,
-                &mut visit_stack,
-                &mut fs_code,
-                false,
-                TargetFormat::Godot,
-            )
-        } else {
-            "vec4(1.0, 0.0, 1.0, 1.0)".to_string()
+        body.push_str("    void surf(Input IN, inout SurfaceOutputStandard o)\n    {\n");
+        for line in &fs.code_lines {
+            body.push_str(&format!("      {}\n", line));
+        }
+        let value = |index: usize| {
+            surface_exprs[index].clone().unwrap_or_else(|| {
+                vector_literal(SURFACE_INPUTS[index].default, TargetFormat::Unity)
+            })
         };
+        body.push_str(&format!("      o.Albedo = {};\n", value(surface::ALBEDO)));
+        body.push_str(&format!(
+            "      o.Metallic = {};\n",
+            value(surface::METALLIC)
+        ));
+        body.push_str(&format!(
+            "      o.Smoothness = 1.0 - {};\n",
+            value(surface::ROUGHNESS)
+        ));
+        if let Some(normal) = &surface_exprs[surface::NORMAL] {
+            body.push_str(&format!("      o.Normal = normalize({});\n", normal));
+        }
+        if let Some(emission) = &surface_exprs[surface::EMISSION] {
+            body.push_str(&format!("      o.Emission = {};\n", emission));
+        }
+        if let Some(ao) = &surface_exprs[surface::AO] {
+            body.push_str(&format!("      o.Occlusion = {};\n", ao));
+        }
+        body.push_str(&format!("      o.Alpha = {};\n", value(surface::ALPHA)));
+        if let Some(clip) = &surface_exprs[surface::ALPHA_CLIP] {
+            body.push_str(&format!("      clip(o.Alpha - {});\n", clip));
+        }
+        body.push_str("    }\n");
 
-        shader.push_str("void fragment() {\n");
-        for line in fs_code {
-            shader.push_str(&format!("  {}\n", line));
+        // Values without a Unity builtin become script-driven uniforms
+        for (builtin, decl) in builtins::UNITY_UNIFORMS {
+            if reads_builtin(&[&vs, &fs], builtin) {
+                declarations.push_str(&format!("    {}\n", decl));
+            }
         }
-        shader.push_str(&format!("  vec4 color_final = {};\n", fs_expr));
-        shader.push_str("  ALBEDO = color_final.rgb;\n");
-        shader.push_str("  ALPHA = color_final.a;\n");
-        shader.push_str("}\n");
+        if !declarations.is_empty() {
+            shader.push_str(&declarations);
+            shader.push('\n');
+        }
+        for line in helper_source(&[&vs, &fs], TargetFormat::Unity).lines() {
+            if line.is_empty() {
+                shader.push('\n');
+            } else {
+                shader.push_str(&format!("    {}\n", line));
+            }
+        }
+        shader.push_str(&body);
+        shader.push_str("    ENDCG\n  }\n\n");
+        shader.push_str("  FallBack \"Diffuse\"\n}\n");
 
         shader
     }
@@ -215,13 +494,15 @@ impl Evaluator {
     fn evaluate_generic(project: &UmbraProject) -> String {
         // Generic .shader (Unity-like or just GLSL)
         // For now, let's treat it as simple GLSL
-        let snarl = &project.graph.snarl;
         let mut shader = String::new();
 
         shader.push_str("// Generic GLSL Shader\n\n");
-        shader.push_str("uniform float time;\n");
+        shader.push_str(builtins::GLSL_DECLARATIONS);
 
         for prop in &project.properties {
+            if UniformKind::of(&prop.value).is_some() {
+                shader.push_str(&doc_comment(prop, ""));
+            }
             match prop.value {
                 crate::common::PropertyValue::Float(_) => {
                     shader.push_str(&format!("uniform float {};\n", prop.name));
@@ -229,215 +510,843 @@ impl Evaluator {
                 crate::common::PropertyValue::Vec2(_) => {
                     shader.push_str(&format!("uniform vec2 {};\n", prop.name));
                 }
+                crate::common::PropertyValue::Vec3(_) => {
+                    shader.push_str(&format!("uniform vec3 {};\n", prop.name));
+                }
                 crate::common::PropertyValue::Color(_) | crate::common::PropertyValue::Vec4(_) => {
                     shader.push_str(&format!("uniform vec4 {};\n", prop.name));
                 }
+                crate::common::PropertyValue::Int(_) => {
+                    shader.push_str(&format!("uniform int {};\n", prop.name));
+                }
+                crate::common::PropertyValue::Bool(_) => {
+                    shader.push_str(&format!("uniform bool {};\n", prop.name));
+                }
+                crate::common::PropertyValue::Mat3(_) => {
+                    shader.push_str(&format!("uniform mat3 {};\n", prop.name));
+                }
+                crate::common::PropertyValue::Mat4(_) => {
+                    shader.push_str(&format!("uniform mat4 {};\n", prop.name));
+                }
                 _ => {}
             }
         }
-        shader.push_str("\n");
+        shader.push('\n');
 
-        // Simple fragment-only style for generic
-        let fs_node = snarl
-            .node_ids()
-            .find(|(_, n)| matches!(n, UmbraNode::FragmentOutput));
-        let mut fs_code = Vec::new();
-        let fs_expr = if let Some((id, _)) = fs_node {
-            let mut vars = HashMap::new();
-            let mut visit_stack = Vec::new();
-            Self::evaluate_node(
-                snarl,
-                id,
-                &mut vars,
-                &mut visit_stack,
-                &mut fs_code,
-                false,
-                TargetFormat::Generic,
-            )
-        } else {
-            "vec4(1.0, 0.0, 1.0, 1.0)".to_string()
+        // Simple unlit fragment-only style for generic
+        let mut fs = StageContext::new(project, false, TargetFormat::Generic);
+        let surface_exprs = Self::evaluate_surface(&mut fs);
+        let value = |index: usize| {
+            surface_exprs[index].clone().unwrap_or_else(|| {
+                vector_literal(SURFACE_INPUTS[index].default, TargetFormat::Generic)
+            })
         };
 
+        shader.push_str(&helper_source(&[&fs], TargetFormat::Generic));
         shader.push_str("void main() {\n");
-        for line in fs_code {
+        for line in &fs.code_lines {
             shader.push_str(&format!("  {}\n", line));
         }
-        shader.push_str(&format!("  gl_FragColor = {};\n", fs_expr));
+        shader.push_str(&format!(
+            "  float surface_alpha = {};\n",
+            value(surface::ALPHA)
+        ));
+        if let Some(clip) = &surface_exprs[surface::ALPHA_CLIP] {
+            shader.push_str(&format!("  if (surface_alpha < {}) discard;\n", clip));
+        }
+        shader.push_str(&format!(
+            "  gl_FragColor = vec4({} + {}, surface_alpha);\n",
+            value(surface::ALBEDO),
+            value(surface::EMISSION)
+        ));
         shader.push_str("}\n");
 
         shader
     }
 
-    fn evaluate_node(
+    fn find_node(
         snarl: &Snarl<UmbraNode>,
-        node_id: NodeId,
-        resolved_vars: &mut HashMap<NodeId, String>,
-        visit_stack: &mut Vec<NodeId>,
-        code_lines: &mut Vec<String>,
-        is_vertex: bool,
-        format: TargetFormat,
-    ) -> String {
-        if let Some(var_name) = resolved_vars.get(&node_id) {
+        predicate: impl Fn(&UmbraNode) -> bool,
+    ) -> Option<NodeId> {
+        snarl
+            .node_ids()
+            .find(|(_, n)| predicate(n))
+            .map(|(id, _)| id)
+    }
+
+    /// Evaluates every connected input of the surface output node, coerced
+    /// to the input's type. Unconnected inputs are returned as `None` so each
+    /// backend can decide whether to emit a default or skip the builtin.
+    fn evaluate_surface(ctx: &mut StageContext) -> Vec<Option<String>> {
+        let Some(id) = Self::find_node(ctx.snarl, |n| n.role() == NodeRole::SurfaceOutput) else {
+            return vec![None; SURFACE_INPUTS.len()];
+        };
+
+        SURFACE_INPUTS
+            .iter()
+            .enumerate()
+            .map(|(i, input)| {
+                ctx.evaluate_connected(id, i, input.type_name).or_else(|| {
+                    let value = ctx.snarl[id].input_values.get(input.name)?;
+                    ctx.value_expr(value, input.type_name)
+                })
+            })
+            .collect()
+    }
+
+    /// Evaluates the position offset fed into the vertex output node.
+    fn evaluate_vertex_offset(ctx: &mut StageContext) -> String {
+        Self::find_node(ctx.snarl, |n| n.role() == NodeRole::VertexOutput)
+            .and_then(|id| ctx.evaluate_connected(id, 0, "Vec3"))
+            .unwrap_or_else(|| vector_literal(&[0.0, 0.0, 0.0], ctx.format))
+    }
+}
+
+impl<'a> StageContext<'a> {
+    fn new(project: &'a UmbraProject, is_vertex: bool, format: TargetFormat) -> Self {
+        Self {
+            snarl: &project.graph.snarl,
+            properties: &project.properties,
+            resolved_vars: HashMap::new(),
+            widths: HashMap::new(),
+            visit_stack: Vec::new(),
+            code_lines: Vec::new(),
+            helpers: Vec::new(),
+            builtins: Vec::new(),
+            is_vertex,
+            format,
+        }
+    }
+
+    fn evaluate_node(&mut self, node_id: NodeId) -> String {
+        if let Some(var_name) = self.resolved_vars.get(&node_id) {
             return var_name.clone();
         }
 
-        if visit_stack.contains(&node_id) {
+        if self.visit_stack.contains(&node_id) {
             return "0.0".to_string();
         }
-        visit_stack.push(node_id);
+        self.visit_stack.push(node_id);
 
+        let snarl = self.snarl;
+        let format = self.format;
         let node = &snarl[node_id];
         let var_name = format!("node_{}", node_id.0);
+        let mut dynamic_width = 1;
 
-        let result_expr = match node {
-            UmbraNode::Generic {
-                type_name,
-                properties,
-            } => {
-                if let Some(impl_node) = global_registry().read().unwrap().get(type_name) {
-                    let mut inputs = Vec::new();
-                    for i in 0..impl_node.inputs().len() {
-                        inputs.push(Self::evaluate_input(
-                            snarl,
-                            node_id,
-                            i,
-                            resolved_vars,
-                            visit_stack,
-                            code_lines,
-                            is_vertex,
-                            format,
-                        ));
-                    }
-                    let expr = impl_node.execute(&inputs, properties);
-                    // Basic transpile if target is not WGSL
-                    if format != TargetFormat::Wgsl {
-                        expr.replace("vec3<f32>", "vec3")
-                            .replace("vec4<f32>", "vec4")
-                            .replace("vec2<f32>", "vec2")
-                            .replace("f32", "float")
-                    } else {
-                        expr
-                    }
-                } else {
-                    "0.0".to_string()
-                }
+        let result_expr = match node.implementation() {
+            Some(impl_node) if impl_node.role() == NodeRole::Property => {
+                self.property_expr(node.property_name().unwrap_or_default())
             }
-            UmbraNode::Position => {
-                match format {
-                    TargetFormat::Wgsl => if is_vertex {
-                        "model.position"
-                    } else {
-                        "in.world_position"
-                    }
-                    .to_string(),
-                    TargetFormat::Godot => if is_vertex {
-                        "VERTEX"
-                    } else {
-                        "WORLD_POSITION"
-                    }
-                    .to_string(), // WORLD_POSITION varies in Godot
-                    TargetFormat::Generic => "position".to_string(),
+            Some(impl_node) => {
+                let definitions = impl_node.inputs();
+                dynamic_width = self.dynamic_width(node_id, &definitions);
+                let inputs: Vec<String> = definitions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, input)| self.evaluate_generic_input(node_id, i, input, dynamic_width))
+                    .collect();
+                let properties = &node.properties;
+                if impl_node.validate(properties, dynamic_width).is_err() {
+                    let width = self.result_width(node_id, dynamic_width);
+                    vector_literal(&vec![0.0; width], format)
+                } else {
+                    let input_types: Vec<String> = definitions
+                        .iter()
+                        .map(|input| self.input_type(input, dynamic_width).to_string())
+                        .collect();
+                    let mut ctx = CodegenContext::new(format, self.stage(), &input_types);
+                    let expr = impl_node.generate(&mut ctx, &inputs, properties);
+                    self.take_requirements(ctx);
+                    expr
                 }
             }
-            UmbraNode::Float(val) => format!("{:.3}", val),
-            UmbraNode::Color(r, g, b, a) => match format {
-                TargetFormat::Wgsl => format!("vec4<f32>({:.3}, {:.3}, {:.3}, {:.3})", r, g, b, a),
-                _ => format!("vec4({:.3}, {:.3}, {:.3}, {:.3})", r, g, b, a),
-            },
-            UmbraNode::Float4(x, y, z, w) => match format {
-                TargetFormat::Wgsl => format!("vec4<f32>({:.3}, {:.3}, {:.3}, {:.3})", x, y, z, w),
-                _ => format!("vec4({:.3}, {:.3}, {:.3}, {:.3})", x, y, z, w),
-            },
-            UmbraNode::Property(name) => match format {
-                TargetFormat::Wgsl => format!("uniforms.{}", name),
-                _ => name.clone(),
-            },
-            UmbraNode::VertexOutput => Self::evaluate_input(
-                snarl,
-                node_id,
-                0,
-                resolved_vars,
-                visit_stack,
-                code_lines,
-                is_vertex,
-                format,
-            ),
-            UmbraNode::FragmentOutput => Self::evaluate_input(
-                snarl,
-                node_id,
-                0,
-                resolved_vars,
-                visit_stack,
-                code_lines,
-                is_vertex,
-                format,
+            None => "0.0".to_string(),
+        };
+
+        self.visit_stack.pop();
+
+        let width = self.result_width(node_id, dynamic_width);
+        let decl = match format {
+            TargetFormat::Wgsl => format!("let {} = {};", var_name, result_expr),
+            _ => format!(
+                "{} {} = {};",
+                value_type(width, format),
+                var_name,
+                result_expr
             ),
         };
+        self.code_lines.push(decl);
+        self.resolved_vars.insert(node_id, var_name.clone());
+        self.widths.insert(node_id, width);
+        var_name
+    }
+
+    /// Reads a project property. The graph works in floats, so integer and
+    /// boolean properties are converted where they are read.
+    fn property_expr(&self, name: &str) -> String {
+        let uniform = match self.format {
+            TargetFormat::Wgsl => format!("uniforms.{}", name),
+            _ => name.to_string(),
+        };
+        let property = self.properties.iter().find(|p| p.name == name);
+        match property.map(|p| &p.value) {
+            // Unbound or deleted property
+            None => vector_literal(&[0.0], self.format),
+            Some(crate::common::PropertyValue::Int(_) | crate::common::PropertyValue::Bool(_)) => {
+                match self.format {
+                    TargetFormat::Wgsl => format!("f32({})", uniform),
+                    _ => format!("float({})", uniform),
+                }
+            }
+            _ => uniform,
+        }
+    }
 
-        visit_stack.pop();
+    /// Evaluates the node behind an output pin, reading the output's
+    /// components for nodes with several outputs.
+    fn evaluate_output(&mut self, pin: OutPinId) -> String {
+        let var_name = self.evaluate_node(pin.node);
+        if let Some(impl_node) = self.snarl[pin.node].implementation() {
+            let mut ctx = CodegenContext::new(self.format, self.stage(), &[]);
+            if let Some(expr) = impl_node.read_output(&mut ctx, pin.output, &var_name) {
+                self.take_requirements(ctx);
+                return expr;
+            }
+        }
+        match self.snarl[pin.node].output_swizzle(pin.output) {
+            Some(swizzle) => format!("{}.{}", var_name, swizzle),
+            None => var_name,
+        }
+    }
 
-        if matches!(node, UmbraNode::VertexOutput | UmbraNode::FragmentOutput) {
-            result_expr
+    fn stage(&self) -> ShaderStage {
+        if self.is_vertex {
+            ShaderStage::Vertex
         } else {
-            let decl = match format {
-                TargetFormat::Wgsl => format!("let {} = {};", var_name, result_expr),
-                _ => {
-                    // Primitive type inference for GLSL/GSL is hard with strings,
-                    // but since our nodes return typed-looking strings:
-                    if result_expr.contains("vec4") {
-                        format!("vec4 {} = {};", var_name, result_expr)
-                    } else if result_expr.contains("vec3") {
-                        format!("vec3 {} = {};", var_name, result_expr)
-                    } else if result_expr.contains("vec2") {
-                        format!("vec2 {} = {};", var_name, result_expr)
-                    } else {
-                        format!("float {} = {};", var_name, result_expr)
-                    }
-                }
-            };
-            code_lines.push(decl);
-            resolved_vars.insert(node_id, var_name.clone());
-            var_name
+            ShaderStage::Fragment
         }
     }
 
-    fn evaluate_input(
-        snarl: &Snarl<UmbraNode>,
+    /// Number of components held by a node's variable. Multi-output nodes
+    /// hold the widest component their outputs read, and dynamic outputs
+    /// take the node's dynamic width, unless the node sets its own width.
+    fn result_width(&self, node_id: NodeId, dynamic_width: usize) -> usize {
+        let node = &self.snarl[node_id];
+        if let Some(name) = node.property_name() {
+            return self.property_width(name).unwrap_or(1);
+        }
+        let Some(impl_node) = node.implementation() else {
+            return 1;
+        };
+        let properties = &node.properties;
+        if let Some(width) = impl_node.result_width(properties, dynamic_width) {
+            return width;
+        }
+
+        let outputs = impl_node.outputs();
+        let swizzled = outputs
+            .iter()
+            .filter_map(|o| o.swizzle.as_deref())
+            .flat_map(str::chars)
+            .filter_map(|c| "xyzw".find(c))
+            .max();
+        match (swizzled, outputs.first()) {
+            (Some(last), _) => last + 1,
+            (None, Some(output)) if is_dynamic(&output.type_name) => dynamic_width,
+            (None, Some(output)) => type_width(&output.type_name).unwrap_or(1),
+            (None, None) => 1,
+        }
+    }
+
+    /// Evaluates the nodes feeding the dynamic inputs of a node and returns
+    /// the width its dynamic pins share: that of the widest connected input,
+    /// and at least 2 if the node has `Vector` pins.
+    fn dynamic_width(&mut self, node_id: NodeId, inputs: &[InputDefinition]) -> usize {
+        let mut width = 1;
+        for (index, input) in inputs.iter().enumerate() {
+            match input.type_name.as_str() {
+                "Vector" => width = width.max(2),
+                "Dynamic" => {}
+                _ => continue,
+            }
+            let in_pin = self.snarl.in_pin(InPinId {
+                node: node_id,
+                input: index,
+            });
+            if let Some(remote) = in_pin.remotes.first() {
+                self.evaluate_output(*remote);
+                width = width.max(self.pin_width(*remote).unwrap_or(1));
+            }
+        }
+        width
+    }
+
+    /// Number of components an evaluated output pin carries, or `None` if it
+    /// is unknown (missing or non-numeric properties).
+    fn pin_width(&self, pin: OutPinId) -> Option<usize> {
+        let node = &self.snarl[pin.node];
+        if let Some(swizzle) = node.output_swizzle(pin.output) {
+            return Some(swizzle.len());
+        }
+        self.widths
+            .get(&pin.node)
+            .copied()
+            .or_else(|| node.output_type(pin.output).and_then(|t| type_width(&t)))
+    }
+
+    /// Number of components of a project property, or `None` if it is
+    /// missing or not a shader value.
+    fn property_width(&self, name: &str) -> Option<usize> {
+        let property = self.properties.iter().find(|p| p.name == name)?;
+        type_width(property.value.type_name())
+    }
+
+    /// Records the helpers and builtins the code generated with `ctx` needs.
+    fn take_requirements(&mut self, ctx: CodegenContext) {
+        let (helpers, used) = ctx.into_parts();
+        for helper in helpers {
+            self.require_helper(helper);
+        }
+        for builtin in used {
+            if !self.builtins.contains(&builtin) {
+                self.builtins.push(builtin);
+            }
+        }
+    }
+
+    /// Adds a helper function, after the helpers it depends on.
+    fn require_helper(&mut self, helper: &'static ShaderFunction) {
+        if self.helpers.iter().any(|h| h.name == helper.name) {
+            return;
+        }
+        for dep in helper.deps {
+            self.require_helper(dep);
+        }
+        self.helpers.push(helper);
+    }
+
+    /// Evaluates the node connected to an input and coerces it to `type_name`,
+    /// or returns `None` if the input is unconnected.
+    fn evaluate_connected(
+        &mut self,
         node_id: NodeId,
         input_index: usize,
-        resolved_vars: &mut HashMap<NodeId, String>,
-        visit_stack: &mut Vec<NodeId>,
-        code_lines: &mut Vec<String>,
-        is_vertex: bool,
-        format: TargetFormat,
-    ) -> String {
-        let in_pin = snarl.in_pin(InPinId {
+        type_name: &str,
+    ) -> Option<String> {
+        let in_pin = self.snarl.in_pin(InPinId {
             node: node_id,
             input: input_index,
         });
+        let remote = *in_pin.remotes.first()?;
+        let expr = self.evaluate_output(remote);
+        match self.pin_width(remote) {
+            Some(width) => Some(coerce(&expr, width, type_name, self.format)),
+            None => Some(expr),
+        }
+    }
 
-        if let Some(remote) = in_pin.remotes.first() {
-            Self::evaluate_node(
-                snarl,
-                remote.node,
-                resolved_vars,
-                visit_stack,
-                code_lines,
-                is_vertex,
-                format,
-            )
+    /// Type an input resolves to: dynamic inputs take the node's dynamic width.
+    fn input_type<'d>(&self, input: &'d InputDefinition, dynamic_width: usize) -> &'d str {
+        if is_dynamic(&input.type_name) {
+            width_type(dynamic_width)
         } else {
-            match snarl[node_id] {
-                UmbraNode::VertexOutput => match format {
-                    TargetFormat::Wgsl => "vec3<f32>(0.0)".to_string(),
-                    _ => "vec3(0.0)".to_string(),
-                },
-                UmbraNode::FragmentOutput => match format {
-                    TargetFormat::Wgsl => "vec4<f32>(1.0, 1.0, 1.0, 1.0)".to_string(),
-                    _ => "vec4(1.0, 1.0, 1.0, 1.0)".to_string(),
-                },
-                _ => "0.0".to_string(),
+            input.type_name.as_str()
+        }
+    }
+
+    /// Evaluates an input of a registry node, coerced to its type. Dynamic
+    /// inputs take the node's dynamic width. Unconnected inputs use the
+    /// input's default expression if it declares one, otherwise its value
+    /// as edited on the node, otherwise zero.
+    fn evaluate_generic_input(
+        &mut self,
+        node_id: NodeId,
+        input_index: usize,
+        input: &InputDefinition,
+        dynamic_width: usize,
+    ) -> String {
+        let type_name = self.input_type(input, dynamic_width);
+        if let Some(expr) = self.evaluate_connected(node_id, input_index, type_name) {
+            return expr;
+        }
+        // A value edited on the node replaces the default expression
+        let node = &self.snarl[node_id];
+        if let Some(expr) = node
+            .input_values
+            .get(&input.name)
+            .and_then(|value| self.value_expr(value, type_name))
+        {
+            return expr;
+        }
+        // Defaults are fragment-stage WGSL, e.g. `in.uv`
+        if let Some(default) = &input.default {
+            let translated =
+                builtins::translate(default, self.format, self.is_vertex, &mut self.builtins);
+            return builtins::translate_syntax(&translated, self.format);
+        }
+        if let Some(expr) = input
+            .value
+            .as_ref()
+            .and_then(|value| self.value_expr(value, type_name))
+        {
+            return expr;
+        }
+        let width = type_width(type_name).unwrap_or(1);
+        match matrix_size(width) {
+            Some(size) => identity_literal(size, self.format),
+            None if self.format == TargetFormat::Wgsl && width == 1 => "f32(0.0)".to_string(),
+            None => vector_literal(&vec![0.0; width], self.format),
+        }
+    }
+
+    /// Constant holding a value, coerced to `type_name`; scalars are
+    /// splatted, e.g. the value of a dynamic input to the node's width.
+    /// WGSL scalars get a concrete type: naga cannot infer one for builtins
+    /// called with only abstract literals, e.g. `mix(0.0, 1.0, 0.5)`.
+    fn value_expr(&self, value: &crate::common::PropertyValue, type_name: &str) -> Option<String> {
+        let components = value.components()?;
+        let literal = vector_literal(&components, self.format);
+        Some(match (self.format, components.len()) {
+            (TargetFormat::Wgsl, 1) => {
+                coerce(&format!("f32({})", literal), 1, type_name, self.format)
             }
+            (_, width) => coerce(&literal, width, type_name, self.format),
+        })
+    }
+}
+
+/// Number of rows and columns of a square matrix with `width` elements.
+fn matrix_size(width: usize) -> Option<usize> {
+    match width {
+        9 => Some(3),
+        16 => Some(4),
+        _ => None,
+    }
+}
+
+/// Pin type with the given number of components.
+fn width_type(width: usize) -> &'static str {
+    match width {
+        2 => "Vec2",
+        3 => "Vec3",
+        4 => "Vec4",
+        _ => "Float",
+    }
+}
+
+/// `Dynamic` pins accept any width and `Vector` pins any vector; a node's
+/// dynamic pins all resolve to the width of its widest connected input.
+fn is_dynamic(type_name: &str) -> bool {
+    matches!(type_name, "Dynamic" | "Vector")
+}
+
+/// Type of a variable holding `width` components, for targets that declare
+/// variable types.
+fn value_type(width: usize, format: TargetFormat) -> String {
+    match (matrix_size(width), format) {
+        (Some(size), TargetFormat::Wgsl) => format!("mat{0}x{0}<f32>", size),
+        (Some(size), TargetFormat::Unity) => format!("float{0}x{0}", size),
+        (Some(size), _) => format!("mat{}", size),
+        (None, _) if width == 1 => "float".to_string(),
+        (None, _) => vector_type(width, format),
+    }
+}
+
+/// Formats an identity matrix, the default of unconnected matrix inputs.
+fn identity_literal(size: usize, format: TargetFormat) -> String {
+    let elements: Vec<&str> = (0..size * size)
+        .map(|i| if i % (size + 1) == 0 { "1.0" } else { "0.0" })
+        .collect();
+    format!(
+        "{}({})",
+        value_type(size * size, format),
+        elements.join(", ")
+    )
+}
+
+/// Returns `true` if any stage reads the builtin given by its WGSL.
+fn reads_builtin(stages: &[&StageContext], builtin: &str) -> bool {
+    stages.iter().any(|stage| stage.builtins.contains(&builtin))
+}
+
+/// Concatenates the helper functions of all stages, each emitted once.
+fn helper_source(stages: &[&StageContext], format: TargetFormat) -> String {
+    let mut emitted: Vec<&str> = Vec::new();
+    let mut source = String::new();
+    for helper in stages.iter().flat_map(|stage| &stage.helpers) {
+        if !emitted.contains(&helper.name) {
+            emitted.push(helper.name);
+            source.push_str(helper.source(format));
+            source.push('\n');
+        }
+    }
+    source
+}
+
+/// Converts an expression between pin types: scalars are splatted, wider
+/// vectors are truncated and narrower vectors are padded (alpha with 1.0).
+fn coerce(expr: &str, from_width: usize, to: &str, format: TargetFormat) -> String {
+    let Some(to_width) = type_width(to) else {
+        return expr.to_string();
+    };
+
+    if from_width == to_width {
+        expr.to_string()
+    } else if matrix_size(from_width).is_some() || matrix_size(to_width).is_some() {
+        // Matrices only connect to matrix pins of the same size
+        expr.to_string()
+    } else if from_width == 1 {
+        match format {
+            // HLSL constructors need every component; a cast splats instead.
+            TargetFormat::Unity => format!("(({})({}))", vector_type(to_width, format), expr),
+            _ => format!("{}({})", vector_type(to_width, format), expr),
+        }
+    } else if to_width < from_width {
+        let swizzle = &"xyzw"[..to_width];
+        format!("({}).{}", expr, swizzle)
+    } else {
+        let mut padding: Vec<&str> = vec!["0.0"; to_width - from_width];
+        if to_width == 4 {
+            padding[to_width - from_width - 1] = "1.0";
+        }
+        format!(
+            "{}({}, {})",
+            vector_type(to_width, format),
+            expr,
+            padding.join(", ")
+        )
+    }
+}
+
+/// Godot `hint_range` of a Float or Int property with a range.
+fn range_hint(property: &ShaderProperty, integer: bool) -> Option<String> {
+    let (min, max) = property.metadata.range()?;
+    let number = |v: f32| {
+        if integer {
+            (v.round() as i32).to_string()
+        } else {
+            format!("{:?}", v)
+        }
+    };
+    Some(match property.metadata.step {
+        Some(step) => format!(
+            "hint_range({}, {}, {})",
+            number(min),
+            number(max),
+            number(step)
+        ),
+        None => format!("hint_range({}, {})", number(min), number(max)),
+    })
+}
+
+/// JSDoc-style comment documenting a property's metadata, or an empty
+/// string if it has none. Godot also reads these as uniform documentation.
+fn doc_comment(property: &ShaderProperty, indent: &str) -> String {
+    let metadata = &property.metadata;
+    let mut lines: Vec<String> = Vec::new();
+    if !metadata.display_name.is_empty() {
+        lines.push(metadata.display_name.clone());
+    }
+    lines.extend(metadata.description.lines().map(str::to_string));
+    if let Some(min) = metadata.min {
+        lines.push(format!("@min {}", min));
+    }
+    if let Some(max) = metadata.max {
+        lines.push(format!("@max {}", max));
+    }
+    if let Some(step) = metadata.step {
+        lines.push(format!("@step {}", step));
+    }
+    if metadata.hdr && matches!(property.value, crate::common::PropertyValue::Color(_)) {
+        lines.push("@hdr".to_string());
+    }
+    if !metadata.group.is_empty() {
+        lines.push(format!("@group {}", metadata.group));
+    }
+    if lines.is_empty() {
+        return String::new();
+    }
+
+    let mut comment = format!("{}/**\n", indent);
+    for line in lines {
+        if line.is_empty() {
+            comment.push_str(&format!("{} *\n", indent));
+        } else {
+            // Keep user text from closing the comment early
+            comment.push_str(&format!("{} * {}\n", indent, line.replace("*/", "* /")));
+        }
+    }
+    comment.push_str(&format!("{} */\n", indent));
+    comment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::PropertyValue;
+    use crate::file::project::PropertyMetadata;
+    use crate::nodes::register_builtins;
+
+    /// Adds `node` to the project with its first output connected to the
+    /// surface `input`.
+    fn connect_to_surface(project: &mut UmbraProject, node: UmbraNode, input: usize) {
+        let snarl = &mut project.graph.snarl;
+        let (output, _) = snarl
+            .node_ids()
+            .find(|(_, node)| node.role() == NodeRole::SurfaceOutput)
+            .unwrap();
+        let node = snarl.insert_node(egui::pos2(0.0, 0.0), node);
+        snarl.connect(
+            OutPinId { node, output: 0 },
+            InPinId {
+                node: output,
+                input,
+            },
+        );
+    }
+
+    /// New project with a Color node of `color` connected to Albedo.
+    fn albedo_project(color: [f32; 4]) -> UmbraProject {
+        let mut project = UmbraProject::new();
+        let mut node = UmbraNode::new("umbra.input.color");
+        node.properties
+            .insert("value".to_string(), PropertyValue::Color(color));
+        connect_to_surface(&mut project, node, surface::ALBEDO);
+        project
+    }
+
+    #[test]
+    fn coerce_splats_truncates_and_pads() {
+        assert_eq!(coerce("x", 3, "Vec3", TargetFormat::Wgsl), "x");
+        assert_eq!(coerce("x", 1, "Vec3", TargetFormat::Wgsl), "vec3<f32>(x)");
+        assert_eq!(coerce("x", 1, "Vec2", TargetFormat::Godot), "vec2(x)");
+        // HLSL splats with a cast
+        assert_eq!(coerce("x", 1, "Vec3", TargetFormat::Unity), "((float3)(x))");
+        assert_eq!(coerce("x", 4, "Vec2", TargetFormat::Wgsl), "(x).xy");
+        assert_eq!(
+            coerce("x", 2, "Vec3", TargetFormat::Wgsl),
+            "vec3<f32>(x, 0.0)"
+        );
+        // Alpha pads to opaque
+        assert_eq!(coerce("x", 3, "Vec4", TargetFormat::Godot), "vec4(x, 1.0)");
+        assert_eq!(
+            coerce("x", 2, "Vec4", TargetFormat::Wgsl),
+            "vec4<f32>(x, 0.0, 1.0)"
+        );
+        // Unknown types are left alone
+        assert_eq!(coerce("x", 3, "Texture", TargetFormat::Wgsl), "x");
+    }
+
+    #[test]
+    fn wgsl_lights_the_surface() {
+        register_builtins();
+        let wgsl = Evaluator::evaluate(&albedo_project([1.0; 4]), TargetFormat::Wgsl);
+        assert!(wgsl.contains("fn fs_main"));
+        assert!(wgsl.contains("fn distribution_ggx"));
+        assert!(wgsl.contains("uniforms.light_direction"));
+    }
+
+    #[test]
+    fn godot_maps_surface_inputs() {
+        register_builtins();
+        let project = albedo_project([1.0, 0.5, 0.25, 1.0]);
+        let godot = Evaluator::evaluate(&project, TargetFormat::Godot);
+        assert!(godot.contains("shader_type spatial;"));
+        assert!(godot.contains("ALBEDO = "));
+        // Unconnected inputs keep Godot's defaults
+        assert!(!godot.contains("ALPHA = "));
+        assert!(!godot.contains("ALPHA_SCISSOR_THRESHOLD"));
+    }
+
+    #[test]
+    fn shared_helpers_are_emitted_once() {
+        register_builtins();
+        let mut project = UmbraProject::new();
+        for input in [surface::ROUGHNESS, surface::METALLIC] {
+            let noise = UmbraNode::new("umbra.noise.value_noise");
+            connect_to_surface(&mut project, noise, input);
+        }
+        for (format, definition) in [
+            (TargetFormat::Wgsl, "fn umbra_value_noise_2d("),
+            (TargetFormat::Godot, "float umbra_value_noise_2d("),
+        ] {
+            let shader = Evaluator::evaluate(&project, format);
+            assert_eq!(shader.matches(definition).count(), 1, "{}", shader);
+        }
+    }
+
+    #[test]
+    fn edited_values_replace_default_expressions() {
+        register_builtins();
+        let incident = "(-normalize(uniforms.camera_position - in.world_position))";
+        let mut project = UmbraProject::new();
+        let reflect = UmbraNode::new("umbra.lighting.reflect");
+        connect_to_surface(&mut project, reflect.clone(), surface::EMISSION);
+        let wgsl = Evaluator::evaluate(&project, TargetFormat::Wgsl);
+        assert!(wgsl.contains(incident));
+
+        let mut project = UmbraProject::new();
+        let mut reflect = reflect;
+        reflect
+            .input_values
+            .insert("In".to_string(), PropertyValue::Vec3([1.0, 0.0, 0.0]));
+        connect_to_surface(&mut project, reflect, surface::EMISSION);
+        let wgsl = Evaluator::evaluate(&project, TargetFormat::Wgsl);
+        assert!(!wgsl.contains(incident));
+        assert!(wgsl.contains("reflect(normalize(vec3<f32>(1.000, 0.000, 0.000)), "));
+    }
+
+    #[test]
+    fn builtin_nodes_emit_target_code() {
+        register_builtins();
+        let nodes = crate::nodes::registry::global_registry()
+            .read()
+            .unwrap()
+            .list();
+        for node in nodes {
+            if node.role() != NodeRole::Expression || node.outputs().is_empty() {
+                continue;
+            }
+            let mut project = UmbraProject::new();
+            connect_to_surface(&mut project, UmbraNode::new(node.id()), surface::EMISSION);
+            for format in [
+                TargetFormat::Godot,
+                TargetFormat::Unity,
+                TargetFormat::Generic,
+            ] {
+                let shader = Evaluator::evaluate(&project, format);
+                for wgsl in ["<f32>", "f32(", "in.", "uniforms."] {
+                    assert!(
+                        !shader.contains(wgsl),
+                        "{} emits {} for {:?}:\n{}",
+                        node.id(),
+                        wgsl,
+                        format,
+                        shader
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn script_uniforms_follow_the_builtins_read() {
+        register_builtins();
+        let mut project = UmbraProject::new();
+        connect_to_surface(
+            &mut project,
+            UmbraNode::new("umbra.input.mouse"),
+            surface::EMISSION,
+        );
+        connect_to_surface(
+            &mut project,
+            UmbraNode::new("umbra.input.frame_count"),
+            surface::ALPHA,
+        );
+        let godot = Evaluator::evaluate(&project, TargetFormat::Godot);
+        assert!(godot.contains("uniform vec2 mouse;"), "{}", godot);
+        assert!(godot.contains("uniform int frame_count;"), "{}", godot);
+        assert!(!godot.contains("uniform float delta_time;"), "{}", godot);
+        let unity = Evaluator::evaluate(&project, TargetFormat::Unity);
+        assert!(unity.contains("float2 mouse;"), "{}", unity);
+        assert!(unity.contains("int frame_count;"), "{}", unity);
+
+        // Unused values are not declared
+        let godot = Evaluator::evaluate(&albedo_project([1.0; 4]), TargetFormat::Godot);
+        assert!(!godot.contains("mouse"), "{}", godot);
+    }
+
+    #[test]
+    fn int_and_bool_properties_are_read_as_floats() {
+        register_builtins();
+        let mut project = UmbraProject::new();
+        project.add_property("count", PropertyValue::Int(3));
+        project.add_property("enabled", PropertyValue::Bool(true));
+        project.add_property("tint", PropertyValue::Vec3([1.0, 0.5, 0.0]));
+        for (name, input) in [
+            ("count", surface::ROUGHNESS),
+            ("enabled", surface::METALLIC),
+            ("tint", surface::EMISSION),
+        ] {
+            connect_to_surface(&mut project, UmbraNode::property(name), input);
+        }
+
+        let wgsl = Evaluator::evaluate(&project, TargetFormat::Wgsl);
+        assert!(wgsl.contains("= f32(uniforms.count);"), "{}", wgsl);
+        assert!(wgsl.contains("= f32(uniforms.enabled);"), "{}", wgsl);
+        assert!(wgsl.contains("= uniforms.tint;"), "{}", wgsl);
+        for format in [
+            TargetFormat::Godot,
+            TargetFormat::Unity,
+            TargetFormat::Generic,
+        ] {
+            let shader = Evaluator::evaluate(&project, format);
+            assert!(shader.contains("= float(count);"), "{}", shader);
+            assert!(shader.contains("= float(enabled);"), "{}", shader);
+            assert!(shader.contains("= tint;"), "{}", shader);
+        }
+    }
+
+    /// Project with one property of each kind the exports annotate.
+    fn annotated_project() -> UmbraProject {
+        let mut project = UmbraProject::new();
+        project.add_property("roughness", PropertyValue::Float(0.5));
+        project.add_property("steps", PropertyValue::Int(4));
+        project.add_property("glow", PropertyValue::Color([1.0, 0.5, 0.0, 1.0]));
+        project.add_property("tint", PropertyValue::Color([1.0; 4]));
+        project.add_property("flip", PropertyValue::Bool(true));
+        let properties = &mut project.properties;
+        properties[0].metadata = PropertyMetadata {
+            display_name: "Surface Roughness".to_string(),
+            min: Some(0.0),
+            max: Some(1.0),
+            step: Some(0.05),
+            group: "Surface".to_string(),
+            ..Default::default()
+        };
+        properties[1].metadata = PropertyMetadata {
+            min: Some(1.0),
+            max: Some(8.0),
+            ..Default::default()
+        };
+        properties[2].metadata.hdr = true;
+        project
+    }
+
+    #[test]
+    fn godot_uniforms_carry_hints() {
+        let godot = Evaluator::evaluate(&annotated_project(), TargetFormat::Godot);
+        for line in [
+            "uniform int steps : hint_range(1, 8);",
+            "uniform vec4 glow : source_color;",
+            "uniform vec4 tint : source_color;",
+            "uniform bool flip;",
+            "group_uniforms Surface;",
+            "uniform float roughness : hint_range(0.0, 1.0, 0.05);",
+            " * Surface Roughness",
+            " * @hdr",
+        ] {
+            assert!(godot.contains(line), "{}\n{}", line, godot);
+        }
+        // Grouped properties follow the ungrouped ones
+        assert!(
+            godot.find("uniform int steps").unwrap()
+                < godot.find("group_uniforms Surface").unwrap()
+        );
+    }
+
+    #[test]
+    fn unity_properties_block_carries_attributes() {
+        let unity = Evaluator::evaluate(&annotated_project(), TargetFormat::Unity);
+        for line in [
+            "    [IntRange] steps (\"steps\", Range(1, 8)) = 4",
+            "    [HDR] glow (\"glow\", Color) = (1.000, 0.500, 0.000, 1.000)",
+            "    tint (\"tint\", Color) = (1.000, 1.000, 1.000, 1.000)",
+            "    [Toggle] flip (\"flip\", Float) = 1",
+            "    [Header(Surface)]\n    roughness (\"Surface Roughness\", Range(0.0, 1.0)) = 0.500",
+        ] {
+            assert!(unity.contains(line), "{}\n{}", line, unity);
         }
     }
 }
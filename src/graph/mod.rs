@@ -1,204 +1,588 @@
-use crate::nodes::registry::global_registry;
+use crate::common::UniformKind;
+use crate::file::history::Edit;
+use crate::file::project::ShaderProperty;
+use crate::nodes::codegen::type_width;
+use crate::nodes::registry::{InputDefinition, NodeImpl, NodeRole, global_registry};
 use crate::nodes::{PropertyValue, register_builtins};
 use egui::Ui;
 use egui_snarl::{
-    InPin, OutPin, Snarl,
-    ui::{PinInfo, SnarlViewer},
+    InPin, InPinId, NodeId, OutPin, OutPinId, Snarl,
+    ui::{AnyPins, PinInfo, SnarlViewer},
 };
+use palette::{DroppedWire, NodePalette};
 use std::collections::HashMap;
+use std::sync::Arc;
 
+pub mod builtins;
 pub mod eval;
+pub mod palette;
+pub mod surface;
 
+/// A node in the graph: the ID of its registry implementation and the
+/// values of its properties.
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
-pub enum UmbraNode {
+#[serde(from = "SavedNode", into = "SavedNode")]
+pub struct UmbraNode {
+    /// Registry ID, e.g. `umbra.math.add`.
+    pub type_name: String,
+    /// Version of the implementation the node was created with.
+    pub version: u32,
+    pub properties: HashMap<String, PropertyValue>,
+    /// Values edited next to unconnected input pins, by input name. Inputs
+    /// without one use their definition's value.
+    pub input_values: HashMap<String, PropertyValue>,
+}
+
+/// Saved form of a node. Projects written before every node came from the
+/// registry also hold the builtin variants, which are migrated on load.
+#[derive(serde::Serialize, serde::Deserialize)]
+enum SavedNode {
     Generic {
         type_name: String,
+        #[serde(default = "first_version")]
+        version: u32,
         properties: HashMap<String, PropertyValue>,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        input_values: HashMap<String, PropertyValue>,
     },
     Float(f32),
     Color(f32, f32, f32, f32),
     Float4(f32, f32, f32, f32),
     Property(String),
     VertexOutput,
+    /// Output of projects written before the surface output, whose single
+    /// Vec4 input held the color and its alpha.
     FragmentOutput,
+    SurfaceOutput,
     Position,
 }
 
+impl From<SavedNode> for UmbraNode {
+    fn from(saved: SavedNode) -> Self {
+        match saved {
+            SavedNode::Generic {
+                type_name,
+                version,
+                properties,
+                input_values,
+            } => {
+                let registry = global_registry().read().unwrap();
+                if let Some(impl_node) = registry.get(&type_name)
+                    && version > impl_node.version()
+                {
+                    log::warn!(
+                        "Node {} was saved by version {}, newer than the available version {}",
+                        type_name,
+                        version,
+                        impl_node.version()
+                    );
+                }
+                UmbraNode {
+                    type_name: registry.resolve(&type_name).to_string(),
+                    version,
+                    properties,
+                    input_values,
+                }
+            }
+            SavedNode::Float(v) => UmbraNode::with_value(FLOAT, PropertyValue::Float(v)),
+            SavedNode::Color(r, g, b, a) => {
+                UmbraNode::with_value(COLOR, PropertyValue::Color([r, g, b, a]))
+            }
+            SavedNode::Float4(x, y, z, w) => {
+                UmbraNode::with_value(FLOAT4, PropertyValue::Vec4([x, y, z, w]))
+            }
+            SavedNode::Property(name) => UmbraNode::property(&name),
+            SavedNode::VertexOutput => UmbraNode::new(VERTEX_OUTPUT),
+            SavedNode::FragmentOutput => UmbraNode::new(FRAGMENT_OUTPUT),
+            SavedNode::SurfaceOutput => UmbraNode::new(SURFACE_OUTPUT),
+            SavedNode::Position => UmbraNode::new("umbra.input.position"),
+        }
+    }
+}
+
+impl From<UmbraNode> for SavedNode {
+    fn from(node: UmbraNode) -> Self {
+        SavedNode::Generic {
+            type_name: node.type_name,
+            version: node.version,
+            properties: node.properties,
+            input_values: node.input_values,
+        }
+    }
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+// IDs of the builtin nodes the editor creates itself.
+const FLOAT: &str = "umbra.input.float";
+const COLOR: &str = "umbra.input.color";
+const FLOAT4: &str = "umbra.input.float4";
+const PROPERTY: &str = "umbra.input.property";
+const VERTEX_OUTPUT: &str = "umbra.output.vertex_output";
+const SURFACE_OUTPUT: &str = "umbra.output.surface_output";
+const SPLIT: &str = "umbra.channel.split";
+/// Not registered: loading a graph replaces these nodes, see
+/// [`GraphEditor::migrate_fragment_outputs`].
+const FRAGMENT_OUTPUT: &str = "umbra.output.fragment_output";
+
 impl UmbraNode {
+    /// Node of a registered type, given by its ID or an alias, with its
+    /// default properties.
+    pub fn new(type_name: &str) -> Self {
+        let registry = global_registry().read().unwrap();
+        let impl_node = registry.get(type_name);
+        Self {
+            type_name: registry.resolve(type_name).to_string(),
+            version: impl_node.as_ref().map_or(1, |n| n.version()),
+            properties: impl_node
+                .map(|n| {
+                    n.define_properties()
+                        .into_iter()
+                        .map(|p| (p.name, p.value))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            input_values: HashMap::new(),
+        }
+    }
+
+    /// Constant node of a registered type holding `value`.
+    fn with_value(type_name: &str, value: PropertyValue) -> Self {
+        let mut node = Self::new(type_name);
+        node.properties.insert("value".to_string(), value);
+        node
+    }
+
+    /// Property node bound to the named project property.
+    pub fn property(name: &str) -> Self {
+        let mut node = Self::new(PROPERTY);
+        node.properties.insert(
+            "property".to_string(),
+            PropertyValue::Text(name.to_string()),
+        );
+        node
+    }
+
+    /// Display name of the node's type, or its ID if it is not registered.
     pub fn name(&self) -> String {
+        let registry = global_registry().read().unwrap();
+        match registry.get(&self.type_name) {
+            Some(impl_node) => registry.display_name(impl_node.as_ref()),
+            None => self.type_name.clone(),
+        }
+    }
+
+    /// Registry implementation of the node, or `None` if its type is not
+    /// registered, e.g. a plugin node that is no longer loaded.
+    pub fn implementation(&self) -> Option<Arc<dyn NodeImpl>> {
+        global_registry().read().unwrap().get(&self.type_name)
+    }
+
+    pub fn role(&self) -> NodeRole {
+        self.implementation()
+            .map_or(NodeRole::Expression, |n| n.role())
+    }
+
+    /// Name of the project property a Property node reads.
+    pub fn property_name(&self) -> Option<&str> {
+        if self.role() != NodeRole::Property {
+            return None;
+        }
+        self.properties.get("property").and_then(|p| p.as_text())
+    }
+
+    /// Declared type of an output pin, if it is known without evaluating the
+    /// graph. Property nodes take their property's type, so it is unknown here.
+    pub fn output_type(&self, output: usize) -> Option<String> {
+        let impl_node = self.implementation()?;
+        if impl_node.role() == NodeRole::Property {
+            return None;
+        }
+        impl_node.outputs().get(output).map(|o| o.type_name.clone())
+    }
+
+    /// Declared type of an input pin.
+    pub fn input_type(&self, input: usize) -> Option<String> {
+        self.implementation()?
+            .inputs()
+            .get(input)
+            .map(|i| i.type_name.clone())
+    }
+
+    /// Value of a constant node, which can be promoted to a property:
+    /// registry nodes without inputs whose only setting is a shader value,
+    /// e.g. the Float node.
+    pub fn constant_value(&self) -> Option<PropertyValue> {
+        let impl_node = self.implementation()?;
+        if !impl_node.inputs().is_empty() || self.properties.len() != 1 {
+            return None;
+        }
+        let value = self.properties.values().next()?;
+        UniformKind::of(value).map(|_| value.clone())
+    }
+
+    /// Constant node holding a property's value, the reverse of
+    /// [`UmbraNode::constant_value`]. Integers and booleans become floats,
+    /// as the graph reads them.
+    pub fn from_constant(value: &PropertyValue) -> Option<UmbraNode> {
+        match *value {
+            PropertyValue::Float(v) => Some(Self::with_value(FLOAT, PropertyValue::Float(v))),
+            PropertyValue::Int(v) => Some(Self::with_value(FLOAT, PropertyValue::Float(v as f32))),
+            PropertyValue::Bool(v) => Some(Self::with_value(
+                FLOAT,
+                PropertyValue::Float(if v { 1.0 } else { 0.0 }),
+            )),
+            PropertyValue::Color(c) => Some(Self::with_value(COLOR, PropertyValue::Color(c))),
+            PropertyValue::Vec4(v) => Some(Self::with_value(FLOAT4, PropertyValue::Vec4(v))),
+            _ => None,
+        }
+    }
+
+    /// Value an unconnected input reads: the one edited on this node, else
+    /// the input's own.
+    pub fn input_value(&self, input: &InputDefinition) -> Option<PropertyValue> {
+        self.input_values
+            .get(&input.name)
+            .or(input.value.as_ref())
+            .cloned()
+    }
+
+    /// Components of the node's result an output pin reads, for registry
+    /// nodes with several outputs.
+    pub fn output_swizzle(&self, output: usize) -> Option<String> {
+        self.implementation()?
+            .outputs()
+            .get(output)
+            .and_then(|o| o.swizzle.clone())
+    }
+}
+
+pub struct UmbraViewer<'a> {
+    /// Project properties, which give Property nodes their type.
+    pub properties: &'a [ShaderProperty],
+    /// Graph to screen transform of the last frame, used to place dropped
+    /// properties and the add-node palette.
+    pub to_global: Option<egui::emath::TSTransform>,
+    /// Action chosen from a node menu this frame.
+    pub action: Option<GraphAction>,
+    /// Wire dropped on empty space this frame, and where in graph space.
+    pub dropped_wire: Option<(egui::Pos2, DroppedWire)>,
+    /// Edits made to the graph this frame.
+    pub edits: Vec<Edit>,
+}
+
+/// Whether a wire may join an output of type `from` to an input of type
+/// `to`. Matrices only connect to matrix pins of the same size; unknown
+/// types, e.g. of unbound Property nodes, connect to any other pin.
+pub fn pins_compatible(from: Option<&str>, to: Option<&str>) -> bool {
+    let is_matrix = |t: Option<&str>| matches!(t, Some("Mat3" | "Mat4"));
+    !(is_matrix(from) || is_matrix(to)) || from == to
+}
+
+/// Edit requested from the graph that also changes the property list. The
+/// graph returns it from [`GraphEditor::draw`] for the project to apply.
+pub enum GraphAction {
+    /// Replace a constant node with a Property node bound to a new property
+    /// holding its value.
+    PromoteNode(NodeId),
+    /// Feed an unconnected input from a new property.
+    PromoteInput(InPinId),
+    /// Replace a Property node with a constant node holding its value.
+    InlineProperty(NodeId),
+}
+
+impl GraphAction {
+    /// Name of the action as shown in the node menu and the undo history.
+    pub fn name(&self) -> &'static str {
         match self {
-            UmbraNode::Generic { type_name, .. } => type_name.clone(),
-            UmbraNode::Float(_) => "Float".to_string(),
-            UmbraNode::Color(_, _, _, _) => "Color".to_string(),
-            UmbraNode::Float4(_, _, _, _) => "Float4".to_string(),
-            UmbraNode::Property(_) => "Property".to_string(),
-            UmbraNode::VertexOutput => "Vertex Output".to_string(),
-            UmbraNode::FragmentOutput => "Fragment Output".to_string(),
-            UmbraNode::Position => "Position".to_string(),
+            GraphAction::PromoteNode(_) => "Promote to Property",
+            GraphAction::PromoteInput(_) => "Promote Input",
+            GraphAction::InlineProperty(_) => "Inline Property",
         }
     }
 }
 
-pub struct UmbraViewer;
+/// Drag-and-drop payload of a property dragged from the Properties panel.
+pub struct PropertyDrag(pub String);
+
+impl UmbraViewer<'_> {
+    /// Type of an output pin, resolving Property nodes to their property's type.
+    fn output_type(&self, node: &UmbraNode, output: usize) -> Option<String> {
+        match node.property_name() {
+            Some(name) => self
+                .properties
+                .iter()
+                .find(|p| p.name == name)
+                .map(|p| p.value.type_name().to_string()),
+            None => node.output_type(output),
+        }
+    }
 
-impl SnarlViewer<UmbraNode> for UmbraViewer {
+    /// Picker binding a Property node to a project property.
+    fn show_property_picker(
+        &self,
+        ui: &mut Ui,
+        node_id: NodeId,
+        node: &mut UmbraNode,
+        output_type: Option<&str>,
+    ) {
+        let Some(PropertyValue::Text(name)) = node.properties.get_mut("property") else {
+            return;
+        };
+        egui::ComboBox::from_id_salt((node_id, "property"))
+            .selected_text(if name.is_empty() {
+                "Select..."
+            } else {
+                name.as_str()
+            })
+            .show_ui(ui, |ui| {
+                for property in self.properties {
+                    if UniformKind::of(&property.value).is_some() {
+                        ui.selectable_value(name, property.name.clone(), &property.name);
+                    }
+                }
+            });
+        if output_type.is_none() {
+            let message = if name.is_empty() {
+                "No property selected".to_string()
+            } else {
+                format!("Property '{}' does not exist", name)
+            };
+            ui.colored_label(ui.visuals().warn_fg_color, message);
+        }
+    }
+}
+
+impl SnarlViewer<UmbraNode> for UmbraViewer<'_> {
     fn title(&mut self, node: &UmbraNode) -> String {
         node.name()
     }
 
     fn inputs(&mut self, node: &UmbraNode) -> usize {
-        match node {
-            UmbraNode::Generic { type_name, .. } => {
-                if let Some(impl_node) = global_registry().read().unwrap().get(type_name) {
-                    impl_node.inputs().len()
-                } else {
-                    0
-                }
-            }
-            UmbraNode::Float(_)
-            | UmbraNode::Property(_)
-            | UmbraNode::Color(_, _, _, _)
-            | UmbraNode::Float4(_, _, _, _)
-            | UmbraNode::Position => 0,
-            UmbraNode::VertexOutput => 1,
-            UmbraNode::FragmentOutput => 1,
-        }
+        node.implementation().map_or(0, |n| n.inputs().len())
     }
 
     fn outputs(&mut self, node: &UmbraNode) -> usize {
-        match node {
-            UmbraNode::Generic { type_name, .. } => {
-                if let Some(impl_node) = global_registry().read().unwrap().get(type_name) {
-                    impl_node.outputs().len()
-                } else {
-                    0
-                }
-            }
-            UmbraNode::Float(_)
-            | UmbraNode::Color(_, _, _, _)
-            | UmbraNode::Float4(_, _, _, _)
-            | UmbraNode::Property(_)
-            | UmbraNode::Position => 1,
-            UmbraNode::VertexOutput | UmbraNode::FragmentOutput => 0,
-        }
+        node.implementation().map_or(0, |n| n.outputs().len())
     }
 
-    #[allow(refining_impl_trait)]
     #[allow(refining_impl_trait)]
     fn show_input(&mut self, pin: &InPin, ui: &mut Ui, snarl: &mut Snarl<UmbraNode>) -> PinInfo {
-        let node = &snarl[pin.id.node];
-        match node {
-            UmbraNode::Generic { type_name, .. } => {
-                if let Some(impl_node) = global_registry().read().unwrap().get(type_name) {
-                    let inputs = impl_node.inputs();
-                    if let Some(input) = inputs.get(pin.id.input) {
-                        ui.label(&input.name);
-                        PinInfo::circle().with_fill(type_color(&input.type_name))
-                    } else {
-                        PinInfo::circle()
+        let inputs = snarl[pin.id.node]
+            .implementation()
+            .map(|n| n.inputs())
+            .unwrap_or_default();
+        match inputs.get(pin.id.input) {
+            Some(input) => {
+                ui.label(&input.name);
+                let node = &mut snarl[pin.id.node];
+                if pin.remotes.is_empty()
+                    && let Some(mut value) = node.input_value(input)
+                {
+                    let edited = node.input_values.contains_key(&input.name);
+                    match &input.default {
+                        // The default expression applies until a value is set
+                        Some(default) if !edited => {
+                            let set = ui
+                                .small_button("Set")
+                                .on_hover_text(format!("Default: {}", default));
+                            if set.clicked() {
+                                let before = node.clone();
+                                node.input_values.insert(input.name.clone(), value);
+                                self.edits.push(Edit::ChangeNode {
+                                    node: pin.id.node,
+                                    before,
+                                });
+                            }
+                        }
+                        default => {
+                            if crate::ui::widgets::property_editor(
+                                ui,
+                                pin.id,
+                                &mut value,
+                                Default::default(),
+                            ) {
+                                let before = node.clone();
+                                node.input_values.insert(input.name.clone(), value);
+                                self.edits.push(Edit::ChangeNode {
+                                    node: pin.id.node,
+                                    before,
+                                });
+                            }
+                            if default.is_some()
+                                && ui.small_button("✖").on_hover_text("Use default").clicked()
+                            {
+                                let before = node.clone();
+                                node.input_values.remove(&input.name);
+                                self.edits.push(Edit::ChangeNode {
+                                    node: pin.id.node,
+                                    before,
+                                });
+                            }
+                        }
                     }
-                } else {
-                    PinInfo::circle()
                 }
+                PinInfo::circle().with_fill(type_color(&input.type_name))
             }
-            UmbraNode::VertexOutput => {
-                ui.label("Position Offset");
-                PinInfo::circle().with_fill(type_color("Vec3"))
-            }
-            UmbraNode::FragmentOutput => {
-                ui.label("Base Color");
-                PinInfo::circle().with_fill(type_color("Vec4"))
-            }
-            _ => PinInfo::circle(),
+            None => PinInfo::circle(),
         }
     }
 
     #[allow(refining_impl_trait)]
     fn show_output(&mut self, pin: &OutPin, ui: &mut Ui, snarl: &mut Snarl<UmbraNode>) -> PinInfo {
+        let output_type = self.output_type(&snarl[pin.id.node], pin.id.output);
         let node = &mut snarl[pin.id.node];
-        match node {
-            UmbraNode::Generic {
-                type_name,
-                properties,
-            } => {
-                if let Some(impl_node) = global_registry().read().unwrap().get(type_name) {
-                    let outputs = impl_node.outputs();
-                    if let Some(output) = outputs.get(pin.id.output) {
-                        ui.label(&output.name);
-
-                        // Property rendering logic on first output pin
-                        if pin.id.output == 0 {
-                            for prop_def in impl_node.define_properties() {
-                                if let Some(val) = properties.get_mut(&prop_def.name) {
-                                    match val {
-                                        PropertyValue::Float(v) => {
-                                            ui.horizontal(|ui| {
-                                                ui.label(&prop_def.name);
-                                                ui.add(egui::DragValue::new(v));
-                                            });
-                                        }
-                                        PropertyValue::Color(c) => {
-                                            crate::ui::widgets::labeled_color_picker(
-                                                ui,
-                                                &prop_def.name,
-                                                c,
-                                            );
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
+        let Some(impl_node) = node.implementation() else {
+            return PinInfo::circle();
+        };
+
+        if impl_node.role() == NodeRole::Property {
+            let before = node.clone();
+            self.show_property_picker(ui, pin.id.node, node, output_type.as_deref());
+            if node.properties != before.properties {
+                self.edits.push(Edit::ChangeNode {
+                    node: pin.id.node,
+                    before,
+                });
+            }
+            let color = match output_type {
+                Some(type_name) => type_color(&type_name),
+                None => egui::Color32::from_rgb(100, 100, 200),
+            };
+            return PinInfo::circle().with_fill(color);
+        }
+
+        let outputs = impl_node.outputs();
+        let Some(output) = outputs.get(pin.id.output) else {
+            return PinInfo::circle();
+        };
+        ui.label(&output.name);
+
+        PinInfo::circle().with_fill(type_color(&output.type_name))
+    }
+
+    fn has_body(&mut self, node: &UmbraNode) -> bool {
+        node.role() != NodeRole::Property && !node.properties.is_empty()
+    }
+
+    /// Draws the node's settings, with its own UI if it has one, and the
+    /// error if they do not suit its inputs.
+    fn show_body(
+        &mut self,
+        node_id: NodeId,
+        _inputs: &[InPin],
+        _outputs: &[OutPin],
+        ui: &mut Ui,
+        snarl: &mut Snarl<UmbraNode>,
+    ) {
+        let dynamic_width = known_dynamic_width(snarl, node_id);
+        let node = &mut snarl[node_id];
+        let Some(impl_node) = node.implementation() else {
+            return;
+        };
+        let before = node.properties.clone();
+        let properties = &mut node.properties;
+        if !impl_node.show_ui(ui, properties) {
+            egui::Grid::new(("node_properties", node_id))
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for definition in impl_node.define_properties() {
+                        if let Some(value) = properties.get_mut(&definition.name) {
+                            ui.label(&definition.name);
+                            crate::ui::widgets::property_editor(
+                                ui,
+                                (node_id, &definition.name),
+                                value,
+                                Default::default(),
+                            );
+                            ui.end_row();
                         }
-                        PinInfo::circle().with_fill(type_color(&output.type_name))
-                    } else {
-                        PinInfo::circle()
                     }
-                } else {
-                    PinInfo::circle()
-                }
-            }
-            UmbraNode::Float(val) => {
-                ui.add(egui::DragValue::new(val));
-                PinInfo::circle().with_fill(type_color("Float"))
-            }
-            UmbraNode::Color(r, g, b, a) => {
-                let mut color = egui::Color32::from_rgba_premultiplied(
-                    (*r * 255.0) as u8,
-                    (*g * 255.0) as u8,
-                    (*b * 255.0) as u8,
-                    (*a * 255.0) as u8,
-                );
-                if ui.color_edit_button_srgba(&mut color).changed() {
-                    let [r_new, g_new, b_new, a_new] = color.to_array();
-                    *r = r_new as f32 / 255.0;
-                    *g = g_new as f32 / 255.0;
-                    *b = b_new as f32 / 255.0;
-                    *a = a_new as f32 / 255.0;
-                }
-                PinInfo::circle().with_fill(color)
-            }
-            UmbraNode::Float4(x, y, z, w) => {
-                ui.horizontal(|ui| {
-                    ui.add(egui::DragValue::new(x).speed(0.1));
-                    ui.add(egui::DragValue::new(y).speed(0.1));
-                    ui.add(egui::DragValue::new(z).speed(0.1));
-                    ui.add(egui::DragValue::new(w).speed(0.1));
                 });
-                PinInfo::circle().with_fill(type_color("Vec4"))
-            }
-            UmbraNode::Property(name) => {
-                ui.label(name.as_str());
-                PinInfo::circle().with_fill(egui::Color32::from_rgb(100, 100, 200))
-            }
-            UmbraNode::Position => {
-                ui.label("Mesh Position");
-                PinInfo::circle().with_fill(type_color("Vec3"))
+        }
+        if let Some(width) = dynamic_width
+            && let Err(message) = impl_node.validate(properties, width)
+        {
+            ui.colored_label(ui.visuals().error_fg_color, message);
+        }
+        if node.properties != before {
+            let mut previous = node.clone();
+            previous.properties = before;
+            self.edits.push(Edit::ChangeNode {
+                node: node_id,
+                before: previous,
+            });
+        }
+    }
+
+    fn connect(&mut self, from: &OutPin, to: &InPin, snarl: &mut Snarl<UmbraNode>) {
+        let from_type = self.output_type(&snarl[from.id.node], from.id.output);
+        let to_type = snarl[to.id.node].input_type(to.id.input);
+        if !pins_compatible(from_type.as_deref(), to_type.as_deref()) {
+            return;
+        }
+        if snarl.connect(from.id, to.id) {
+            self.edits.push(Edit::Connect(from.id, to.id));
+        }
+    }
+
+    fn disconnect(&mut self, from: &OutPin, to: &InPin, snarl: &mut Snarl<UmbraNode>) {
+        if snarl.disconnect(from.id, to.id) {
+            self.edits.push(Edit::Disconnect(from.id, to.id));
+        }
+    }
+
+    fn drop_outputs(&mut self, pin: &OutPin, snarl: &mut Snarl<UmbraNode>) {
+        for &to in &pin.remotes {
+            self.edits.push(Edit::Disconnect(pin.id, to));
+        }
+        snarl.drop_outputs(pin.id);
+    }
+
+    fn drop_inputs(&mut self, pin: &InPin, snarl: &mut Snarl<UmbraNode>) {
+        for &from in &pin.remotes {
+            self.edits.push(Edit::Disconnect(from, pin.id));
+        }
+        snarl.drop_inputs(pin.id);
+    }
+
+    fn has_dropped_wire_menu(&mut self, _src_pins: AnyPins, _snarl: &mut Snarl<UmbraNode>) -> bool {
+        true
+    }
+
+    /// Hands the dropped wire to the graph editor, which opens the add-node
+    /// palette for it, instead of showing a menu.
+    fn show_dropped_wire_menu(
+        &mut self,
+        pos: egui::Pos2,
+        ui: &mut Ui,
+        src_pins: AnyPins,
+        snarl: &mut Snarl<UmbraNode>,
+    ) {
+        let wire = match src_pins {
+            AnyPins::Out(pins) => {
+                let first = pins
+                    .first()
+                    .and_then(|p| self.output_type(&snarl[p.node], p.output));
+                DroppedWire::Outputs(pins.to_vec(), first)
             }
-            _ => {
-                ui.label("Out");
-                PinInfo::circle().with_fill(egui::Color32::GRAY)
+            AnyPins::In(pins) => {
+                let first = pins.first().and_then(|p| snarl[p.node].input_type(p.input));
+                DroppedWire::Inputs(pins.to_vec(), first)
             }
-        }
+        };
+        self.dropped_wire = Some((pos, wire));
+        ui.close();
+    }
+
+    fn current_transform(
+        &mut self,
+        to_global: &mut egui::emath::TSTransform,
+        _snarl: &mut Snarl<UmbraNode>,
+    ) {
+        self.to_global = Some(*to_global);
     }
 
     fn has_graph_menu(&mut self, _pos: egui::Pos2, _snarl: &mut Snarl<UmbraNode>) -> bool {
@@ -208,7 +592,9 @@ impl SnarlViewer<UmbraNode> for UmbraViewer {
     #[allow(refining_impl_trait)]
     fn show_graph_menu(&mut self, pos: egui::Pos2, ui: &mut Ui, snarl: &mut Snarl<UmbraNode>) {
         ui.label("Add Node");
-        show_add_node_menu(ui, pos, snarl);
+        if let Some(node) = show_add_node_menu(ui, pos, snarl) {
+            self.edits.push(Edit::AddNode(node));
+        }
     }
 
     fn has_node_menu(&mut self, _node: &UmbraNode) -> bool {
@@ -218,100 +604,349 @@ impl SnarlViewer<UmbraNode> for UmbraViewer {
     fn show_node_menu(
         &mut self,
         node_id: egui_snarl::NodeId,
-        _inputs: &[egui_snarl::InPin],
+        inputs: &[egui_snarl::InPin],
         _outputs: &[egui_snarl::OutPin],
         ui: &mut egui::Ui,
         snarl: &mut egui_snarl::Snarl<UmbraNode>,
     ) {
+        let node = &snarl[node_id];
+        if node.constant_value().is_some() && ui.button("Promote to Property").clicked() {
+            self.action = Some(GraphAction::PromoteNode(node_id));
+            ui.close();
+        }
+        if let Some(impl_node) = node.implementation() {
+            let promotable: Vec<_> = impl_node
+                .inputs()
+                .into_iter()
+                .zip(inputs)
+                .filter(|(input, pin)| pin.remotes.is_empty() && input.property_value().is_some())
+                .collect();
+            if !promotable.is_empty() {
+                ui.menu_button("Promote Input", |ui| {
+                    for (input, pin) in promotable {
+                        if ui.button(&input.name).clicked() {
+                            self.action = Some(GraphAction::PromoteInput(pin.id));
+                            ui.close();
+                        }
+                    }
+                });
+            }
+        }
+        if let Some(name) = node.property_name() {
+            let property = self.properties.iter().find(|p| p.name == name);
+            let inlinable = property.is_some_and(|p| UmbraNode::from_constant(&p.value).is_some());
+            let response = ui.add_enabled(inlinable, egui::Button::new("Inline Property"));
+            if response.clicked() {
+                self.action = Some(GraphAction::InlineProperty(node_id));
+                ui.close();
+            }
+            response.on_disabled_hover_text("No constant node holds this property's type");
+        }
         if ui.button("Delete").clicked() {
-            snarl.remove_node(node_id);
+            self.edits.extend(Edit::remove_node(snarl, node_id));
             ui.close();
         }
     }
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
+#[serde(from = "SavedGraph")]
 pub struct GraphEditor {
     pub snarl: Snarl<UmbraNode>,
 }
 
+/// Saved form of a graph, migrated on load.
+#[derive(serde::Deserialize)]
+struct SavedGraph {
+    snarl: Snarl<UmbraNode>,
+}
+
+impl From<SavedGraph> for GraphEditor {
+    fn from(saved: SavedGraph) -> Self {
+        let mut graph = Self { snarl: saved.snarl };
+        graph.migrate_fragment_outputs();
+        graph
+    }
+}
+
 impl GraphEditor {
     pub fn new() -> Self {
         register_builtins();
         let mut snarl = Snarl::new();
         // Add default output nodes
-        snarl.insert_node(egui::pos2(400.0, 200.0), UmbraNode::VertexOutput);
-        snarl.insert_node(egui::pos2(400.0, 400.0), UmbraNode::FragmentOutput);
+        snarl.insert_node(egui::pos2(400.0, 200.0), UmbraNode::new(VERTEX_OUTPUT));
+        snarl.insert_node(egui::pos2(400.0, 400.0), UmbraNode::new(SURFACE_OUTPUT));
 
         Self { snarl }
     }
 
-    pub fn draw(&mut self, ui: &mut egui::Ui, id_source: impl std::hash::Hash) {
-        let style = egui_snarl::ui::SnarlStyle::new();
-        self.snarl.show(&mut UmbraViewer, &style, id_source, ui);
+    /// Replaces the Fragment Output nodes of old projects by Surface Output
+    /// nodes. The color keeps feeding the first input, now Albedo, and its
+    /// alpha feeds Alpha through a Split node, so transparency is kept.
+    fn migrate_fragment_outputs(&mut self) {
+        let legacy: Vec<(NodeId, egui::Pos2)> = self
+            .snarl
+            .nodes_pos_ids()
+            .filter(|(_, _, node)| node.type_name == FRAGMENT_OUTPUT)
+            .map(|(id, pos, _)| (id, pos))
+            .collect();
+        for (node, pos) in legacy {
+            self.snarl[node] = UmbraNode::new(SURFACE_OUTPUT);
+            let color = self.snarl.in_pin(InPinId { node, input: 0 }).remotes;
+            if let Some(&color) = color.first() {
+                let split = self
+                    .snarl
+                    .insert_node(pos - egui::vec2(160.0, 0.0), UmbraNode::new(SPLIT));
+                self.snarl.connect(
+                    color,
+                    InPinId {
+                        node: split,
+                        input: 0,
+                    },
+                );
+                self.snarl.connect(
+                    OutPinId {
+                        node: split,
+                        output: 3,
+                    },
+                    InPinId {
+                        node,
+                        input: surface::ALPHA,
+                    },
+                );
+            }
+        }
     }
-}
 
-// Standalone helper for adding nodes context menu
-pub fn show_add_node_menu(ui: &mut egui::Ui, pos: egui::Pos2, snarl: &mut Snarl<UmbraNode>) {
-    let registry = global_registry().read().unwrap();
-    let mut categories: HashMap<String, Vec<std::sync::Arc<dyn crate::nodes::registry::NodeImpl>>> =
-        HashMap::new();
-
-    for node in registry.list() {
-        let cat = node.category();
-        let cat_name = cat.first().cloned().unwrap_or("Uncategorized".to_string());
-        categories.entry(cat_name).or_default().push(node);
-    }
+    /// Draws the graph and returns the action requested from a node menu.
+    /// The edits made to the graph are added to `edits`.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        id_source: impl std::hash::Hash,
+        properties: &[ShaderProperty],
+        edits: &mut Vec<Edit>,
+    ) -> Option<GraphAction> {
+        let style = egui_snarl::ui::SnarlStyle::new();
+        let mut viewer = UmbraViewer {
+            properties,
+            to_global: None,
+            action: None,
+            dropped_wire: None,
+            edits: Vec::new(),
+        };
+        let positions: Vec<(NodeId, egui::Pos2)> = self
+            .snarl
+            .nodes_pos_ids()
+            .map(|(id, pos, _)| (id, pos))
+            .collect();
+        let wires: Vec<(OutPinId, InPinId)> = self.snarl.wires().collect();
+        let drag_id = ui.make_persistent_id(("node_drag", &id_source));
+        self.snarl.show(&mut viewer, &style, id_source, ui);
+        edits.append(&mut viewer.edits);
 
-    let mut sorted_categories: Vec<_> = categories.into_iter().collect();
-    sorted_categories.sort_by(|(a, _), (b, _)| a.cmp(b));
+        // egui-snarl moves nodes and drops the wires picked up with the
+        // command key itself, without asking the viewer
+        for (from, to) in wires {
+            let picked_up = self.snarl.get_node(from.node).is_some()
+                && self.snarl.get_node(to.node).is_some()
+                && !self.snarl.in_pin(to).remotes.contains(&from)
+                && !edits
+                    .iter()
+                    .any(|e| matches!(e, Edit::Disconnect(f, t) if *f == from && *t == to));
+            if picked_up {
+                edits.push(Edit::Disconnect(from, to));
+            }
+        }
+        // A drag is one edit, from where the nodes were when it started
+        let mut moves: Vec<(NodeId, egui::Pos2, egui::Pos2)> =
+            ui.data_mut(|d| d.remove_temp(drag_id)).unwrap_or_default();
+        for (node, old) in positions {
+            let Some(new) = self.snarl.get_node_info(node).map(|n| n.pos) else {
+                continue;
+            };
+            if new == old {
+                continue;
+            }
+            match moves.iter_mut().find(|(id, _, _)| *id == node) {
+                Some((_, _, to)) => *to = new,
+                None => moves.push((node, old, new)),
+            }
+        }
+        if !moves.is_empty() {
+            if ui.input(|i| i.pointer.any_down()) {
+                ui.data_mut(|d| d.insert_temp(drag_id, moves));
+            } else {
+                edits.push(Edit::MoveNodes(moves));
+            }
+        }
 
-    for (category, mut nodes) in sorted_categories {
-        nodes.sort_by(|a, b| a.name().cmp(b.name()));
+        // A property dropped from the Properties panel becomes a node bound to it
+        let released = ui.input(|i| i.pointer.any_released());
+        if released
+            && let Some(to_global) = viewer.to_global
+            && let Some(pointer) = ui.ctx().pointer_interact_pos()
+            && ui.max_rect().contains(pointer)
+            && let Some(payload) = egui::DragAndDrop::take_payload::<PropertyDrag>(ui.ctx())
+        {
+            let pos = to_global.inverse() * pointer;
+            let node = self.snarl.insert_node(pos, UmbraNode::property(&payload.0));
+            edits.push(Edit::AddNode(node));
+        }
 
-        ui.menu_button(&category, |ui| {
-            for node in nodes {
-                if ui.button(node.name()).clicked() {
-                    let mut properties = HashMap::new();
-                    for prop in node.define_properties() {
-                        properties.insert(prop.name, prop.value);
+        // Space or Tab over the graph opens the add-node palette at the cursor
+        let ctx = ui.ctx().clone();
+        let mut palette = NodePalette::load(&ctx);
+        if !palette.is_open()
+            && let Some(to_global) = viewer.to_global
+            && let Some(pointer) = ctx.pointer_hover_pos()
+            && ui.max_rect().contains(pointer)
+            && ctx.memory(|m| m.focused().is_none())
+            && ui.input_mut(|i| {
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Space)
+                    || i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)
+            })
+        {
+            palette.open(pointer, to_global.inverse() * pointer);
+        }
+        if let Some((pos, wire)) = viewer.dropped_wire
+            && let Some(to_global) = viewer.to_global
+        {
+            palette.open_for_wire(to_global * pos, pos, wire);
+        }
+        if let Some(chosen) = palette.show(&ctx) {
+            let node = self
+                .snarl
+                .insert_node(chosen.pos, UmbraNode::new(&chosen.id));
+            edits.push(Edit::AddNode(node));
+            match chosen.wire {
+                Some((DroppedWire::Outputs(pins, _), input)) => {
+                    // An input takes a single wire
+                    if let Some(&from) = pins.first() {
+                        let to = InPinId { node, input };
+                        self.snarl.connect(from, to);
+                        edits.push(Edit::Connect(from, to));
+                    }
+                }
+                Some((DroppedWire::Inputs(pins, _), output)) => {
+                    let from = OutPinId { node, output };
+                    for to in pins {
+                        for remote in self.snarl.in_pin(to).remotes {
+                            edits.push(Edit::Disconnect(remote, to));
+                        }
+                        self.snarl.drop_inputs(to);
+                        self.snarl.connect(from, to);
+                        edits.push(Edit::Connect(from, to));
                     }
-                    snarl.insert_node(
-                        pos,
-                        UmbraNode::Generic {
-                            type_name: node.name().to_string(),
-                            properties,
-                        },
-                    );
-                    ui.close();
                 }
+                None => {}
             }
-        });
+        }
+        palette.store(&ctx);
+        viewer.action
     }
+}
 
-    ui.separator();
+/// Registry nodes grouped by their category path.
+#[derive(Default)]
+struct CategoryTree {
+    children: std::collections::BTreeMap<String, CategoryTree>,
+    /// Display names and IDs of the nodes in this category.
+    nodes: Vec<(String, String)>,
+}
 
-    if ui.button("Float").clicked() {
-        snarl.insert_node(pos, UmbraNode::Float(0.0));
-        ui.close();
-    }
-    if ui.button("Float4").clicked() {
-        snarl.insert_node(pos, UmbraNode::Float4(0.0, 0.0, 0.0, 0.0));
-        ui.close();
+impl CategoryTree {
+    fn insert(&mut self, path: &[String], node: (String, String)) {
+        match path.split_first() {
+            Some((first, rest)) => self
+                .children
+                .entry(first.clone())
+                .or_default()
+                .insert(rest, node),
+            None => self.nodes.push(node),
+        }
     }
-    if ui.button("Color").clicked() {
-        snarl.insert_node(pos, UmbraNode::Color(1.0, 1.0, 1.0, 1.0));
-        ui.close();
+
+    /// Draws the category submenus and node buttons. Returns the node
+    /// inserted.
+    fn show(
+        &self,
+        ui: &mut egui::Ui,
+        pos: egui::Pos2,
+        snarl: &mut Snarl<UmbraNode>,
+    ) -> Option<NodeId> {
+        let mut inserted = None;
+        for (category, child) in &self.children {
+            if let Some(node) = ui
+                .menu_button(category, |ui| child.show(ui, pos, snarl))
+                .inner
+            {
+                inserted = node;
+            }
+        }
+        for (name, id) in &self.nodes {
+            if ui.button(name).clicked() {
+                inserted = Some(snarl.insert_node(pos, UmbraNode::new(id)));
+                NodePalette::remember(ui.ctx(), id);
+                ui.close();
+            }
+        }
+        inserted
     }
-    if ui.button("Property").clicked() {
-        snarl.insert_node(pos, UmbraNode::Property("unnamed".to_string()));
-        ui.close();
+}
+
+// Standalone helper for adding nodes context menu, returns the node inserted
+pub fn show_add_node_menu(
+    ui: &mut egui::Ui,
+    pos: egui::Pos2,
+    snarl: &mut Snarl<UmbraNode>,
+) -> Option<NodeId> {
+    let registry = global_registry().read().unwrap();
+    let mut tree = CategoryTree::default();
+    // Graphs have exactly one of each output node
+    for node in registry
+        .list()
+        .into_iter()
+        .filter(|n| !n.role().is_output())
+    {
+        let mut path = node.category();
+        if path.is_empty() {
+            path.push("Uncategorized".to_string());
+        }
+        tree.insert(
+            &path,
+            (registry.display_name(node.as_ref()), node.id().to_string()),
+        );
     }
-    if ui.button("Position").clicked() {
-        snarl.insert_node(pos, UmbraNode::Position);
-        ui.close();
+    drop(registry);
+    tree.show(ui, pos, snarl)
+}
+
+/// Width a registry node's dynamic pins resolve to, if every connected
+/// dynamic input has a known width without evaluating the graph.
+fn known_dynamic_width(snarl: &Snarl<UmbraNode>, node_id: egui_snarl::NodeId) -> Option<usize> {
+    let impl_node = snarl[node_id].implementation()?;
+
+    let mut width = 1;
+    for (index, input) in impl_node.inputs().iter().enumerate() {
+        match input.type_name.as_str() {
+            "Vector" => width = width.max(2),
+            "Dynamic" => {}
+            _ => continue,
+        }
+        let in_pin = snarl.in_pin(egui_snarl::InPinId {
+            node: node_id,
+            input: index,
+        });
+        if let Some(remote) = in_pin.remotes.first() {
+            let remote_width = match snarl[remote.node].output_swizzle(remote.output) {
+                Some(swizzle) => swizzle.len(),
+                None => type_width(&snarl[remote.node].output_type(remote.output)?)?,
+            };
+            width = width.max(remote_width);
+        }
     }
+    Some(width)
 }
 
 // Helper for type colors
@@ -321,6 +956,156 @@ fn type_color(type_name: &str) -> egui::Color32 {
         "Vec2" => egui::Color32::from_rgb(150, 150, 250),  // Pale Blue
         "Vec3" => egui::Color32::from_rgb(250, 150, 250),  // Pinkish
         "Vec4" | "Color" => egui::Color32::from_rgb(250, 200, 150), // Pale Orange
+        "Dynamic" | "Vector" => egui::Color32::from_rgb(220, 220, 220), // Off White
+        "Mat3" | "Mat4" => egui::Color32::from_rgb(120, 210, 210), // Teal
+        "Int" => egui::Color32::from_rgb(120, 180, 120),   // Green
+        "Bool" => egui::Color32::from_rgb(230, 120, 120),  // Pale Red
         _ => egui::Color32::GRAY,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fragment_output_alpha_feeds_surface_alpha() {
+        register_builtins();
+        let mut saved = Snarl::new();
+        let color = saved.insert_node(egui::pos2(0.0, 0.0), SavedNode::Color(1.0, 0.5, 0.0, 0.25));
+        let output = saved.insert_node(egui::pos2(300.0, 0.0), SavedNode::FragmentOutput);
+        saved.connect(
+            OutPinId {
+                node: color,
+                output: 0,
+            },
+            InPinId {
+                node: output,
+                input: 0,
+            },
+        );
+        let json = serde_json::json!({ "snarl": saved });
+
+        let graph: GraphEditor = serde_json::from_value(json).unwrap();
+        let snarl = &graph.snarl;
+        assert_eq!(snarl[output].type_name, SURFACE_OUTPUT);
+        let albedo = snarl.in_pin(InPinId {
+            node: output,
+            input: surface::ALBEDO,
+        });
+        assert_eq!(
+            albedo.remotes,
+            vec![OutPinId {
+                node: color,
+                output: 0
+            }]
+        );
+
+        let alpha = snarl.in_pin(InPinId {
+            node: output,
+            input: surface::ALPHA,
+        });
+        let [split] = alpha.remotes[..] else {
+            panic!("Alpha is not connected");
+        };
+        assert_eq!(snarl[split.node].type_name, SPLIT);
+        assert_eq!(split.output, 3);
+        let split_input = snarl.in_pin(InPinId {
+            node: split.node,
+            input: 0,
+        });
+        assert_eq!(
+            split_input.remotes,
+            vec![OutPinId {
+                node: color,
+                output: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn unconnected_fragment_output_becomes_surface_output() {
+        register_builtins();
+        let mut saved = Snarl::new();
+        let output = saved.insert_node(egui::pos2(0.0, 0.0), SavedNode::FragmentOutput);
+        let json = serde_json::json!({ "snarl": saved });
+
+        let graph: GraphEditor = serde_json::from_value(json).unwrap();
+        assert_eq!(graph.snarl[output].type_name, SURFACE_OUTPUT);
+        assert_eq!(graph.snarl.node_ids().count(), 1);
+    }
+
+    #[test]
+    fn category_paths_nest() {
+        let mut tree = CategoryTree::default();
+        let path = |p: &str| p.split('/').map(str::to_string).collect::<Vec<_>>();
+        tree.insert(&path("Math/Basic"), ("Add".into(), "umbra.math.add".into()));
+        tree.insert(
+            &path("Math/Basic"),
+            ("Multiply".into(), "umbra.math.multiply".into()),
+        );
+        tree.insert(&path("Math"), ("Lerp".into(), "umbra.math.lerp".into()));
+        tree.insert(&path("Noise"), ("FBM".into(), "umbra.noise.fbm".into()));
+
+        assert_eq!(tree.children.keys().collect::<Vec<_>>(), ["Math", "Noise"]);
+        let math = &tree.children["Math"];
+        assert_eq!(
+            math.nodes,
+            [("Lerp".to_string(), "umbra.math.lerp".to_string())]
+        );
+        assert_eq!(math.children["Basic"].nodes.len(), 2);
+        assert!(tree.nodes.is_empty());
+    }
+
+    #[test]
+    fn legacy_variants_load_as_registry_nodes() {
+        register_builtins();
+        let load = |json: serde_json::Value| serde_json::from_value::<UmbraNode>(json).unwrap();
+
+        let float = load(serde_json::json!({ "Float": 0.5 }));
+        assert_eq!(float.type_name, FLOAT);
+        assert_eq!(float.constant_value(), Some(PropertyValue::Float(0.5)));
+
+        let color = load(serde_json::json!({ "Color": [1.0, 0.5, 0.0, 0.25] }));
+        assert_eq!(color.type_name, COLOR);
+        assert_eq!(
+            color.constant_value(),
+            Some(PropertyValue::Color([1.0, 0.5, 0.0, 0.25]))
+        );
+
+        let float4 = load(serde_json::json!({ "Float4": [1.0, 2.0, 3.0, 4.0] }));
+        assert_eq!(float4.type_name, FLOAT4);
+        assert_eq!(
+            float4.constant_value(),
+            Some(PropertyValue::Vec4([1.0, 2.0, 3.0, 4.0]))
+        );
+
+        let property = load(serde_json::json!({ "Property": "speed" }));
+        assert_eq!(property.type_name, PROPERTY);
+        assert_eq!(property.property_name(), Some("speed"));
+
+        for (variant, type_name) in [
+            ("VertexOutput", VERTEX_OUTPUT),
+            ("SurfaceOutput", SURFACE_OUTPUT),
+            ("FragmentOutput", FRAGMENT_OUTPUT),
+            ("Position", "umbra.input.position"),
+        ] {
+            assert_eq!(load(serde_json::json!(variant)).type_name, type_name);
+        }
+
+        // Generic nodes saved under an alias, before versions were recorded
+        let add = load(serde_json::json!({
+            "Generic": { "type_name": "Add", "properties": {} }
+        }));
+        assert_eq!(add.type_name, "umbra.math.add");
+        assert_eq!(add.version, 1);
+    }
+
+    #[test]
+    fn nodes_are_saved_as_generic() {
+        register_builtins();
+        let json = serde_json::to_value(UmbraNode::property("speed")).unwrap();
+        assert_eq!(json["Generic"]["type_name"], serde_json::json!(PROPERTY));
+        assert!(json["Generic"].get("input_values").is_none());
+    }
+}
@@ -0,0 +1,391 @@
+//! Searchable add-node palette.
+//!
+//! Opened with Space or Tab over the graph, it lists the registry nodes
+//! matching the typed query by name, category and keywords, recently used
+//! nodes first. Arrow keys move the selection and Enter inserts the selected
+//! node where the palette was opened.
+//!
+//! A wire dropped on empty space opens it too, listing only the nodes with
+//! a pin the wire can connect to, and the chosen node is connected to it.
+
+use super::pins_compatible;
+use crate::nodes::registry::global_registry;
+use egui_snarl::{InPinId, OutPinId};
+
+/// Number of recently used nodes remembered.
+const RECENT_LIMIT: usize = 8;
+/// Number of matches listed at once.
+const RESULT_LIMIT: usize = 12;
+
+/// A node the palette can insert.
+struct Entry {
+    id: String,
+    name: String,
+    /// Category path joined with `/`, e.g. `Math/Basic`.
+    category: String,
+    keywords: Vec<String>,
+    /// Pin of the node the dropped wire connects to.
+    pin: Option<usize>,
+}
+
+impl Entry {
+    /// How well the entry matches a query; names count most, then
+    /// keywords, then the category.
+    fn score(&self, query: &str) -> Option<i32> {
+        let name = fuzzy_score(query, &self.name).map(|s| s + 8);
+        let keyword = self
+            .keywords
+            .iter()
+            .filter_map(|k| fuzzy_score(query, k))
+            .max()
+            .map(|s| s + 4);
+        let category = fuzzy_score(query, &self.category);
+        name.max(keyword).max(category)
+    }
+}
+
+/// Score of `query` as a case-insensitive subsequence of `text`, or `None`
+/// if it is not one. Runs of consecutive characters and matches at the
+/// start of words score higher, long texts slightly lower.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (next..text.len()).find(|&i| text[i] == c)?;
+        score += 1;
+        if found > 0 && previous == Some(found - 1) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score - text.len() as i32 / 8)
+}
+
+/// A wire dragged from pins and dropped on empty space, with the type of
+/// the first pin if known.
+#[derive(Clone, Debug)]
+pub enum DroppedWire {
+    Outputs(Vec<OutPinId>, Option<String>),
+    Inputs(Vec<InPinId>, Option<String>),
+}
+
+/// A node chosen in the palette.
+pub struct Chosen {
+    pub id: String,
+    /// Where to insert the node, in graph space.
+    pub pos: egui::Pos2,
+    /// The dropped wire and the pin of the new node it connects to.
+    pub wire: Option<(DroppedWire, usize)>,
+}
+
+/// State of the palette, kept in egui's memory so recently used nodes are
+/// remembered across projects.
+#[derive(Clone, Default)]
+pub struct NodePalette {
+    open: bool,
+    /// Set when opened, to focus the search field once.
+    just_opened: bool,
+    query: String,
+    selected: usize,
+    /// Where the palette is shown, on screen.
+    screen_pos: egui::Pos2,
+    /// Where the chosen node is inserted, in graph space.
+    graph_pos: egui::Pos2,
+    /// IDs of recently inserted nodes, most recent first.
+    recent: Vec<String>,
+    /// Wire the palette was opened for.
+    wire: Option<DroppedWire>,
+}
+
+impl NodePalette {
+    fn id() -> egui::Id {
+        egui::Id::new("node_palette")
+    }
+
+    pub fn load(ctx: &egui::Context) -> Self {
+        ctx.data(|d| d.get_temp(Self::id())).unwrap_or_default()
+    }
+
+    pub fn store(self, ctx: &egui::Context) {
+        ctx.data_mut(|d| d.insert_temp(Self::id(), self));
+    }
+
+    /// Records that a node was inserted, by the palette or the menu.
+    pub fn remember(ctx: &egui::Context, id: &str) {
+        let mut palette = Self::load(ctx);
+        palette.push_recent(id);
+        palette.store(ctx);
+    }
+
+    fn push_recent(&mut self, id: &str) {
+        self.recent.retain(|r| r != id);
+        self.recent.insert(0, id.to_string());
+        self.recent.truncate(RECENT_LIMIT);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opens the palette at a screen position, inserting at `graph_pos`.
+    pub fn open(&mut self, screen_pos: egui::Pos2, graph_pos: egui::Pos2) {
+        self.open = true;
+        self.just_opened = true;
+        self.query.clear();
+        self.selected = 0;
+        self.screen_pos = screen_pos;
+        self.graph_pos = graph_pos;
+        self.wire = None;
+    }
+
+    /// Opens the palette for a wire dropped at `graph_pos`.
+    pub fn open_for_wire(
+        &mut self,
+        screen_pos: egui::Pos2,
+        graph_pos: egui::Pos2,
+        wire: DroppedWire,
+    ) {
+        self.open(screen_pos, graph_pos);
+        self.wire = Some(wire);
+    }
+
+    /// Registry nodes matching the query, and the wire if there is one,
+    /// best first. An empty query lists the recently used nodes, then the
+    /// rest by name.
+    fn matches(&self) -> Vec<Entry> {
+        let registry = global_registry().read().unwrap();
+        // Graphs have exactly one of each output node
+        let entries = registry
+            .list()
+            .into_iter()
+            .filter(|n| !n.role().is_output())
+            .filter_map(|n| {
+                let pin = match &self.wire {
+                    None => None,
+                    Some(DroppedWire::Outputs(_, from)) => Some(
+                        n.inputs()
+                            .iter()
+                            .position(|i| pins_compatible(from.as_deref(), Some(&i.type_name)))?,
+                    ),
+                    Some(DroppedWire::Inputs(_, to)) => Some(
+                        n.outputs()
+                            .iter()
+                            .position(|o| pins_compatible(Some(&o.type_name), to.as_deref()))?,
+                    ),
+                };
+                Some(Entry {
+                    id: n.id().to_string(),
+                    name: registry.display_name(n.as_ref()),
+                    category: n.category().join("/"),
+                    keywords: n.keywords(),
+                    pin,
+                })
+            });
+        let recency = |id: &str| self.recent.iter().position(|r| r == id);
+
+        let mut scored: Vec<(i32, Option<usize>, Entry)> = entries
+            .filter_map(|entry| {
+                let score = if self.query.trim().is_empty() {
+                    0
+                } else {
+                    entry.score(&self.query)?
+                };
+                Some((score, recency(&entry.id), entry))
+            })
+            .collect();
+        // Higher scores first; among equals, recent nodes in order of use.
+        // The list is already sorted by name, which the stable sort keeps.
+        scored.sort_by_key(|(score, recent, _)| {
+            (std::cmp::Reverse(*score), recent.unwrap_or(usize::MAX))
+        });
+        scored.into_iter().map(|(_, _, entry)| entry).collect()
+    }
+
+    /// Draws the palette if open. Returns the node chosen.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Chosen> {
+        if !self.open {
+            return None;
+        }
+
+        let matches = self.matches();
+        let shown = matches.len().min(RESULT_LIMIT);
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if down && self.selected + 1 < shown {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(shown.saturating_sub(1));
+
+        let mut chosen = enter.then_some(self.selected).filter(|&i| i < shown);
+        let area = egui::Area::new(egui::Id::new("node_palette_area"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(self.screen_pos)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(240.0);
+                    let search = ui.add(
+                        egui::TextEdit::singleline(&mut self.query)
+                            .hint_text("Search nodes…")
+                            .desired_width(f32::INFINITY),
+                    );
+                    if self.just_opened {
+                        search.request_focus();
+                        self.just_opened = false;
+                    }
+                    if search.changed() {
+                        self.selected = 0;
+                    }
+                    ui.separator();
+
+                    if shown == 0 {
+                        ui.weak("No matching nodes");
+                    }
+                    for (index, entry) in matches.iter().take(shown).enumerate() {
+                        let recent =
+                            self.query.trim().is_empty() && self.recent.contains(&entry.id);
+                        let row = ui
+                            .horizontal(|ui| {
+                                let label =
+                                    ui.selectable_label(index == self.selected, &entry.name);
+                                if recent {
+                                    ui.weak("recent");
+                                } else {
+                                    ui.weak(&entry.category);
+                                }
+                                label
+                            })
+                            .inner;
+                        if index == self.selected && (up || down) {
+                            row.scroll_to_me(None);
+                        }
+                        if row.clicked() {
+                            chosen = Some(index);
+                        }
+                    }
+                });
+            });
+
+        let clicked_outside = ctx.input(|i| i.pointer.any_pressed())
+            && !ctx
+                .pointer_interact_pos()
+                .is_some_and(|p| area.response.rect.contains(p));
+        if escape || clicked_outside {
+            self.open = false;
+        }
+
+        let entry = matches.into_iter().nth(chosen?)?;
+        self.open = false;
+        self.push_recent(&entry.id);
+        Some(Chosen {
+            id: entry.id,
+            pos: self.graph_pos,
+            wire: self.wire.take().zip(entry.pin),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_prefers_runs_and_word_starts() {
+        assert_eq!(fuzzy_score("xyz", "Multiply"), None);
+        assert_eq!(fuzzy_score("ml", "Lerp"), None);
+        assert!(fuzzy_score("MUL", "multiply").is_some());
+        // Spaces in the query are ignored
+        assert!(fuzzy_score("gradient noise", "GradientNoise").is_some());
+        let run = fuzzy_score("mul", "Multiply").unwrap();
+        let scattered = fuzzy_score("mly", "Multiply").unwrap();
+        assert!(run > scattered);
+        let word_start = fuzzy_score("n", "Value Noise").unwrap();
+        let inside = fuzzy_score("o", "Value Noise").unwrap();
+        assert!(word_start > inside);
+        assert!(fuzzy_score("add", "Add").unwrap() > fuzzy_score("add", "Add Node Group").unwrap());
+    }
+
+    #[test]
+    fn recent_nodes_are_unique_and_bounded() {
+        let mut palette = NodePalette::default();
+        for i in 0..RECENT_LIMIT + 2 {
+            palette.push_recent(&format!("test.node.n{}", i));
+        }
+        palette.push_recent("test.node.n5");
+        assert_eq!(palette.recent.len(), RECENT_LIMIT);
+        assert_eq!(palette.recent[0], "test.node.n5");
+        assert_eq!(
+            palette
+                .recent
+                .iter()
+                .filter(|r| *r == "test.node.n5")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn matches_rank_names_then_keywords() {
+        crate::nodes::register_builtins();
+        let mut palette = NodePalette {
+            query: "multiply".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(palette.matches()[0].id, "umbra.math.multiply");
+        palette.query = "times".to_string();
+        assert_eq!(palette.matches()[0].id, "umbra.math.multiply");
+        // Output nodes are never listed
+        palette.query.clear();
+        let all = palette.matches();
+        assert!(!all.iter().any(|e| e.id == "umbra.output.surface_output"));
+        // An empty query lists recent nodes first
+        palette.push_recent("umbra.math.multiply");
+        assert_eq!(palette.matches()[0].id, "umbra.math.multiply");
+    }
+
+    #[test]
+    fn dropped_wires_list_compatible_nodes() {
+        crate::nodes::register_builtins();
+        let mut palette = NodePalette::default();
+        let pin = |palette: &NodePalette, id: &str| {
+            palette
+                .matches()
+                .into_iter()
+                .find(|e| e.id == id)
+                .map(|e| e.pin)
+        };
+
+        // A matrix output only reaches matrix inputs of the same size
+        palette.wire = Some(DroppedWire::Outputs(Vec::new(), Some("Mat4".to_string())));
+        assert_eq!(pin(&palette, "umbra.math.multiply_mat4"), Some(Some(0)));
+        assert_eq!(pin(&palette, "umbra.math.multiply_mat3"), None);
+        assert_eq!(pin(&palette, "umbra.math.add"), None);
+        // Nodes without inputs cannot take the wire
+        assert_eq!(pin(&palette, "umbra.input.time"), None);
+
+        // A vector input is fed by the first non-matrix output
+        palette.wire = Some(DroppedWire::Inputs(Vec::new(), Some("Vec4".to_string())));
+        assert_eq!(pin(&palette, "umbra.math.add"), Some(Some(0)));
+        assert_eq!(pin(&palette, "umbra.input.time"), Some(Some(0)));
+        assert_eq!(pin(&palette, "umbra.math.transformation_matrix"), None);
+        assert_eq!(pin(&palette, "umbra.math.multiply_mat4"), Some(Some(0)));
+
+        // Unknown types, e.g. of unbound Property nodes, skip matrix pins
+        palette.wire = Some(DroppedWire::Outputs(Vec::new(), None));
+        assert_eq!(pin(&palette, "umbra.math.multiply_mat3"), Some(Some(1)));
+    }
+}
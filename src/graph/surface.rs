@@ -0,0 +1,138 @@
+//! Surface (PBR) output definition.
+//!
+//! The pin layout is shared by the graph UI, which draws the inputs, and the
+//! evaluator, which maps each input onto the lighting model of the target.
+
+use crate::common::PropertyValue;
+
+pub struct SurfaceInput {
+    pub name: &'static str,
+    /// Identifier used for the generated local variable.
+    pub ident: &'static str,
+    pub type_name: &'static str,
+    /// Value used when the pin is left unconnected.
+    pub default: &'static [f32],
+}
+
+impl SurfaceInput {
+    /// Default as a value of the input's type.
+    pub fn default_value(&self) -> PropertyValue {
+        match *self.default {
+            [x, y, z] => PropertyValue::Vec3([x, y, z]),
+            [v, ..] => PropertyValue::Float(v),
+            [] => PropertyValue::Float(0.0),
+        }
+    }
+}
+
+pub const ALBEDO: usize = 0;
+pub const NORMAL: usize = 1;
+pub const METALLIC: usize = 2;
+pub const ROUGHNESS: usize = 3;
+pub const EMISSION: usize = 4;
+pub const AO: usize = 5;
+pub const ALPHA: usize = 6;
+pub const ALPHA_CLIP: usize = 7;
+
+pub const SURFACE_INPUTS: [SurfaceInput; 8] = [
+    SurfaceInput {
+        name: "Albedo",
+        ident: "albedo",
+        type_name: "Vec3",
+        default: &[1.0, 1.0, 1.0],
+    },
+    SurfaceInput {
+        name: "Normal",
+        ident: "normal_ts",
+        type_name: "Vec3",
+        default: &[0.0, 0.0, 1.0],
+    },
+    SurfaceInput {
+        name: "Metallic",
+        ident: "metallic",
+        type_name: "Float",
+        default: &[0.0],
+    },
+    SurfaceInput {
+        name: "Roughness",
+        ident: "roughness",
+        type_name: "Float",
+        default: &[0.5],
+    },
+    SurfaceInput {
+        name: "Emission",
+        ident: "emission",
+        type_name: "Vec3",
+        default: &[0.0, 0.0, 0.0],
+    },
+    SurfaceInput {
+        name: "AO",
+        ident: "ao",
+        type_name: "Float",
+        default: &[1.0],
+    },
+    SurfaceInput {
+        name: "Alpha",
+        ident: "alpha",
+        type_name: "Float",
+        default: &[1.0],
+    },
+    SurfaceInput {
+        name: "Alpha Clip",
+        ident: "alpha_clip",
+        type_name: "Float",
+        default: &[0.0],
+    },
+];
+
+/// Cook-Torrance (GGX / Smith / Schlick) lighting for the WGSL preview.
+///
+/// Expects the `Uniforms` struct to provide `camera_position`,
+/// `light_direction`, `light_color`, `light_intensity` and `ambient_intensity`.
+pub const PBR_WGSL: &str = r#"const PI: f32 = 3.14159265;
+
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+  let a = roughness * roughness;
+  let a2 = a * a;
+  let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+  return a2 / max(PI * d * d, 0.0001);
+}
+
+fn geometry_schlick_ggx(n_dot_x: f32, roughness: f32) -> f32 {
+  let r = roughness + 1.0;
+  let k = (r * r) / 8.0;
+  return n_dot_x / (n_dot_x * (1.0 - k) + k);
+}
+
+fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+  return f0 + (vec3<f32>(1.0) - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// Transforms a tangent-space normal into world space using the
+// interpolated vertex tangent frame.
+fn apply_normal_map(n: vec3<f32>, t: vec3<f32>, b: vec3<f32>, normal_ts: vec3<f32>) -> vec3<f32> {
+  return normalize(t * normal_ts.x + b * normal_ts.y + n * normal_ts.z);
+}
+
+fn shade_surface(albedo: vec3<f32>, n: vec3<f32>, metallic: f32, roughness: f32, ao: f32, world_position: vec3<f32>) -> vec3<f32> {
+  let v = normalize(uniforms.camera_position - world_position);
+  let l = normalize(-uniforms.light_direction);
+  let h = normalize(v + l);
+  let n_dot_l = max(dot(n, l), 0.0);
+  let n_dot_v = max(dot(n, v), 0.0001);
+  let n_dot_h = max(dot(n, h), 0.0);
+  let r = clamp(roughness, 0.04, 1.0);
+
+  let f0 = mix(vec3<f32>(0.04), albedo, metallic);
+  let f = fresnel_schlick(max(dot(h, v), 0.0), f0);
+  let d = distribution_ggx(n_dot_h, r);
+  let g = geometry_schlick_ggx(n_dot_v, r) * geometry_schlick_ggx(n_dot_l, r);
+  let specular = d * g * f / (4.0 * n_dot_v * n_dot_l + 0.0001);
+
+  let k_d = (vec3<f32>(1.0) - f) * (1.0 - metallic);
+  let radiance = uniforms.light_color * uniforms.light_intensity;
+  let direct = (k_d * albedo / PI + specular) * radiance * n_dot_l;
+  let ambient = albedo * uniforms.ambient_intensity * ao;
+  return direct + ambient;
+}
+"#;
@@ -0,0 +1,228 @@
+//! Channel nodes for shader graphs
+//!
+//! These nodes build vectors from floats, split them back into components
+//! and reorder their channels.
+
+use crate::common::PropertyValue;
+use crate::define_node;
+use std::collections::HashMap;
+
+const COMPONENTS: &str = "xyzw";
+const COLOR_COMPONENTS: &str = "rgba";
+
+/// Parses a swizzle mask such as `zyx` or `rgb` into `xyzw` components,
+/// checking each against the width of the vector it reads.
+pub fn parse_mask(mask: &str, input_width: usize) -> Result<String, String> {
+    let mask = mask.trim();
+    if mask.is_empty() {
+        return Err("Mask is empty".to_string());
+    }
+    if mask.chars().count() > 4 {
+        return Err("Mask has more than 4 components".to_string());
+    }
+
+    let set = if mask.chars().all(|c| COMPONENTS.contains(c)) {
+        COMPONENTS
+    } else if mask.chars().all(|c| COLOR_COMPONENTS.contains(c)) {
+        COLOR_COMPONENTS
+    } else {
+        return Err(format!("'{}' is not a valid mask", mask));
+    };
+
+    mask.chars()
+        .map(|c| {
+            let index = set.find(c).unwrap_or_default();
+            if index < input_width {
+                Ok(COMPONENTS.as_bytes()[index] as char)
+            } else {
+                Err(format!(
+                    "'{}' is out of range for a {}-component input",
+                    c, input_width
+                ))
+            }
+        })
+        .collect()
+}
+
+fn mask(properties: &HashMap<String, PropertyValue>) -> &str {
+    properties
+        .get("mask")
+        .and_then(|p| p.as_text())
+        .unwrap_or("yx")
+}
+
+// ============================================================================
+// Combine Nodes - Build a vector from floats
+// ============================================================================
+
+define_node!(
+    CombineVec2Node,
+    id: "umbra.channel.combine_vec2",
+    name: "Combine Vec2",
+    aliases: ["Combine Vec2"],
+    category: "Channel",
+    inputs: [("X", "Float"), ("Y", "Float")],
+    outputs: [("Out", "Vec2")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.vector(inputs)
+);
+
+define_node!(
+    CombineVec3Node,
+    id: "umbra.channel.combine_vec3",
+    name: "Combine Vec3",
+    aliases: ["Combine Vec3"],
+    category: "Channel",
+    inputs: [("X", "Float"), ("Y", "Float"), ("Z", "Float")],
+    outputs: [("Out", "Vec3")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.vector(inputs)
+);
+
+define_node!(
+    CombineVec4Node,
+    id: "umbra.channel.combine_vec4",
+    name: "Combine Vec4",
+    aliases: ["Combine Vec4"],
+    category: "Channel",
+    inputs: [("X", "Float"), ("Y", "Float"), ("Z", "Float"), ("W", "Float", "1.0")],
+    outputs: [("Out", "Vec4")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.vector(inputs)
+);
+
+// ============================================================================
+// Split Node - One output per component
+// ============================================================================
+
+define_node!(
+    SplitNode,
+    id: "umbra.channel.split",
+    name: "Split",
+    aliases: ["Split"],
+    keywords: ["channels", "components"],
+    category: "Channel",
+    inputs: [("In", "Vec4")],
+    outputs: [
+        ("X", "Float", "x"),
+        ("Y", "Float", "y"),
+        ("Z", "Float", "z"),
+        ("W", "Float", "w"),
+    ],
+    properties: [],
+    generate: |_ctx, inputs, _properties| inputs[0].clone()
+);
+
+// ============================================================================
+// Swizzle Node - Reorders or repeats components, e.g. `zyx` or `xxy`
+// ============================================================================
+
+define_node!(
+    SwizzleNode,
+    id: "umbra.channel.swizzle",
+    name: "Swizzle",
+    aliases: ["Swizzle"],
+    keywords: ["mask", "reorder", "channels"],
+    category: "Channel",
+    inputs: [("In", "Vector")],
+    outputs: [("Out", "Dynamic")],
+    properties: [("mask", PropertyValue::Text("yx".to_string()))],
+    generate: |ctx, inputs, properties| {
+        // The evaluator has already validated the mask against the input width
+        let components = parse_mask(mask(properties), ctx.input_width(0)).unwrap_or_default();
+        format!("({}).{}", inputs[0], components)
+    },
+    width: |properties, _dynamic_width| {
+        let width = mask(properties).trim().chars().count();
+        Some(width.clamp(1, 4))
+    },
+    validate: |properties, dynamic_width| {
+        parse_mask(mask(properties), dynamic_width).map(|_| ())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::UmbraProject;
+    use crate::file::export::TargetFormat;
+    use crate::graph::eval::Evaluator;
+    use crate::graph::{UmbraNode, surface};
+    use crate::nodes::registry::{NodeImpl, NodeRole};
+    use egui_snarl::{InPinId, OutPinId};
+
+    #[test]
+    fn masks_are_checked_against_the_input_width() {
+        assert_eq!(parse_mask("zyx", 3).as_deref(), Ok("zyx"));
+        assert_eq!(parse_mask(" bgr ", 4).as_deref(), Ok("zyx"));
+        assert_eq!(parse_mask("xxy", 2).as_deref(), Ok("xxy"));
+        assert!(parse_mask("z", 2).is_err());
+        assert!(parse_mask("a", 3).is_err());
+        // Sets cannot be mixed, and masks hold at most four components
+        assert!(parse_mask("xg", 4).is_err());
+        assert!(parse_mask("xyzwx", 4).is_err());
+        assert!(parse_mask("", 4).is_err());
+    }
+
+    #[test]
+    fn swizzle_width_follows_the_mask() {
+        let properties =
+            HashMap::from([("mask".to_string(), PropertyValue::Text("xxy".to_string()))]);
+        assert_eq!(SwizzleNode.result_width(&properties, 2), Some(3));
+        assert!(SwizzleNode.validate(&properties, 2).is_ok());
+        assert!(SwizzleNode.validate(&properties, 1).is_err());
+    }
+
+    /// Evaluates UV -> Swizzle with `mask` -> Emission.
+    fn swizzled_uv(mask: &str, format: TargetFormat) -> String {
+        crate::nodes::register_builtins();
+        let mut project = UmbraProject::new();
+        let snarl = &mut project.graph.snarl;
+        let (output, _) = snarl
+            .node_ids()
+            .find(|(_, node)| node.role() == NodeRole::SurfaceOutput)
+            .unwrap();
+        let uv = snarl.insert_node(egui::pos2(0.0, 0.0), UmbraNode::new("umbra.input.uv"));
+        let mut swizzle = UmbraNode::new("umbra.channel.swizzle");
+        swizzle
+            .properties
+            .insert("mask".to_string(), PropertyValue::Text(mask.to_string()));
+        let swizzle = snarl.insert_node(egui::pos2(0.0, 0.0), swizzle);
+        snarl.connect(
+            OutPinId {
+                node: uv,
+                output: 0,
+            },
+            InPinId {
+                node: swizzle,
+                input: 0,
+            },
+        );
+        snarl.connect(
+            OutPinId {
+                node: swizzle,
+                output: 0,
+            },
+            InPinId {
+                node: output,
+                input: surface::EMISSION,
+            },
+        );
+        Evaluator::evaluate(&project, format)
+    }
+
+    #[test]
+    fn swizzle_reads_the_masked_components() {
+        let godot = swizzled_uv("yxx", TargetFormat::Godot);
+        assert!(godot.contains("vec3 node_3 = (node_2).yxx;"), "{}", godot);
+
+        // A mask reaching past the input's width yields zero
+        let godot = swizzled_uv("xz", TargetFormat::Godot);
+        assert!(
+            godot.contains("vec2 node_3 = vec2(0.000, 0.000);"),
+            "{}",
+            godot
+        );
+        assert!(!godot.contains(".xz"), "{}", godot);
+    }
+}
@@ -0,0 +1,219 @@
+//! Target-aware code generation for nodes.
+//!
+//! The evaluator hands each node a [`CodegenContext`] describing what it is
+//! generating: the export target, the shader stage and the types the node's
+//! inputs resolved to. Nodes build their expression with its helpers, so
+//! they emit code for every target directly: literals, vector constructors,
+//! function names and builtin values are all spelled for the target.
+
+use super::registry::{ShaderFunction, ShaderStage};
+use crate::file::export::TargetFormat;
+use crate::graph::builtins;
+
+/// What a node is generating code for.
+pub struct CodegenContext<'a> {
+    pub format: TargetFormat,
+    pub stage: ShaderStage,
+    /// Type each input resolved to, e.g. `Vec3` for a dynamic pin fed a
+    /// three-component vector.
+    pub input_types: &'a [String],
+    helpers: Vec<&'static ShaderFunction>,
+    builtins: Vec<&'static str>,
+}
+
+impl<'a> CodegenContext<'a> {
+    pub fn new(format: TargetFormat, stage: ShaderStage, input_types: &'a [String]) -> Self {
+        Self {
+            format,
+            stage,
+            input_types,
+            helpers: Vec::new(),
+            builtins: Vec::new(),
+        }
+    }
+
+    pub fn is_vertex(&self) -> bool {
+        self.stage == ShaderStage::Vertex
+    }
+
+    /// Float constant, e.g. `0.500`.
+    pub fn float(&self, value: f32) -> String {
+        vector_literal(&[value], self.format)
+    }
+
+    /// Float or vector constant, e.g. `float3(1.000, 0.000, 0.000)` on Unity.
+    pub fn literal(&self, values: &[f32]) -> String {
+        vector_literal(values, self.format)
+    }
+
+    /// Vector constructor from component expressions.
+    pub fn vector(&self, components: &[String]) -> String {
+        format!(
+            "{}({})",
+            vector_type(components.len(), self.format),
+            components.join(", ")
+        )
+    }
+
+    /// Conversion of an integer expression to a float.
+    pub fn to_float(&self, expr: &str) -> String {
+        match self.format {
+            TargetFormat::Wgsl => format!("f32({})", expr),
+            _ => format!("float({})", expr),
+        }
+    }
+
+    /// Call of a WGSL builtin function, renamed for the target, e.g. `mix`
+    /// becomes `lerp` on Unity. GLSL has no `saturate`, so it clamps.
+    pub fn call(&self, function: &str, args: &[impl AsRef<str>]) -> String {
+        let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
+        match (function, self.format) {
+            ("saturate", TargetFormat::Godot | TargetFormat::Generic) => {
+                format!("clamp({}, 0.0, 1.0)", args.join(", "))
+            }
+            _ => format!(
+                "{}({})",
+                builtins::function_name(function, self.format),
+                args.join(", ")
+            ),
+        }
+    }
+
+    /// Number of components of an input, e.g. 3 for a `Vec3`. Matrices
+    /// count every element.
+    pub fn input_width(&self, index: usize) -> usize {
+        self.input_types
+            .get(index)
+            .and_then(|t| type_width(t))
+            .unwrap_or(1)
+    }
+
+    /// Target expression of a builtin value, given as the fragment-stage
+    /// WGSL listed in [`builtins::BUILTINS`], e.g. `in.uv`.
+    pub fn builtin(&mut self, wgsl: &str) -> String {
+        builtins::translate(wgsl, self.format, self.is_vertex(), &mut self.builtins)
+    }
+
+    /// Emits a helper function once at the top of the shader and returns
+    /// the name to call it by.
+    pub fn helper(&mut self, function: &'static ShaderFunction) -> &'static str {
+        if !self.helpers.iter().any(|h| h.name == function.name) {
+            self.helpers.push(function);
+        }
+        function.name
+    }
+
+    /// Translates a fragment-stage WGSL expression for the target: builtins
+    /// first, then type and function names. For WGSL the nodes do not
+    /// generate themselves, i.e. library templates and default expressions.
+    pub fn translate(&mut self, wgsl: &str) -> String {
+        builtins::translate_syntax(&self.builtin(wgsl), self.format)
+    }
+
+    /// Helper functions the generated code calls, in the order required,
+    /// and the builtins it reads, by their WGSL.
+    pub fn into_parts(self) -> (Vec<&'static ShaderFunction>, Vec<&'static str>) {
+        (self.helpers, self.builtins)
+    }
+}
+
+/// Number of components of a pin type, or `None` for non-numeric types.
+/// Matrices count every element; integers and booleans are read as floats.
+pub fn type_width(type_name: &str) -> Option<usize> {
+    match type_name {
+        "Float" | "Int" | "Bool" => Some(1),
+        "Vec2" => Some(2),
+        "Vec3" => Some(3),
+        "Vec4" | "Color" => Some(4),
+        "Mat3" => Some(9),
+        "Mat4" => Some(16),
+        _ => None,
+    }
+}
+
+/// Name of the vector type with `width` components on a target.
+pub fn vector_type(width: usize, format: TargetFormat) -> String {
+    match format {
+        TargetFormat::Wgsl => format!("vec{}<f32>", width),
+        TargetFormat::Unity => format!("float{}", width),
+        _ => format!("vec{}", width),
+    }
+}
+
+/// Formats a float or vector constant for the target.
+pub fn vector_literal(values: &[f32], format: TargetFormat) -> String {
+    let components: Vec<String> = values.iter().map(|v| format!("{:.3}", v)).collect();
+    if components.len() == 1 {
+        components[0].clone()
+    } else {
+        format!(
+            "{}({})",
+            vector_type(components.len(), format),
+            components.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literals_use_the_target_types() {
+        assert_eq!(vector_literal(&[0.5], TargetFormat::Unity), "0.500");
+        assert_eq!(
+            vector_literal(&[1.0, 0.0], TargetFormat::Wgsl),
+            "vec2<f32>(1.000, 0.000)"
+        );
+        assert_eq!(
+            vector_literal(&[1.0, 0.0, 0.0], TargetFormat::Godot),
+            "vec3(1.000, 0.000, 0.000)"
+        );
+        assert_eq!(
+            vector_literal(&[0.0; 4], TargetFormat::Unity),
+            "float4(0.000, 0.000, 0.000, 0.000)"
+        );
+    }
+
+    #[test]
+    fn input_width_defaults_to_scalar() {
+        let types = [
+            "Vec3".to_string(),
+            "Mat3".to_string(),
+            "Texture".to_string(),
+        ];
+        let ctx = CodegenContext::new(TargetFormat::Wgsl, ShaderStage::Fragment, &types);
+        assert_eq!(ctx.input_width(0), 3);
+        assert_eq!(ctx.input_width(1), 9);
+        assert_eq!(ctx.input_width(2), 1);
+        assert_eq!(ctx.input_width(3), 1);
+    }
+
+    #[test]
+    fn vertex_builtins_are_translated() {
+        let mut ctx = CodegenContext::new(TargetFormat::Wgsl, ShaderStage::Vertex, &[]);
+        assert_eq!(ctx.builtin("in.uv"), "model.uv");
+        let mut ctx = CodegenContext::new(TargetFormat::Godot, ShaderStage::Fragment, &[]);
+        assert_eq!(ctx.translate("vec2<f32>(in.uv.x, 0.0)"), "vec2(UV.x, 0.0)");
+        assert_eq!(
+            ctx.vector(&["a".to_string(), "b".to_string()]),
+            "vec2(a, b)"
+        );
+    }
+
+    #[test]
+    fn calls_follow_the_target() {
+        let ctx = CodegenContext::new(TargetFormat::Unity, ShaderStage::Fragment, &[]);
+        assert_eq!(ctx.call("mix", &["a", "b", "t"]), "lerp(a, b, t)");
+        assert_eq!(ctx.call("saturate", &["x"]), "saturate(x)");
+        assert_eq!(ctx.to_float("i"), "float(i)");
+
+        let ctx = CodegenContext::new(TargetFormat::Generic, ShaderStage::Fragment, &[]);
+        assert_eq!(ctx.call("saturate", &["x"]), "clamp(x, 0.0, 1.0)");
+        assert_eq!(ctx.call("atan2", &["y", "x"]), "atan(y, x)");
+
+        let ctx = CodegenContext::new(TargetFormat::Wgsl, ShaderStage::Fragment, &[]);
+        assert_eq!(ctx.call("fract", &["x"]), "fract(x)");
+        assert_eq!(ctx.to_float("i"), "f32(i)");
+    }
+}
@@ -0,0 +1,574 @@
+//! Colour nodes for shader graphs
+//!
+//! HSV and sRGB conversions, common adjustments and Photoshop-style blend
+//! modes. Conversions and the blend modes that branch per channel are
+//! emitted once per shader as helpers.
+
+use crate::common::PropertyValue;
+use crate::define_node;
+use crate::nodes::registry::ShaderFunction;
+use std::collections::HashMap;
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+pub static RGB_TO_HSV: ShaderFunction = ShaderFunction {
+    name: "umbra_rgb_to_hsv",
+    deps: &[],
+    wgsl: "fn umbra_rgb_to_hsv(c: vec3<f32>) -> vec3<f32> {
+  let k = vec4<f32>(0.0, -1.0 / 3.0, 2.0 / 3.0, -1.0);
+  let p = mix(vec4<f32>(c.zy, k.wz), vec4<f32>(c.yz, k.xy), step(c.z, c.y));
+  let q = mix(vec4<f32>(p.xyw, c.x), vec4<f32>(c.x, p.yzx), step(p.x, c.x));
+  let d = q.x - min(q.w, q.y);
+  let e = 1.0e-10;
+  return vec3<f32>(abs(q.z + (q.w - q.y) / (6.0 * d + e)), d / (q.x + e), q.x);
+}
+",
+    glsl: RGB_TO_HSV_GLSL,
+    hlsl: "float3 umbra_rgb_to_hsv(float3 c) {
+  float4 k = float4(0.0, -1.0 / 3.0, 2.0 / 3.0, -1.0);
+  float4 p = lerp(float4(c.zy, k.wz), float4(c.yz, k.xy), step(c.z, c.y));
+  float4 q = lerp(float4(p.xyw, c.x), float4(c.x, p.yzx), step(p.x, c.x));
+  float d = q.x - min(q.w, q.y);
+  float e = 1.0e-10;
+  return float3(abs(q.z + (q.w - q.y) / (6.0 * d + e)), d / (q.x + e), q.x);
+}
+",
+    godot: RGB_TO_HSV_GLSL,
+};
+
+const RGB_TO_HSV_GLSL: &str = "vec3 umbra_rgb_to_hsv(vec3 c) {
+  vec4 k = vec4(0.0, -1.0 / 3.0, 2.0 / 3.0, -1.0);
+  vec4 p = mix(vec4(c.zy, k.wz), vec4(c.yz, k.xy), step(c.z, c.y));
+  vec4 q = mix(vec4(p.xyw, c.x), vec4(c.x, p.yzx), step(p.x, c.x));
+  float d = q.x - min(q.w, q.y);
+  float e = 1.0e-10;
+  return vec3(abs(q.z + (q.w - q.y) / (6.0 * d + e)), d / (q.x + e), q.x);
+}
+";
+
+pub static HSV_TO_RGB: ShaderFunction = ShaderFunction {
+    name: "umbra_hsv_to_rgb",
+    deps: &[],
+    wgsl: "fn umbra_hsv_to_rgb(c: vec3<f32>) -> vec3<f32> {
+  let k = vec4<f32>(1.0, 2.0 / 3.0, 1.0 / 3.0, 3.0);
+  let p = abs(fract(c.xxx + k.xyz) * 6.0 - k.www);
+  return c.z * mix(k.xxx, clamp(p - k.xxx, vec3<f32>(0.0), vec3<f32>(1.0)), c.y);
+}
+",
+    glsl: HSV_TO_RGB_GLSL,
+    hlsl: "float3 umbra_hsv_to_rgb(float3 c) {
+  float4 k = float4(1.0, 2.0 / 3.0, 1.0 / 3.0, 3.0);
+  float3 p = abs(frac(c.xxx + k.xyz) * 6.0 - k.www);
+  return c.z * lerp(k.xxx, saturate(p - k.xxx), c.y);
+}
+",
+    godot: HSV_TO_RGB_GLSL,
+};
+
+const HSV_TO_RGB_GLSL: &str = "vec3 umbra_hsv_to_rgb(vec3 c) {
+  vec4 k = vec4(1.0, 2.0 / 3.0, 1.0 / 3.0, 3.0);
+  vec3 p = abs(fract(c.xxx + k.xyz) * 6.0 - k.www);
+  return c.z * mix(k.xxx, clamp(p - k.xxx, 0.0, 1.0), c.y);
+}
+";
+
+/// Rotates the hue of an RGB colour by `shift` turns.
+pub static HUE_SHIFT: ShaderFunction = ShaderFunction {
+    name: "umbra_hue_shift",
+    deps: &[&RGB_TO_HSV, &HSV_TO_RGB],
+    wgsl: "fn umbra_hue_shift(c: vec3<f32>, shift: f32) -> vec3<f32> {
+  let hsv = umbra_rgb_to_hsv(c);
+  return umbra_hsv_to_rgb(vec3<f32>(fract(hsv.x + shift), hsv.yz));
+}
+",
+    glsl: HUE_SHIFT_GLSL,
+    hlsl: "float3 umbra_hue_shift(float3 c, float shift) {
+  float3 hsv = umbra_rgb_to_hsv(c);
+  return umbra_hsv_to_rgb(float3(frac(hsv.x + shift), hsv.yz));
+}
+",
+    godot: HUE_SHIFT_GLSL,
+};
+
+const HUE_SHIFT_GLSL: &str = "vec3 umbra_hue_shift(vec3 c, float shift) {
+  vec3 hsv = umbra_rgb_to_hsv(c);
+  return umbra_hsv_to_rgb(vec3(fract(hsv.x + shift), hsv.yz));
+}
+";
+
+pub static SRGB_TO_LINEAR: ShaderFunction = ShaderFunction {
+    name: "umbra_srgb_to_linear",
+    deps: &[],
+    wgsl: "fn umbra_srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+  let v = max(c, vec3<f32>(0.0));
+  let low = v / 12.92;
+  let high = pow((v + 0.055) / 1.055, vec3<f32>(2.4));
+  return mix(high, low, step(v, vec3<f32>(0.04045)));
+}
+",
+    glsl: SRGB_TO_LINEAR_GLSL,
+    hlsl: "float3 umbra_srgb_to_linear(float3 c) {
+  float3 v = max(c, 0.0);
+  float3 low = v / 12.92;
+  float3 high = pow((v + 0.055) / 1.055, float3(2.4, 2.4, 2.4));
+  return lerp(high, low, step(v, 0.04045));
+}
+",
+    godot: SRGB_TO_LINEAR_GLSL,
+};
+
+const SRGB_TO_LINEAR_GLSL: &str = "vec3 umbra_srgb_to_linear(vec3 c) {
+  vec3 v = max(c, 0.0);
+  vec3 low = v / 12.92;
+  vec3 high = pow((v + 0.055) / 1.055, vec3(2.4));
+  return mix(high, low, step(v, vec3(0.04045)));
+}
+";
+
+pub static LINEAR_TO_SRGB: ShaderFunction = ShaderFunction {
+    name: "umbra_linear_to_srgb",
+    deps: &[],
+    wgsl: "fn umbra_linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+  let v = max(c, vec3<f32>(0.0));
+  let low = v * 12.92;
+  let high = 1.055 * pow(v, vec3<f32>(1.0 / 2.4)) - 0.055;
+  return mix(high, low, step(v, vec3<f32>(0.0031308)));
+}
+",
+    glsl: LINEAR_TO_SRGB_GLSL,
+    hlsl: "float3 umbra_linear_to_srgb(float3 c) {
+  float3 v = max(c, 0.0);
+  float3 low = v * 12.92;
+  float3 high = 1.055 * pow(v, float3(1.0, 1.0, 1.0) / 2.4) - 0.055;
+  return lerp(high, low, step(v, 0.0031308));
+}
+",
+    godot: LINEAR_TO_SRGB_GLSL,
+};
+
+const LINEAR_TO_SRGB_GLSL: &str = "vec3 umbra_linear_to_srgb(vec3 c) {
+  vec3 v = max(c, 0.0);
+  vec3 low = v * 12.92;
+  vec3 high = 1.055 * pow(v, vec3(1.0 / 2.4)) - 0.055;
+  return mix(high, low, step(v, vec3(0.0031308)));
+}
+";
+
+pub static BLEND_OVERLAY: ShaderFunction = ShaderFunction {
+    name: "umbra_blend_overlay",
+    deps: &[],
+    wgsl: "fn umbra_blend_overlay(base: vec3<f32>, blend: vec3<f32>) -> vec3<f32> {
+  let multiply = 2.0 * base * blend;
+  let screen = 1.0 - 2.0 * (1.0 - base) * (1.0 - blend);
+  return mix(multiply, screen, step(vec3<f32>(0.5), base));
+}
+",
+    glsl: BLEND_OVERLAY_GLSL,
+    hlsl: "float3 umbra_blend_overlay(float3 base, float3 blend) {
+  float3 multiply = 2.0 * base * blend;
+  float3 screen = 1.0 - 2.0 * (1.0 - base) * (1.0 - blend);
+  return lerp(multiply, screen, step(0.5, base));
+}
+",
+    godot: BLEND_OVERLAY_GLSL,
+};
+
+const BLEND_OVERLAY_GLSL: &str = "vec3 umbra_blend_overlay(vec3 base, vec3 blend) {
+  vec3 multiply = 2.0 * base * blend;
+  vec3 screen = 1.0 - 2.0 * (1.0 - base) * (1.0 - blend);
+  return mix(multiply, screen, step(vec3(0.5), base));
+}
+";
+
+/// Soft light in the Pegtop form, which has no discontinuity at 0.5.
+pub static BLEND_SOFT_LIGHT: ShaderFunction = ShaderFunction {
+    name: "umbra_blend_soft_light",
+    deps: &[],
+    wgsl: "fn umbra_blend_soft_light(base: vec3<f32>, blend: vec3<f32>) -> vec3<f32> {
+  return (1.0 - 2.0 * blend) * base * base + 2.0 * blend * base;
+}
+",
+    glsl: BLEND_SOFT_LIGHT_GLSL,
+    hlsl: "float3 umbra_blend_soft_light(float3 base, float3 blend) {
+  return (1.0 - 2.0 * blend) * base * base + 2.0 * blend * base;
+}
+",
+    godot: BLEND_SOFT_LIGHT_GLSL,
+};
+
+const BLEND_SOFT_LIGHT_GLSL: &str = "vec3 umbra_blend_soft_light(vec3 base, vec3 blend) {
+  return (1.0 - 2.0 * blend) * base * base + 2.0 * blend * base;
+}
+";
+
+/// Relative luminance weights of linear Rec. 709 primaries.
+const LUMINANCE: [&str; 3] = ["0.2126", "0.7152", "0.0722"];
+
+fn blend_mode() -> PropertyValue {
+    PropertyValue::enumeration(
+        0,
+        &[
+            "Multiply",
+            "Screen",
+            "Overlay",
+            "Soft Light",
+            "Add",
+            "Difference",
+        ],
+    )
+}
+
+fn selected_mode(properties: &HashMap<String, PropertyValue>) -> &str {
+    properties
+        .get("mode")
+        .and_then(|p| p.selected_option())
+        .unwrap_or("Multiply")
+}
+
+// ============================================================================
+// RGB to HSV Node - Hue, saturation and value, each in [0, 1]
+// ============================================================================
+
+define_node!(
+    RgbToHsvNode,
+    id: "umbra.color.rgb_to_hsv",
+    name: "RGB to HSV",
+    aliases: ["RGB to HSV"],
+    category: "Color/Conversion",
+    inputs: [("RGB", "Vec3")],
+    outputs: [("HSV", "Vec3")],
+    properties: [],
+    generate: |ctx, inputs, _properties| {
+        format!("{}({})", ctx.helper(&RGB_TO_HSV), inputs.join(", "))
+    }
+);
+
+// ============================================================================
+// HSV to RGB Node - Inverse of RGB to HSV
+// ============================================================================
+
+define_node!(
+    HsvToRgbNode,
+    id: "umbra.color.hsv_to_rgb",
+    name: "HSV to RGB",
+    aliases: ["HSV to RGB"],
+    category: "Color/Conversion",
+    inputs: [("HSV", "Vec3")],
+    outputs: [("RGB", "Vec3")],
+    properties: [],
+    generate: |ctx, inputs, _properties| {
+        format!("{}({})", ctx.helper(&HSV_TO_RGB), inputs.join(", "))
+    }
+);
+
+// ============================================================================
+// sRGB to Linear Node - Decodes gamma-encoded colours
+// ============================================================================
+
+define_node!(
+    SrgbToLinearNode,
+    id: "umbra.color.srgb_to_linear",
+    name: "sRGB to Linear",
+    aliases: ["sRGB to Linear"],
+    category: "Color/Conversion",
+    inputs: [("In", "Vec3")],
+    outputs: [("Out", "Vec3")],
+    properties: [],
+    generate: |ctx, inputs, _properties| {
+        format!("{}({})", ctx.helper(&SRGB_TO_LINEAR), inputs.join(", "))
+    }
+);
+
+// ============================================================================
+// Linear to sRGB Node - Gamma-encodes linear colours
+// ============================================================================
+
+define_node!(
+    LinearToSrgbNode,
+    id: "umbra.color.linear_to_srgb",
+    name: "Linear to sRGB",
+    aliases: ["Linear to sRGB"],
+    category: "Color/Conversion",
+    inputs: [("In", "Vec3")],
+    outputs: [("Out", "Vec3")],
+    properties: [],
+    generate: |ctx, inputs, _properties| {
+        format!("{}({})", ctx.helper(&LINEAR_TO_SRGB), inputs.join(", "))
+    }
+);
+
+// ============================================================================
+// Hue Shift Node - Rotates the hue, in turns
+// ============================================================================
+
+define_node!(
+    HueShiftNode,
+    id: "umbra.color.hue_shift",
+    name: "Hue Shift",
+    aliases: ["Hue Shift"],
+    category: "Color/Adjustment",
+    inputs: [("In", "Vec3"), ("Shift", "Float")],
+    outputs: [("Out", "Vec3")],
+    properties: [],
+    generate: |ctx, inputs, _properties| {
+        format!("{}({})", ctx.helper(&HUE_SHIFT), inputs.join(", "))
+    }
+);
+
+// ============================================================================
+// Saturation Node - Scales colourfulness; 0 is greyscale
+// ============================================================================
+
+define_node!(
+    SaturationNode,
+    id: "umbra.color.saturation",
+    name: "Saturation",
+    aliases: ["Saturation"],
+    category: "Color/Adjustment",
+    inputs: [("In", "Vec3"), ("Saturation", "Float", "1.0")],
+    outputs: [("Out", "Vec3")],
+    properties: [],
+    generate: |ctx, inputs, _properties| {
+        // Lerps from the luminance towards the colour
+        let weights = ctx.vector(&LUMINANCE.map(str::to_string));
+        let luminance = ctx.call("dot", &[&inputs[0], &weights]);
+        format!(
+            "({} + ({} - {}) * {})",
+            luminance, inputs[0], luminance, inputs[1]
+        )
+    }
+);
+
+// ============================================================================
+// Contrast Node - Scales values about mid-grey
+// ============================================================================
+
+define_node!(
+    ContrastNode,
+    id: "umbra.color.contrast",
+    name: "Contrast",
+    aliases: ["Contrast"],
+    category: "Color/Adjustment",
+    inputs: [("In", "Dynamic"), ("Contrast", "Float", "1.0")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |_ctx, inputs, _properties| {
+        format!("(({} - 0.5) * {} + 0.5)", inputs[0], inputs[1])
+    }
+);
+
+// ============================================================================
+// Brightness Node - Offsets every channel
+// ============================================================================
+
+define_node!(
+    BrightnessNode,
+    id: "umbra.color.brightness",
+    name: "Brightness",
+    aliases: ["Brightness"],
+    category: "Color/Adjustment",
+    inputs: [("In", "Dynamic"), ("Brightness", "Float")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |_ctx, inputs, _properties| {
+        format!("({} + {})", inputs[0], inputs[1])
+    }
+);
+
+// ============================================================================
+// Posterize Node - Quantizes each channel to a number of steps
+// ============================================================================
+
+define_node!(
+    PosterizeNode,
+    id: "umbra.color.posterize",
+    name: "Posterize",
+    aliases: ["Posterize"],
+    keywords: ["quantize", "steps"],
+    category: "Color/Adjustment",
+    inputs: [("In", "Dynamic"), ("Steps", "Float", "4.0")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |_ctx, inputs, _properties| {
+        format!(
+            "(floor({} * {}) / {})",
+            inputs[0], inputs[1], inputs[1]
+        )
+    }
+);
+
+// ============================================================================
+// Invert Node - One minus each channel
+// ============================================================================
+
+define_node!(
+    InvertNode,
+    id: "umbra.color.invert",
+    name: "Invert",
+    aliases: ["Invert"],
+    keywords: ["negative"],
+    category: "Color/Adjustment",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |_ctx, inputs, _properties| {
+        format!("(1.0 - {})", inputs[0])
+    }
+);
+
+// ============================================================================
+// Blend Node - Photoshop-style layer blending with opacity
+// ============================================================================
+
+define_node!(
+    BlendNode,
+    id: "umbra.color.blend",
+    name: "Blend",
+    aliases: ["Blend"],
+    keywords: ["mix", "overlay", "screen"],
+    category: "Color",
+    inputs: [("Base", "Vec3"), ("Blend", "Vec3"), ("Opacity", "Float", "1.0")],
+    outputs: [("Out", "Vec3")],
+    properties: [("mode", blend_mode())],
+    generate: |ctx, inputs, properties| {
+        let (base, blend) = (&inputs[0], &inputs[1]);
+        let blended = match selected_mode(properties) {
+            "Screen" => format!("(1.0 - (1.0 - {}) * (1.0 - {}))", base, blend),
+            "Overlay" => format!("{}({}, {})", ctx.helper(&BLEND_OVERLAY), base, blend),
+            "Soft Light" => format!("{}({}, {})", ctx.helper(&BLEND_SOFT_LIGHT), base, blend),
+            "Add" => format!("({} + {})", base, blend),
+            "Difference" => format!("abs({} - {})", base, blend),
+            _ => format!("({} * {})", base, blend),
+        };
+        ctx.call("mix", &[base, &blended, &inputs[2]])
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::UmbraProject;
+    use crate::file::export::TargetFormat;
+    use crate::graph::eval::Evaluator;
+    use crate::graph::{UmbraNode, surface};
+    use crate::nodes::codegen::CodegenContext;
+    use crate::nodes::registry::{NodeImpl, NodeRole, ShaderStage};
+    use egui_snarl::{InPinId, OutPinId};
+
+    const HELPERS: [&ShaderFunction; 7] = [
+        &RGB_TO_HSV,
+        &HSV_TO_RGB,
+        &HUE_SHIFT,
+        &SRGB_TO_LINEAR,
+        &LINEAR_TO_SRGB,
+        &BLEND_OVERLAY,
+        &BLEND_SOFT_LIGHT,
+    ];
+
+    #[test]
+    fn helpers_share_a_signature_across_targets() {
+        let formats = [
+            TargetFormat::Wgsl,
+            TargetFormat::Godot,
+            TargetFormat::Unity,
+            TargetFormat::Generic,
+        ];
+        for helper in HELPERS {
+            let parameters: Vec<usize> = formats
+                .iter()
+                .map(|&format| {
+                    let source = helper.source(format);
+                    let signature = source.lines().next().unwrap();
+                    assert!(
+                        signature.contains(&format!(" {}(", helper.name)),
+                        "{:?}: {}",
+                        format,
+                        signature
+                    );
+                    signature.split(')').next().unwrap().matches(',').count()
+                })
+                .collect();
+            assert!(
+                parameters.iter().all(|&p| p == parameters[0]),
+                "{}",
+                helper.name
+            );
+        }
+    }
+
+    fn blend(mode: &str, format: TargetFormat) -> (String, Vec<&'static str>) {
+        let mut properties = HashMap::from([("mode".to_string(), blend_mode())]);
+        if let Some(PropertyValue::Enum { selected, options }) = properties.get_mut("mode") {
+            *selected = options.iter().position(|o| o == mode).unwrap();
+        }
+        let inputs = ["a".to_string(), "b".to_string(), "t".to_string()];
+        let mut ctx = CodegenContext::new(format, ShaderStage::Fragment, &[]);
+        let expr = BlendNode.generate(&mut ctx, &inputs, &properties);
+        let helpers = ctx.into_parts().0.iter().map(|h| h.name).collect();
+        (expr, helpers)
+    }
+
+    #[test]
+    fn blend_modes_mix_by_opacity() {
+        assert_eq!(
+            blend("Screen", TargetFormat::Godot),
+            (
+                "mix(a, (1.0 - (1.0 - a) * (1.0 - b)), t)".to_string(),
+                vec![]
+            )
+        );
+        assert_eq!(
+            blend("Overlay", TargetFormat::Unity),
+            (
+                "lerp(a, umbra_blend_overlay(a, b), t)".to_string(),
+                vec!["umbra_blend_overlay"]
+            )
+        );
+        assert_eq!(
+            blend("Soft Light", TargetFormat::Wgsl),
+            (
+                "mix(a, umbra_blend_soft_light(a, b), t)".to_string(),
+                vec!["umbra_blend_soft_light"]
+            )
+        );
+        assert_eq!(
+            blend("Difference", TargetFormat::Generic).0,
+            "mix(a, abs(a - b), t)"
+        );
+    }
+
+    #[test]
+    fn hue_shift_emits_its_dependencies_first() {
+        crate::nodes::register_builtins();
+        let mut project = UmbraProject::new();
+        let snarl = &mut project.graph.snarl;
+        let (output, _) = snarl
+            .node_ids()
+            .find(|(_, node)| node.role() == NodeRole::SurfaceOutput)
+            .unwrap();
+        for (id, input) in [
+            ("umbra.color.hue_shift", surface::EMISSION),
+            ("umbra.color.rgb_to_hsv", surface::ALBEDO),
+        ] {
+            let node = snarl.insert_node(egui::pos2(0.0, 0.0), UmbraNode::new(id));
+            snarl.connect(
+                OutPinId { node, output: 0 },
+                InPinId {
+                    node: output,
+                    input,
+                },
+            );
+        }
+
+        let godot = Evaluator::evaluate(&project, TargetFormat::Godot);
+        let position = |name: &str| {
+            let definition = format!("vec3 {}(", name);
+            assert_eq!(godot.matches(&definition).count(), 1, "{}", godot);
+            godot.find(&definition).unwrap()
+        };
+        assert!(position("umbra_rgb_to_hsv") < position("umbra_hue_shift"));
+        assert!(position("umbra_hsv_to_rgb") < position("umbra_hue_shift"));
+    }
+}
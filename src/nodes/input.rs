@@ -4,6 +4,14 @@
 
 use crate::common::PropertyValue;
 use crate::define_node;
+use crate::file::export::TargetFormat;
+use crate::nodes::registry::{NodeRole, ShaderStage};
+
+// Builtins of the translation table in `graph::builtins`
+const VIEW_NORMAL: &str = "normalize((uniforms.view * vec4<f32>(in.world_normal, 0.0)).xyz)";
+const VIEW_DIRECTION_VIEW: &str =
+    "normalize((uniforms.view * vec4<f32>(uniforms.camera_position - in.world_position, 0.0)).xyz)";
+const VIEW_DIRECTION_WORLD: &str = "normalize(uniforms.camera_position - in.world_position)";
 
 // ============================================================================
 // Time Node - Provides elapsed time uniform
@@ -11,14 +19,15 @@ use crate::define_node;
 
 define_node!(
     TimeNode,
+    id: "umbra.input.time",
     name: "Time",
+    aliases: ["Time"],
+    keywords: ["clock", "animate"],
     category: "Input",
     inputs: [],
     outputs: [("Time", "Float")],
     properties: [],
-    execute: |_inputs, _properties| {
-        "uniforms.time".to_string()
-    }
+    generate: |ctx, _inputs, _properties| ctx.builtin("uniforms.time")
 );
 
 // ============================================================================
@@ -27,14 +36,14 @@ define_node!(
 
 define_node!(
     UVNode,
+    id: "umbra.input.uv",
     name: "UV",
+    aliases: ["UV"],
     category: "Input",
     inputs: [],
     outputs: [("UV", "Vec2")],
     properties: [],
-    execute: |_inputs, _properties| {
-        "in.uv".to_string()
-    }
+    generate: |ctx, _inputs, _properties| ctx.builtin("in.uv")
 );
 
 // ============================================================================
@@ -43,17 +52,22 @@ define_node!(
 
 define_node!(
     FloatNode,
+    id: "umbra.input.float",
     name: "Float",
+    aliases: ["Float"],
+    keywords: ["constant", "number", "scalar"],
     category: "Input",
     inputs: [],
     outputs: [("Out", "Float")],
     properties: [("value", PropertyValue::Float(0.0))],
-    execute: |_inputs, properties| {
-        if let Some(PropertyValue::Float(v)) = properties.get("value") {
-            format!("{:.3}", v)
-        } else {
-            "0.0".to_string()
+    generate: |ctx, _inputs, properties| {
+        ctx.float(properties.get("value").and_then(|p| p.as_float()).unwrap_or(0.0))
+    },
+    ui: |ui, properties| {
+        if let Some(PropertyValue::Float(v)) = properties.get_mut("value") {
+            ui.add(egui::DragValue::new(v));
         }
+        true
     }
 );
 
@@ -63,16 +77,293 @@ define_node!(
 
 define_node!(
     ColorNode,
+    id: "umbra.input.color",
     name: "Color",
+    aliases: ["Color"],
+    keywords: ["constant", "rgb"],
     category: "Input",
     inputs: [],
     outputs: [("Color", "Vec4")],
     properties: [("value", PropertyValue::Color([1.0, 1.0, 1.0, 1.0]))],
-    execute: |_inputs, properties| {
-        if let Some(PropertyValue::Color([r, g, b, a])) = properties.get("value") {
-            format!("vec4<f32>({:.3}, {:.3}, {:.3}, {:.3})", r, g, b, a)
-        } else {
-            "vec4<f32>(1.0, 1.0, 1.0, 1.0)".to_string()
+    generate: |ctx, _inputs, properties| match properties.get("value") {
+        Some(PropertyValue::Color(c)) => ctx.literal(c),
+        _ => ctx.literal(&[1.0, 1.0, 1.0, 1.0]),
+    },
+    ui: |ui, properties| {
+        if let Some(PropertyValue::Color(c)) = properties.get_mut("value") {
+            ui.color_edit_button_rgba_unmultiplied(c);
+        }
+        true
+    }
+);
+
+// ============================================================================
+// Float4 Node - Constant four-component vector
+// ============================================================================
+
+define_node!(
+    Float4Node,
+    id: "umbra.input.float4",
+    name: "Float4",
+    aliases: ["Float4"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Out", "Vec4")],
+    properties: [("value", PropertyValue::Vec4([0.0, 0.0, 0.0, 0.0]))],
+    generate: |ctx, _inputs, properties| match properties.get("value") {
+        Some(PropertyValue::Vec4(v)) => ctx.literal(v),
+        _ => ctx.literal(&[0.0, 0.0, 0.0, 0.0]),
+    },
+    ui: |ui, properties| {
+        if let Some(PropertyValue::Vec4(v)) = properties.get_mut("value") {
+            ui.horizontal(|ui| {
+                for component in v {
+                    ui.add(egui::DragValue::new(component).speed(0.1));
+                }
+            });
+        }
+        true
+    }
+);
+
+// ============================================================================
+// Property Node - Reads a project property
+// ============================================================================
+
+define_node!(
+    PropertyNode,
+    id: "umbra.input.property",
+    name: "Property",
+    aliases: ["Property"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Value", "Dynamic")],
+    properties: [("property", PropertyValue::Text(String::new()))],
+    // The evaluator resolves the property, which may be converted or missing
+    generate: |ctx, _inputs, _properties| ctx.float(0.0),
+    role: NodeRole::Property
+);
+
+// ============================================================================
+// Position Node - Mesh position, the one the vertex stage displaces
+// ============================================================================
+
+define_node!(
+    PositionNode,
+    id: "umbra.input.position",
+    name: "Position",
+    aliases: ["Position"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Mesh Position", "Vec3")],
+    properties: [],
+    generate: |ctx, _inputs, _properties| match (ctx.format, ctx.stage) {
+        // Displacement works in object space, so keep the exports' vertex
+        // position; the preview reads the world position in both stages
+        (TargetFormat::Godot, ShaderStage::Vertex) => "VERTEX".to_string(),
+        (TargetFormat::Unity, ShaderStage::Vertex) => "v.vertex.xyz".to_string(),
+        _ => ctx.builtin("in.world_position"),
+    }
+);
+
+// ============================================================================
+// Normal Node - Interpolated surface normal
+// ============================================================================
+
+define_node!(
+    NormalNode,
+    id: "umbra.input.normal",
+    name: "Normal",
+    aliases: ["Normal"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Normal", "Vec3")],
+    properties: [("space", PropertyValue::enumeration(1, &["Object", "World", "View"]))],
+    generate: |ctx, _inputs, properties| {
+        match properties.get("space").and_then(|p| p.selected_option()) {
+            Some("Object") => {
+                let normal = ctx.builtin("in.object_normal");
+                ctx.call("normalize", &[normal])
+            }
+            Some("View") => ctx.builtin(VIEW_NORMAL),
+            _ => {
+                let normal = ctx.builtin("in.world_normal");
+                ctx.call("normalize", &[normal])
+            }
         }
     }
 );
+
+// ============================================================================
+// Tangent Node - World space tangent
+// ============================================================================
+
+define_node!(
+    TangentNode,
+    id: "umbra.input.tangent",
+    name: "Tangent",
+    aliases: ["Tangent"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Tangent", "Vec3")],
+    properties: [],
+    generate: |ctx, _inputs, _properties| {
+        let tangent = ctx.builtin("in.world_tangent");
+        ctx.call("normalize", &[tangent])
+    }
+);
+
+// ============================================================================
+// Bitangent Node - World space bitangent
+// ============================================================================
+
+define_node!(
+    BitangentNode,
+    id: "umbra.input.bitangent",
+    name: "Bitangent",
+    aliases: ["Bitangent"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Bitangent", "Vec3")],
+    properties: [],
+    generate: |ctx, _inputs, _properties| {
+        let bitangent = ctx.builtin("in.world_bitangent");
+        ctx.call("normalize", &[bitangent])
+    }
+);
+
+// ============================================================================
+// Screen Position Node - Normalized screen coordinates
+// ============================================================================
+
+define_node!(
+    ScreenPositionNode,
+    id: "umbra.input.screen_position",
+    name: "Screen Position",
+    aliases: ["Screen Position"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Position", "Vec2")],
+    properties: [],
+    generate: |ctx, _inputs, _properties| ctx.builtin("(in.clip_position.xy / uniforms.resolution)")
+);
+
+// ============================================================================
+// Pixel Coordinate Node - Fragment position in pixels
+// ============================================================================
+
+define_node!(
+    PixelCoordinateNode,
+    id: "umbra.input.pixel_coordinate",
+    name: "Pixel Coordinate",
+    aliases: ["Pixel Coordinate"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Pixel", "Vec2")],
+    properties: [],
+    generate: |ctx, _inputs, _properties| ctx.builtin("in.clip_position.xy")
+);
+
+// ============================================================================
+// Resolution Node - Render target size in pixels
+// ============================================================================
+
+define_node!(
+    ResolutionNode,
+    id: "umbra.input.resolution",
+    name: "Resolution",
+    aliases: ["Resolution"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Resolution", "Vec2")],
+    properties: [],
+    generate: |ctx, _inputs, _properties| ctx.builtin("uniforms.resolution")
+);
+
+// ============================================================================
+// Mouse Node - Cursor position in pixels
+// ============================================================================
+
+define_node!(
+    MouseNode,
+    id: "umbra.input.mouse",
+    name: "Mouse",
+    aliases: ["Mouse"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Mouse", "Vec2")],
+    properties: [],
+    generate: |ctx, _inputs, _properties| ctx.builtin("uniforms.mouse")
+);
+
+// ============================================================================
+// Camera Position Node - World space camera position
+// ============================================================================
+
+define_node!(
+    CameraPositionNode,
+    id: "umbra.input.camera_position",
+    name: "Camera Position",
+    aliases: ["Camera Position"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Position", "Vec3")],
+    properties: [],
+    generate: |ctx, _inputs, _properties| ctx.builtin("uniforms.camera_position")
+);
+
+// ============================================================================
+// View Direction Node - Direction from the surface to the camera
+// ============================================================================
+
+define_node!(
+    ViewDirectionNode,
+    id: "umbra.input.view_direction",
+    name: "View Direction",
+    aliases: ["View Direction"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Direction", "Vec3")],
+    properties: [("space", PropertyValue::enumeration(0, &["World", "View"]))],
+    generate: |ctx, _inputs, properties| {
+        match properties.get("space").and_then(|p| p.selected_option()) {
+            Some("View") => ctx.builtin(VIEW_DIRECTION_VIEW),
+            _ => ctx.builtin(VIEW_DIRECTION_WORLD),
+        }
+    }
+);
+
+// ============================================================================
+// Delta Time Node - Seconds since the previous frame
+// ============================================================================
+
+define_node!(
+    DeltaTimeNode,
+    id: "umbra.input.delta_time",
+    name: "Delta Time",
+    aliases: ["Delta Time"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Delta", "Float")],
+    properties: [],
+    generate: |ctx, _inputs, _properties| ctx.builtin("uniforms.delta_time")
+);
+
+// ============================================================================
+// Frame Count Node - Number of frames rendered so far
+// ============================================================================
+
+define_node!(
+    FrameCountNode,
+    id: "umbra.input.frame_count",
+    name: "Frame Count",
+    aliases: ["Frame Count"],
+    category: "Input",
+    inputs: [],
+    outputs: [("Frame", "Float")],
+    properties: [],
+    generate: |ctx, _inputs, _properties| {
+        let frame_count = ctx.builtin("uniforms.frame_count");
+        ctx.to_float(&frame_count)
+    }
+);
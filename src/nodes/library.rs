@@ -0,0 +1,554 @@
+//! Nodes defined in TOML or JSON files instead of Rust.
+//!
+//! Definitions are loaded from the user node directory and from the `nodes`
+//! directory next to the open project, and reloaded when the files change.
+//! A definition looks like:
+//!
+//! ```toml
+//! id = "studio.pattern.checker"
+//! name = "Checker"
+//! category = "Pattern"
+//! keywords = ["checkerboard", "grid"]
+//!
+//! [names]
+//! de = "Schachbrett"
+//!
+//! [[inputs]]
+//! name = "UV"
+//! type = "Vec2"
+//! default = "in.uv"
+//!
+//! [[inputs]]
+//! name = "Scale"
+//! type = "Float"
+//! default = "8.0"
+//!
+//! [[outputs]]
+//! name = "Out"
+//! type = "Float"
+//!
+//! [[properties]]
+//! name = "contrast"
+//! value = { Float = 1.0 }
+//!
+//! [code]
+//! wgsl = "(abs(floor({A}.x * {B}) + floor({A}.y * {B})) % 2.0) * {contrast}"
+//! glsl = "mod(floor({A}.x * {B}) + floor({A}.y * {B}), 2.0) * {contrast}"
+//! ```
+//!
+//! The `id` is what projects save, so it must stay the same when the node is
+//! renamed; `aliases` lists IDs it was saved under before, and `version` is
+//! bumped when pins or properties change. `names` translates the display
+//! name by language code.
+//!
+//! `{A}`, `{B}`, ... are the inputs in order and `{name}` a property's value.
+//! Templates for `glsl`, `hlsl` and `godot` are optional; targets without one
+//! translate the WGSL template.
+
+use super::codegen::{CodegenContext, vector_literal};
+use super::registry::{InputDefinition, NodeImpl, OutputDefinition, global_registry, validate_id};
+use crate::common::{Property, PropertyValue};
+use crate::file::export::TargetFormat;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Pin types a definition may declare.
+const PIN_TYPES: &[&str] = &[
+    "Float", "Vec2", "Vec3", "Vec4", "Color", "Mat3", "Mat4", "Dynamic", "Vector",
+];
+
+/// How often the node directories are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeDefinition {
+    /// Namespaced ID, e.g. `studio.pattern.checker`.
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Other words the add-node palette finds the node by.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Display names by language code, e.g. `de`.
+    #[serde(default)]
+    pub names: HashMap<String, String>,
+    /// Category path, e.g. `Pattern/Procedural`.
+    #[serde(default = "default_category")]
+    pub category: String,
+    #[serde(default)]
+    pub inputs: Vec<PinDefinition>,
+    pub outputs: Vec<PinDefinition>,
+    #[serde(default)]
+    pub properties: Vec<Property>,
+    pub code: CodeTemplates,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PinDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// Value of an input while unconnected: a number or vector of numbers,
+    /// which users can edit in the graph, or a WGSL expression, used until
+    /// a value is set in the graph.
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CodeTemplates {
+    pub wgsl: String,
+    pub glsl: Option<String>,
+    pub hlsl: Option<String>,
+    pub godot: Option<String>,
+}
+
+fn default_category() -> String {
+    "Custom".to_string()
+}
+
+fn default_version() -> u32 {
+    1
+}
+
+impl NodeDefinition {
+    /// Parses a definition from the contents of a `.toml` or `.json` file.
+    pub fn parse(path: &Path, source: &str) -> Result<Self, String> {
+        let definition: Self = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(source).map_err(|e| e.to_string())?,
+            _ => toml::from_str(source).map_err(|e| e.to_string())?,
+        };
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_id(&self.id)?;
+        if self.name.trim().is_empty() {
+            return Err("Node name is empty".to_string());
+        }
+        if self.outputs.is_empty() {
+            return Err("Node has no outputs".to_string());
+        }
+        for pin in self.inputs.iter().chain(&self.outputs) {
+            if !PIN_TYPES.contains(&pin.type_name.as_str()) {
+                return Err(format!(
+                    "Pin '{}' has unknown type '{}'",
+                    pin.name, pin.type_name
+                ));
+            }
+        }
+        if self.inputs.len() > 26 {
+            return Err("Nodes have at most 26 inputs, {A} to {Z}".to_string());
+        }
+        let templates = [
+            ("wgsl", Some(&self.code.wgsl)),
+            ("glsl", self.code.glsl.as_ref()),
+            ("hlsl", self.code.hlsl.as_ref()),
+            ("godot", self.code.godot.as_ref()),
+        ];
+        for (target, template) in templates {
+            let Some(template) = template else {
+                continue;
+            };
+            for placeholder in placeholders(template)? {
+                if self.input_index(placeholder).is_none()
+                    && !self.properties.iter().any(|p| p.name == placeholder)
+                {
+                    return Err(format!(
+                        "The {} template uses {{{}}}, which is neither an input nor a property",
+                        target, placeholder
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Input a placeholder letter refers to, e.g. 1 for `B`.
+    fn input_index(&self, placeholder: &str) -> Option<usize> {
+        let mut chars = placeholder.chars();
+        let letter = chars.next().filter(|c| c.is_ascii_uppercase())?;
+        let index = (letter as u8 - b'A') as usize;
+        (chars.next().is_none() && index < self.inputs.len()).then_some(index)
+    }
+
+    /// Fills a template's placeholders with the inputs and property values.
+    fn fill(
+        &self,
+        template: &str,
+        inputs: &[String],
+        properties: &HashMap<String, PropertyValue>,
+        format: TargetFormat,
+    ) -> String {
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+            let placeholder = &rest[start + 1..start + end];
+            match self.input_index(placeholder) {
+                Some(index) => result.push_str(&inputs[index]),
+                None => result.push_str(&property_literal(properties.get(placeholder), format)),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+/// Names between braces in a template.
+fn placeholders(template: &str) -> Result<Vec<&str>, String> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            return Err("Unclosed '{' in template".to_string());
+        };
+        names.push(&rest[start + 1..start + end]);
+        rest = &rest[start + end + 1..];
+    }
+    Ok(names)
+}
+
+/// A property value written into generated code.
+fn property_literal(value: Option<&PropertyValue>, format: TargetFormat) -> String {
+    match value {
+        Some(PropertyValue::Float(v)) => vector_literal(&[*v], format),
+        Some(PropertyValue::Int(v)) => vector_literal(&[*v as f32], format),
+        Some(PropertyValue::Bool(v)) => vector_literal(&[if *v { 1.0 } else { 0.0 }], format),
+        Some(PropertyValue::Vec2(v)) => vector_literal(v, format),
+        Some(PropertyValue::Vec3(v)) => vector_literal(v, format),
+        Some(PropertyValue::Vec4(v) | PropertyValue::Color(v)) => vector_literal(v, format),
+        Some(PropertyValue::Enum { selected, .. }) => vector_literal(&[*selected as f32], format),
+        Some(PropertyValue::Text(text)) => text.clone(),
+        _ => vector_literal(&[0.0], format),
+    }
+}
+
+/// A node registered from a definition file.
+pub struct LibraryNode {
+    definition: NodeDefinition,
+}
+
+impl NodeImpl for LibraryNode {
+    fn id(&self) -> &str {
+        &self.definition.id
+    }
+
+    fn name(&self) -> &str {
+        &self.definition.name
+    }
+
+    fn version(&self) -> u32 {
+        self.definition.version
+    }
+
+    fn aliases(&self) -> Vec<String> {
+        self.definition.aliases.clone()
+    }
+
+    fn keywords(&self) -> Vec<String> {
+        self.definition.keywords.clone()
+    }
+
+    fn localized_name(&self, language: &str) -> Option<String> {
+        self.definition.names.get(language).cloned()
+    }
+
+    fn category(&self) -> Vec<String> {
+        self.definition
+            .category
+            .split('/')
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn inputs(&self) -> Vec<InputDefinition> {
+        self.definition
+            .inputs
+            .iter()
+            .map(|pin| InputDefinition::new(&pin.name, &pin.type_name, pin.default.as_deref()))
+            .collect()
+    }
+
+    fn outputs(&self) -> Vec<OutputDefinition> {
+        self.definition
+            .outputs
+            .iter()
+            .map(|pin| OutputDefinition {
+                name: pin.name.clone(),
+                type_name: pin.type_name.clone(),
+                swizzle: None,
+            })
+            .collect()
+    }
+
+    fn define_properties(&self) -> Vec<Property> {
+        self.definition.properties.clone()
+    }
+
+    fn generate(
+        &self,
+        ctx: &mut CodegenContext,
+        inputs: &[String],
+        properties: &HashMap<String, PropertyValue>,
+    ) -> String {
+        let code = &self.definition.code;
+        let template = match ctx.format {
+            TargetFormat::Wgsl => Some(&code.wgsl),
+            TargetFormat::Godot => code.godot.as_ref(),
+            TargetFormat::Unity => code.hlsl.as_ref(),
+            TargetFormat::Generic => code.glsl.as_ref(),
+        };
+        match template {
+            Some(template) => self
+                .definition
+                .fill(template, inputs, properties, ctx.format),
+            None => {
+                let wgsl = self
+                    .definition
+                    .fill(&code.wgsl, inputs, properties, TargetFormat::Wgsl);
+                ctx.translate(&wgsl)
+            }
+        }
+    }
+}
+
+/// A definition file that could not be loaded.
+#[derive(Debug, Clone)]
+pub struct LibraryError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// Loads node definitions from the node directories into the registry and
+/// keeps them in sync with the files.
+#[derive(Default)]
+pub struct NodeLibrary {
+    dirs: Vec<PathBuf>,
+    /// Definition files and their modification times at the last load.
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    /// IDs of the nodes registered from files.
+    loaded: Vec<String>,
+    pub errors: Vec<LibraryError>,
+    last_poll: Option<Instant>,
+}
+
+impl NodeLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Per-user node directory, e.g. `~/.config/umbra/nodes`.
+    pub fn user_dir() -> Option<PathBuf> {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .or_else(|| std::env::var_os("APPDATA"))
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config.join("umbra").join("nodes"))
+    }
+
+    /// Node directory of a project saved at `project_path`.
+    pub fn project_dir(project_path: &Path) -> Option<PathBuf> {
+        project_path.parent().map(|dir| dir.join("nodes"))
+    }
+
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// IDs of the nodes loaded from files, in load order.
+    pub fn loaded(&self) -> &[String] {
+        &self.loaded
+    }
+
+    /// Reloads the definitions if the directories or any file changed since
+    /// the last load. Checks at most once per [`POLL_INTERVAL`].
+    pub fn poll(&mut self, project_path: Option<&Path>) {
+        if self.last_poll.is_some_and(|t| t.elapsed() < POLL_INTERVAL) {
+            return;
+        }
+        self.last_poll = Some(Instant::now());
+
+        let dirs: Vec<PathBuf> = Self::user_dir()
+            .into_iter()
+            .chain(project_path.and_then(Self::project_dir))
+            .collect();
+        let files = scan(&dirs);
+        if dirs != self.dirs || files != self.files {
+            self.dirs = dirs;
+            self.reload();
+        }
+    }
+
+    /// Unregisters the nodes loaded so far and loads every definition file
+    /// again. Files are loaded in path order; an ID or alias that is already
+    /// taken, by a builtin node or an earlier file, is reported as an error.
+    pub fn reload(&mut self) {
+        self.files = scan(&self.dirs);
+        self.errors.clear();
+
+        let mut registry = global_registry().write().unwrap();
+        for id in self.loaded.drain(..) {
+            registry.unregister(&id);
+        }
+        for (path, _) in &self.files {
+            let definition = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|source| NodeDefinition::parse(path, &source));
+            let result = definition.and_then(|definition| {
+                let id = definition.id.clone();
+                registry.try_register(LibraryNode { definition })?;
+                self.loaded.push(id);
+                Ok(())
+            });
+            if let Err(message) = result {
+                log::warn!("Failed to load node {}: {}", path.display(), message);
+                self.errors.push(LibraryError {
+                    path: path.clone(),
+                    message,
+                });
+            }
+        }
+    }
+}
+
+/// Definition files in the directories and their subdirectories, sorted by
+/// path, with their modification times.
+fn scan(dirs: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>)> {
+    fn visit(dir: &Path, files: &mut Vec<(PathBuf, Option<SystemTime>)>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                visit(&path, files);
+            } else if matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("toml" | "json")
+            ) {
+                let modified = entry.metadata().and_then(|m| m.modified()).ok();
+                files.push((path, modified));
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    for dir in dirs {
+        visit(dir, &mut files);
+    }
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::registry::ShaderStage;
+
+    const CHECKER: &str = r#"
+id = "studio.pattern.checker"
+name = "Checker"
+category = "Pattern/Procedural"
+
+[[inputs]]
+name = "UV"
+type = "Vec2"
+default = "in.uv"
+
+[[inputs]]
+name = "Scale"
+type = "Float"
+default = "8.0"
+
+[[outputs]]
+name = "Out"
+type = "Float"
+
+[[properties]]
+name = "contrast"
+value = { Float = 1.0 }
+
+[code]
+wgsl = "floor({A}.x * {B}) * {contrast}"
+glsl = "mod({A}.x, {B})"
+"#;
+
+    fn parse(source: &str) -> Result<NodeDefinition, String> {
+        NodeDefinition::parse(Path::new("checker.toml"), source)
+    }
+
+    #[test]
+    fn parses_toml_and_json() {
+        let definition = parse(CHECKER).unwrap();
+        assert_eq!(definition.id, "studio.pattern.checker");
+        assert_eq!(definition.version, 1);
+        assert_eq!(definition.inputs.len(), 2);
+
+        let json = r#"{
+            "id": "studio.math.twice",
+            "name": "Twice",
+            "inputs": [{ "name": "In", "type": "Vector" }],
+            "outputs": [{ "name": "Out", "type": "Vector" }],
+            "code": { "wgsl": "{A} * 2.0" }
+        }"#;
+        let definition = NodeDefinition::parse(Path::new("twice.json"), json).unwrap();
+        assert_eq!(definition.category, "Custom");
+    }
+
+    #[test]
+    fn rejects_invalid_definitions() {
+        let invalid = [
+            CHECKER.replace("studio.pattern.checker", "checker"),
+            CHECKER.replace("type = \"Vec2\"", "type = \"Vec5\""),
+            CHECKER.replace("{contrast}", "{brightness}"),
+            CHECKER.replace("* {B}", "* {C}"),
+            CHECKER.replace("mod({A}.x, {B})", "mod({A}.x, {B)"),
+            CHECKER.replace("name = \"Checker\"", "name = \" \""),
+            CHECKER.replace("category", "categories"),
+        ];
+        for source in &invalid {
+            assert!(parse(source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn templates_are_filled_per_target() {
+        let node = LibraryNode {
+            definition: parse(CHECKER).unwrap(),
+        };
+        let inputs = ["uv".to_string(), "4.0".to_string()];
+        let properties = HashMap::from([("contrast".to_string(), PropertyValue::Float(0.5))]);
+        let mut ctx = CodegenContext::new(TargetFormat::Wgsl, ShaderStage::Fragment, &[]);
+        assert_eq!(
+            node.generate(&mut ctx, &inputs, &properties),
+            "floor(uv.x * 4.0) * 0.500"
+        );
+
+        let mut ctx = CodegenContext::new(TargetFormat::Generic, ShaderStage::Fragment, &[]);
+        assert_eq!(
+            node.generate(&mut ctx, &inputs, &properties),
+            "mod(uv.x, 4.0)"
+        );
+        // Targets without a template translate the WGSL one
+        let mut ctx = CodegenContext::new(TargetFormat::Unity, ShaderStage::Fragment, &[]);
+        assert_eq!(
+            node.generate(&mut ctx, &inputs, &properties),
+            "floor(uv.x * 4.0) * 0.500"
+        );
+        assert_eq!(node.category(), ["Pattern", "Procedural"]);
+    }
+}
@@ -0,0 +1,241 @@
+//! Lighting utility nodes for shader graphs
+//!
+//! View-dependent effects such as Fresnel and rim lighting, and reflection
+//! vectors. Unconnected normal and view inputs default to the surface normal
+//! and the surface-to-camera direction in world space, until a value is set
+//! on the node.
+
+use crate::common::PropertyValue;
+use crate::define_node;
+use crate::nodes::codegen::CodegenContext;
+
+const WORLD_NORMAL: &str = "normalize(in.world_normal)";
+const VIEW_DIRECTION: &str = "normalize(uniforms.camera_position - in.world_position)";
+const INCIDENT: &str = "(-normalize(uniforms.camera_position - in.world_position))";
+
+/// Fresnel term: one at grazing angles, falling off with `power` towards
+/// surfaces facing the viewer.
+fn fresnel(ctx: &CodegenContext, normal: &str, view: &str, power: f32) -> String {
+    let facing = ctx.call(
+        "dot",
+        &[
+            ctx.call("normalize", &[normal]),
+            ctx.call("normalize", &[view]),
+        ],
+    );
+    ctx.call(
+        "pow",
+        &[
+            format!("1.0 - {}", ctx.call("saturate", &[facing])),
+            ctx.float(power),
+        ],
+    )
+}
+
+// ============================================================================
+// Fresnel Effect Node - Brightens surfaces seen at grazing angles
+// ============================================================================
+
+define_node!(
+    FresnelNode,
+    id: "umbra.lighting.fresnel_effect",
+    name: "Fresnel Effect",
+    aliases: ["Fresnel Effect"],
+    keywords: ["rim", "edge", "facing"],
+    category: "Lighting",
+    inputs: [("Normal", "Vec3", WORLD_NORMAL), ("View Dir", "Vec3", VIEW_DIRECTION)],
+    outputs: [("Out", "Float")],
+    properties: [("power", PropertyValue::Float(5.0))],
+    generate: |ctx, inputs, properties| {
+        let power = properties
+            .get("power")
+            .and_then(|p| p.as_float())
+            .unwrap_or(5.0);
+        fresnel(ctx, &inputs[0], &inputs[1], power)
+    }
+);
+
+// ============================================================================
+// Reflect Node - Mirrors an incident vector about a normal
+// ============================================================================
+
+define_node!(
+    ReflectNode,
+    id: "umbra.lighting.reflect",
+    name: "Reflect",
+    aliases: ["Reflect"],
+    category: "Lighting",
+    inputs: [("In", "Vec3", INCIDENT), ("Normal", "Vec3", WORLD_NORMAL)],
+    outputs: [("Out", "Vec3")],
+    properties: [],
+    generate: |ctx, inputs, _properties| {
+        let incident = ctx.call("normalize", &inputs[..1]);
+        let normal = ctx.call("normalize", &inputs[1..2]);
+        ctx.call("reflect", &[incident, normal])
+    }
+);
+
+// ============================================================================
+// Refract Node - Bends an incident vector entering a medium
+// ============================================================================
+
+define_node!(
+    RefractNode,
+    id: "umbra.lighting.refract",
+    name: "Refract",
+    aliases: ["Refract"],
+    category: "Lighting",
+    inputs: [("In", "Vec3", INCIDENT), ("Normal", "Vec3", WORLD_NORMAL)],
+    outputs: [("Out", "Vec3")],
+    properties: [("ior", PropertyValue::Float(1.5))],
+    generate: |ctx, inputs, properties| {
+        let ior = properties
+            .get("ior")
+            .and_then(|p| p.as_float())
+            .unwrap_or(1.5)
+            .max(0.001);
+        let incident = ctx.call("normalize", &inputs[..1]);
+        let normal = ctx.call("normalize", &inputs[1..2]);
+        // Ratio of indices for a ray going from air into the surface
+        let eta = format!("{:.4}", 1.0 / ior);
+        ctx.call("refract", &[incident, normal, eta])
+    }
+);
+
+// ============================================================================
+// Rim Light Node - Coloured Fresnel term for silhouette highlights
+// ============================================================================
+
+define_node!(
+    RimLightNode,
+    id: "umbra.lighting.rim_light",
+    name: "Rim Light",
+    aliases: ["Rim Light"],
+    category: "Lighting",
+    inputs: [
+        ("Normal", "Vec3", WORLD_NORMAL),
+        ("View Dir", "Vec3", VIEW_DIRECTION),
+        ("Color", "Vec3", "1.0"),
+    ],
+    outputs: [("Out", "Vec3")],
+    properties: [
+        ("power", PropertyValue::Float(3.0)),
+        ("intensity", PropertyValue::Float(1.0)),
+    ],
+    generate: |ctx, inputs, properties| {
+        let power = properties
+            .get("power")
+            .and_then(|p| p.as_float())
+            .unwrap_or(3.0);
+        let intensity = properties
+            .get("intensity")
+            .and_then(|p| p.as_float())
+            .unwrap_or(1.0);
+        format!(
+            "({} * ({} * {}))",
+            inputs[2],
+            fresnel(ctx, &inputs[0], &inputs[1], power),
+            ctx.float(intensity)
+        )
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::UmbraProject;
+    use crate::file::export::TargetFormat;
+    use crate::graph::eval::Evaluator;
+    use crate::graph::{UmbraNode, surface};
+    use crate::nodes::registry::{NodeImpl, NodeRole, ShaderStage};
+    use egui_snarl::{InPinId, OutPinId};
+    use std::collections::HashMap;
+
+    fn generate(node: &dyn NodeImpl, format: TargetFormat, inputs: &[&str]) -> String {
+        let mut ctx = CodegenContext::new(format, ShaderStage::Fragment, &[]);
+        let inputs: Vec<String> = inputs.iter().map(|i| i.to_string()).collect();
+        let properties = node
+            .define_properties()
+            .into_iter()
+            .map(|p| (p.name, p.value))
+            .collect::<HashMap<_, _>>();
+        node.generate(&mut ctx, &inputs, &properties)
+    }
+
+    #[test]
+    fn fresnel_clamps_in_glsl_and_saturates_in_hlsl() {
+        assert_eq!(
+            generate(&FresnelNode, TargetFormat::Godot, &["n", "v"]),
+            "pow(1.0 - clamp(dot(normalize(n), normalize(v)), 0.0, 1.0), 5.000)"
+        );
+        assert_eq!(
+            generate(&FresnelNode, TargetFormat::Generic, &["n", "v"]),
+            "pow(1.0 - clamp(dot(normalize(n), normalize(v)), 0.0, 1.0), 5.000)"
+        );
+        assert_eq!(
+            generate(&FresnelNode, TargetFormat::Unity, &["n", "v"]),
+            "pow(1.0 - saturate(dot(normalize(n), normalize(v))), 5.000)"
+        );
+        assert_eq!(
+            generate(&RimLightNode, TargetFormat::Unity, &["n", "v", "c"]),
+            "(c * (pow(1.0 - saturate(dot(normalize(n), normalize(v))), 3.000) * 1.000))"
+        );
+    }
+
+    #[test]
+    fn refract_passes_the_index_ratio() {
+        assert_eq!(
+            generate(&RefractNode, TargetFormat::Unity, &["i", "n"]),
+            "refract(normalize(i), normalize(n), 0.6667)"
+        );
+        assert_eq!(
+            generate(&ReflectNode, TargetFormat::Godot, &["i", "n"]),
+            "reflect(normalize(i), normalize(n))"
+        );
+    }
+
+    #[test]
+    fn unconnected_inputs_read_the_target_builtins() {
+        crate::nodes::register_builtins();
+        let mut project = UmbraProject::new();
+        let snarl = &mut project.graph.snarl;
+        let (output, _) = snarl
+            .node_ids()
+            .find(|(_, node)| node.role() == NodeRole::SurfaceOutput)
+            .unwrap();
+        let node = snarl.insert_node(
+            egui::pos2(0.0, 0.0),
+            UmbraNode::new("umbra.lighting.fresnel_effect"),
+        );
+        snarl.connect(
+            OutPinId { node, output: 0 },
+            InPinId {
+                node: output,
+                input: surface::EMISSION,
+            },
+        );
+
+        let cases = [
+            (
+                TargetFormat::Godot,
+                "normalize((INV_VIEW_MATRIX * vec4(NORMAL, 0.0)).xyz)",
+                "(INV_VIEW_MATRIX * vec4(VIEW, 0.0)).xyz",
+            ),
+            (
+                TargetFormat::Unity,
+                "normalize(IN.world_normal)",
+                "normalize(_WorldSpaceCameraPos - IN.worldPos)",
+            ),
+            (
+                TargetFormat::Generic,
+                "normalize(v_world_normal)",
+                "normalize(camera_position - v_world_position)",
+            ),
+        ];
+        for (format, normal, view) in cases {
+            let shader = Evaluator::evaluate(&project, format);
+            assert!(shader.contains(normal), "{:?}:\n{}", format, shader);
+            assert!(shader.contains(view), "{:?}:\n{}", format, shader);
+        }
+    }
+}
@@ -9,46 +9,103 @@
 /// ```ignore
 /// define_node!(
 ///     TimeNode,
+///     id: "umbra.input.time",
 ///     name: "Time",
 ///     category: "Input",
 ///     inputs: [],
 ///     outputs: [("Time", "Float")],
 ///     properties: [],
-///     execute: |_inputs, _properties| {
-///         "uniforms.time".to_string()
-///     }
+///     generate: |ctx, _inputs, _properties| ctx.builtin("uniforms.time")
 /// );
 /// ```
+///
+/// The ID is what projects save and must never change; the name is only
+/// shown in the editor. IDs a node was saved under before follow the name:
+/// `aliases: ["Time"]`. Next, other words the add-node palette finds the
+/// node by: `keywords: ["clock", "animate"]`.
+///
+/// The category may be a path such as `"Math/Trigonometry"`, which the
+/// add-node menu shows as nested submenus.
+///
+/// An input may give a third element, its value while unconnected, which the
+/// graph lets users edit: `("B", "Dynamic", "1.0")`. A default that is not a
+/// number is a fragment-stage WGSL expression instead, which the evaluator
+/// translates for the target: `("Normal", "Vec3", "normalize(in.world_normal)")`.
+/// Likewise an output may give the components of the result it reads, for
+/// nodes with several outputs: `("Cell ID", "Float", "y")`.
+///
+/// Nodes whose result width depends on their properties give it next, and
+/// may then check their properties against the width of their dynamic pins:
+/// `width: |properties, dynamic_width| Some(2)`,
+/// `validate: |properties, dynamic_width| Ok(())`.
+///
+/// `generate` builds the node's expression for the target and stage with the
+/// [`CodegenContext`] helpers, e.g. `ctx.call("mix", inputs)`, including the
+/// helper functions it calls: `ctx.helper(&VALUE_NOISE_2D)`.
+/// An output that derives more from the result than a swizzle reads it in
+/// `read_output`: `read_output: |ctx, output, result| None`.
+///
+/// Finally a node may set its role and draw its own settings UI:
+/// `role: NodeRole::VertexOutput`,
+/// `ui: |ui, properties| true`.
+///
+/// [`CodegenContext`]: crate::nodes::codegen::CodegenContext
 #[macro_export]
 macro_rules! define_node {
     (
         $node_name:ident,
+        id: $id:expr,
         name: $display_name:expr,
+        $(aliases: [$($alias:expr),* $(,)?],)?
+        $(keywords: [$($keyword:expr),* $(,)?],)?
         category: $category:expr,
-        inputs: [$( ($in_name:expr, $in_type:expr) ),* $(,)?],
-        outputs: [$( ($out_name:expr, $out_type:expr) ),* $(,)?],
+        inputs: [$( ($in_name:expr, $in_type:expr $(, $in_default:expr)?) ),* $(,)?],
+        outputs: [$( ($out_name:expr, $out_type:expr $(, $out_swizzle:expr)?) ),* $(,)?],
         properties: [$( ($prop_name:expr, $prop_val:expr) ),* $(,)?],
-        execute: $exec_fn:expr
+        generate: $generate_fn:expr
+        $(, read_output: $read_output_fn:expr)?
+        $(, width: $width_fn:expr)?
+        $(, validate: $validate_fn:expr)?
+        $(, role: $role:expr)?
+        $(, ui: $ui_fn:expr)?
+        $(,)?
     ) => {
         #[allow(dead_code)]
         pub struct $node_name;
 
         impl $crate::nodes::registry::NodeImpl for $node_name {
+            fn id(&self) -> &str {
+                $id
+            }
+
             fn name(&self) -> &str {
                 $display_name
             }
 
+            $(
+                fn aliases(&self) -> Vec<String> {
+                    vec![$($alias.to_string()),*]
+                }
+            )?
+
+            $(
+                fn keywords(&self) -> Vec<String> {
+                    vec![$($keyword.to_string()),*]
+                }
+            )?
+
             fn category(&self) -> Vec<String> {
-                vec![$category.to_string()]
+                $category.split('/').map(str::to_string).collect()
             }
 
             fn inputs(&self) -> Vec<$crate::nodes::registry::InputDefinition> {
                 vec![
                     $(
-                        $crate::nodes::registry::InputDefinition {
-                            name: $in_name.to_string(),
-                            type_name: $in_type.to_string(),
-                        }
+                        $crate::nodes::registry::InputDefinition::new(
+                            $in_name,
+                            $in_type,
+                            None$(.or(Some($in_default)))?,
+                        )
                     ),*
                 ]
             }
@@ -59,6 +116,7 @@ macro_rules! define_node {
                         $crate::nodes::registry::OutputDefinition {
                             name: $out_name.to_string(),
                             type_name: $out_type.to_string(),
+                            swizzle: None$(.or(Some($out_swizzle.to_string())))?,
                         }
                     ),*
                 ]
@@ -75,14 +133,66 @@ macro_rules! define_node {
                 ]
             }
 
-            fn execute(
+            fn generate(
                 &self,
+                ctx: &mut $crate::nodes::codegen::CodegenContext,
                 inputs: &[String],
                 properties: &std::collections::HashMap<String, $crate::common::PropertyValue>,
             ) -> String {
-                let exec: fn(&[String], &std::collections::HashMap<String, $crate::common::PropertyValue>) -> String = $exec_fn;
-                exec(inputs, properties)
+                let generate: fn(&mut $crate::nodes::codegen::CodegenContext, &[String], &std::collections::HashMap<String, $crate::common::PropertyValue>) -> String = $generate_fn;
+                generate(ctx, inputs, properties)
             }
+
+            $(
+                fn read_output(
+                    &self,
+                    ctx: &mut $crate::nodes::codegen::CodegenContext,
+                    output: usize,
+                    result: &str,
+                ) -> Option<String> {
+                    let read: fn(&mut $crate::nodes::codegen::CodegenContext, usize, &str) -> Option<String> = $read_output_fn;
+                    read(ctx, output, result)
+                }
+            )?
+
+            $(
+                fn result_width(
+                    &self,
+                    properties: &std::collections::HashMap<String, $crate::common::PropertyValue>,
+                    dynamic_width: usize,
+                ) -> Option<usize> {
+                    let width: fn(&std::collections::HashMap<String, $crate::common::PropertyValue>, usize) -> Option<usize> = $width_fn;
+                    width(properties, dynamic_width)
+                }
+            )?
+
+            $(
+                fn validate(
+                    &self,
+                    properties: &std::collections::HashMap<String, $crate::common::PropertyValue>,
+                    dynamic_width: usize,
+                ) -> Result<(), String> {
+                    let validate: fn(&std::collections::HashMap<String, $crate::common::PropertyValue>, usize) -> Result<(), String> = $validate_fn;
+                    validate(properties, dynamic_width)
+                }
+            )?
+
+            $(
+                fn role(&self) -> $crate::nodes::registry::NodeRole {
+                    $role
+                }
+            )?
+
+            $(
+                fn show_ui(
+                    &self,
+                    ui: &mut egui::Ui,
+                    properties: &mut std::collections::HashMap<String, $crate::common::PropertyValue>,
+                ) -> bool {
+                    let show: fn(&mut egui::Ui, &mut std::collections::HashMap<String, $crate::common::PropertyValue>) -> bool = $ui_fn;
+                    show(ui, properties)
+                }
+            )?
         }
     };
 }
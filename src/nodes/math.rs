@@ -1,6 +1,8 @@
 //! Math nodes for shader graphs
 //!
-//! These nodes perform mathematical operations on inputs.
+//! These nodes perform mathematical operations on inputs. Most pins are
+//! `Dynamic`: they accept a float or any vector, and scalars are splatted to
+//! the width of the widest connected input.
 
 // ============================================================================
 // Add Node - Adds two values
@@ -8,46 +10,449 @@
 
 define_node!(
     AddNode,
+    id: "umbra.math.add",
     name: "Add",
-    category: "Math",
-    inputs: [("A", "Float"), ("B", "Float")],
-    outputs: [("Out", "Float")],
+    aliases: ["Add"],
+    keywords: ["plus", "sum"],
+    category: "Math/Basic",
+    inputs: [("A", "Dynamic"), ("B", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
     properties: [],
-    execute: |inputs, _properties| {
+    generate: |_ctx, inputs, _properties| {
         format!("({} + {})", inputs[0], inputs[1])
     }
 );
 
+// ============================================================================
+// Subtract Node - Subtracts B from A
+// ============================================================================
+
+define_node!(
+    SubtractNode,
+    id: "umbra.math.subtract",
+    name: "Subtract",
+    aliases: ["Subtract"],
+    keywords: ["minus", "difference"],
+    category: "Math/Basic",
+    inputs: [("A", "Dynamic"), ("B", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |_ctx, inputs, _properties| {
+        format!("({} - {})", inputs[0], inputs[1])
+    }
+);
+
 // ============================================================================
 // Multiply Node - Multiplies two values
 // ============================================================================
 
 define_node!(
     MultiplyNode,
+    id: "umbra.math.multiply",
     name: "Multiply",
-    category: "Math",
-    inputs: [("A", "Float"), ("B", "Float")],
-    outputs: [("Out", "Float")],
+    aliases: ["Multiply"],
+    keywords: ["times", "product", "scale"],
+    category: "Math/Basic",
+    inputs: [("A", "Dynamic"), ("B", "Dynamic", "1.0")],
+    outputs: [("Out", "Dynamic")],
     properties: [],
-    execute: |inputs, _properties| {
+    generate: |_ctx, inputs, _properties| {
         format!("({} * {})", inputs[0], inputs[1])
     }
 );
 
+// ============================================================================
+// Divide Node - Divides A by B
+// ============================================================================
+
+define_node!(
+    DivideNode,
+    id: "umbra.math.divide",
+    name: "Divide",
+    aliases: ["Divide"],
+    keywords: ["quotient", "ratio"],
+    category: "Math/Basic",
+    inputs: [("A", "Dynamic"), ("B", "Dynamic", "1.0")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |_ctx, inputs, _properties| {
+        format!("({} / {})", inputs[0], inputs[1])
+    }
+);
+
+// ============================================================================
+// Mod Node - Floored remainder, the same on every target
+// ============================================================================
+
+define_node!(
+    ModNode,
+    id: "umbra.math.mod",
+    name: "Mod",
+    aliases: ["Mod"],
+    keywords: ["modulo", "remainder", "fmod"],
+    category: "Math/Basic",
+    inputs: [("A", "Dynamic"), ("B", "Dynamic", "1.0")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |_ctx, inputs, _properties| {
+        format!("({0} - {1} * floor({0} / {1}))", inputs[0], inputs[1])
+    }
+);
+
+// ============================================================================
+// Negate Node - Flips the sign
+// ============================================================================
+
+define_node!(
+    NegateNode,
+    id: "umbra.math.negate",
+    name: "Negate",
+    aliases: ["Negate"],
+    category: "Math/Basic",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |_ctx, inputs, _properties| {
+        format!("(-{})", inputs[0])
+    }
+);
+
+// ============================================================================
+// One Minus Node - Inverts a value in [0, 1]
+// ============================================================================
+
+define_node!(
+    OneMinusNode,
+    id: "umbra.math.one_minus",
+    name: "One Minus",
+    aliases: ["One Minus"],
+    keywords: ["invert", "complement"],
+    category: "Math/Basic",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |_ctx, inputs, _properties| {
+        format!("(1.0 - {})", inputs[0])
+    }
+);
+
+// ============================================================================
+// Power Node - A raised to the power B
+// ============================================================================
+
+define_node!(
+    PowerNode,
+    id: "umbra.math.power",
+    name: "Power",
+    aliases: ["Power"],
+    keywords: ["pow", "exponent"],
+    category: "Math/Advanced",
+    inputs: [("A", "Dynamic"), ("B", "Dynamic", "1.0")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("pow", inputs)
+);
+
+// ============================================================================
+// Sqrt Node - Square root
+// ============================================================================
+
+define_node!(
+    SqrtNode,
+    id: "umbra.math.sqrt",
+    name: "Sqrt",
+    aliases: ["Sqrt"],
+    keywords: ["square root"],
+    category: "Math/Advanced",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("sqrt", inputs)
+);
+
+// ============================================================================
+// Exp Node - Natural exponential
+// ============================================================================
+
+define_node!(
+    ExpNode,
+    id: "umbra.math.exp",
+    name: "Exp",
+    aliases: ["Exp"],
+    category: "Math/Advanced",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("exp", inputs)
+);
+
+// ============================================================================
+// Log Node - Natural logarithm
+// ============================================================================
+
+define_node!(
+    LogNode,
+    id: "umbra.math.log",
+    name: "Log",
+    aliases: ["Log"],
+    category: "Math/Advanced",
+    inputs: [("In", "Dynamic", "1.0")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("log", inputs)
+);
+
+// ============================================================================
+// Abs Node - Absolute value
+// ============================================================================
+
+define_node!(
+    AbsNode,
+    id: "umbra.math.abs",
+    name: "Abs",
+    aliases: ["Abs"],
+    category: "Math/Advanced",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("abs", inputs)
+);
+
+// ============================================================================
+// Sign Node - -1, 0 or 1 depending on the sign
+// ============================================================================
+
+define_node!(
+    SignNode,
+    id: "umbra.math.sign",
+    name: "Sign",
+    aliases: ["Sign"],
+    category: "Math/Advanced",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("sign", inputs)
+);
+
+// ============================================================================
+// Min Node - Smaller of two values
+// ============================================================================
+
+define_node!(
+    MinNode,
+    id: "umbra.math.min",
+    name: "Min",
+    aliases: ["Min"],
+    category: "Math/Range",
+    inputs: [("A", "Dynamic"), ("B", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("min", inputs)
+);
+
+// ============================================================================
+// Max Node - Larger of two values
+// ============================================================================
+
+define_node!(
+    MaxNode,
+    id: "umbra.math.max",
+    name: "Max",
+    aliases: ["Max"],
+    category: "Math/Range",
+    inputs: [("A", "Dynamic"), ("B", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("max", inputs)
+);
+
+// ============================================================================
+// Clamp Node - Limits a value to a range
+// ============================================================================
+
+define_node!(
+    ClampNode,
+    id: "umbra.math.clamp",
+    name: "Clamp",
+    aliases: ["Clamp"],
+    category: "Math/Range",
+    inputs: [("In", "Dynamic"), ("Min", "Dynamic", "0.0"), ("Max", "Dynamic", "1.0")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("clamp", inputs)
+);
+
+// ============================================================================
+// Saturate Node - Clamps to [0, 1]
+// ============================================================================
+
+define_node!(
+    SaturateNode,
+    id: "umbra.math.saturate",
+    name: "Saturate",
+    aliases: ["Saturate"],
+    keywords: ["clamp01"],
+    category: "Math/Range",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("saturate", inputs)
+);
+
+// ============================================================================
+// Remap Node - Maps a value from one range to another
+// ============================================================================
+
+define_node!(
+    RemapNode,
+    id: "umbra.math.remap",
+    name: "Remap",
+    aliases: ["Remap"],
+    keywords: ["range", "map"],
+    category: "Math/Range",
+    inputs: [
+        ("In", "Dynamic"),
+        ("In Min", "Dynamic", "0.0"),
+        ("In Max", "Dynamic", "1.0"),
+        ("Out Min", "Dynamic", "0.0"),
+        ("Out Max", "Dynamic", "1.0"),
+    ],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |_ctx, inputs, _properties| {
+        format!("({3} + ({0} - {1}) * ({4} - {3}) / ({2} - {1}))", inputs[0], inputs[1], inputs[2], inputs[3], inputs[4])
+    }
+);
+
+// ============================================================================
+// Floor Node - Rounds down
+// ============================================================================
+
+define_node!(
+    FloorNode,
+    id: "umbra.math.floor",
+    name: "Floor",
+    aliases: ["Floor"],
+    category: "Math/Range",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("floor", inputs)
+);
+
+// ============================================================================
+// Ceil Node - Rounds up
+// ============================================================================
+
+define_node!(
+    CeilNode,
+    id: "umbra.math.ceil",
+    name: "Ceil",
+    aliases: ["Ceil"],
+    category: "Math/Range",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("ceil", inputs)
+);
+
+// ============================================================================
+// Round Node - Rounds to the nearest integer
+// ============================================================================
+
+define_node!(
+    RoundNode,
+    id: "umbra.math.round",
+    name: "Round",
+    aliases: ["Round"],
+    category: "Math/Range",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("round", inputs)
+);
+
+// ============================================================================
+// Fract Node - Fractional part
+// ============================================================================
+
+define_node!(
+    FractNode,
+    id: "umbra.math.fract",
+    name: "Fract",
+    aliases: ["Fract"],
+    keywords: ["frac", "fractional", "repeat"],
+    category: "Math/Range",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("fract", inputs)
+);
+
+// ============================================================================
+// Lerp Node - Linear interpolation between A and B
+// ============================================================================
+
+define_node!(
+    LerpNode,
+    id: "umbra.math.lerp",
+    name: "Lerp",
+    aliases: ["Lerp"],
+    keywords: ["mix", "interpolate"],
+    category: "Math/Interpolation",
+    inputs: [("A", "Dynamic"), ("B", "Dynamic", "1.0"), ("T", "Dynamic", "0.5")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("mix", inputs)
+);
+
+// ============================================================================
+// Smoothstep Node - Smooth Hermite step between two edges
+// ============================================================================
+
+define_node!(
+    SmoothstepNode,
+    id: "umbra.math.smoothstep",
+    name: "Smoothstep",
+    aliases: ["Smoothstep"],
+    keywords: ["smooth", "ease"],
+    category: "Math/Interpolation",
+    inputs: [("Edge 1", "Dynamic", "0.0"), ("Edge 2", "Dynamic", "1.0"), ("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("smoothstep", inputs)
+);
+
+// ============================================================================
+// Step Node - 0 below the edge, 1 at or above it
+// ============================================================================
+
+define_node!(
+    StepNode,
+    id: "umbra.math.step",
+    name: "Step",
+    aliases: ["Step"],
+    category: "Math/Interpolation",
+    inputs: [("Edge", "Dynamic", "0.5"), ("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("step", inputs)
+);
+
 // ============================================================================
 // Sin Node - Sine function
 // ============================================================================
 
 define_node!(
     SinNode,
+    id: "umbra.math.sin",
     name: "Sin",
-    category: "Math",
-    inputs: [("In", "Float")],
-    outputs: [("Out", "Float")],
+    aliases: ["Sin"],
+    category: "Math/Trigonometry",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
     properties: [],
-    execute: |inputs, _properties| {
-        format!("sin({})", inputs[0])
-    }
+    generate: |ctx, inputs, _properties| ctx.call("sin", inputs)
 );
 
 // ============================================================================
@@ -56,44 +461,127 @@ define_node!(
 
 define_node!(
     CosNode,
+    id: "umbra.math.cos",
     name: "Cos",
-    category: "Math",
-    inputs: [("In", "Float")],
+    aliases: ["Cos"],
+    category: "Math/Trigonometry",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("cos", inputs)
+);
+
+// ============================================================================
+// Tan Node - Tangent function
+// ============================================================================
+
+define_node!(
+    TanNode,
+    id: "umbra.math.tan",
+    name: "Tan",
+    aliases: ["Tan"],
+    category: "Math/Trigonometry",
+    inputs: [("In", "Dynamic")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("tan", inputs)
+);
+
+// ============================================================================
+// Atan2 Node - Angle of the vector (X, Y)
+// ============================================================================
+
+define_node!(
+    Atan2Node,
+    id: "umbra.math.atan2",
+    name: "Atan2",
+    aliases: ["Atan2"],
+    keywords: ["angle", "arctangent"],
+    category: "Math/Trigonometry",
+    inputs: [("Y", "Dynamic"), ("X", "Dynamic", "1.0")],
+    outputs: [("Out", "Dynamic")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("atan2", inputs)
+);
+
+// ============================================================================
+// Dot Node - Dot product
+// ============================================================================
+
+define_node!(
+    DotNode,
+    id: "umbra.math.dot",
+    name: "Dot",
+    aliases: ["Dot"],
+    category: "Math/Vector",
+    inputs: [("A", "Vector"), ("B", "Vector")],
     outputs: [("Out", "Float")],
     properties: [],
-    execute: |inputs, _properties| {
-        format!("cos({})", inputs[0])
-    }
+    generate: |ctx, inputs, _properties| ctx.call("dot", inputs)
 );
 
 // ============================================================================
-// Fract Node - Fractional part
+// Cross Node - Cross product
 // ============================================================================
 
 define_node!(
-    FractNode,
-    name: "Fract",
-    category: "Math",
-    inputs: [("In", "Float")],
+    CrossNode,
+    id: "umbra.math.cross",
+    name: "Cross",
+    aliases: ["Cross"],
+    category: "Math/Vector",
+    inputs: [("A", "Vec3"), ("B", "Vec3")],
+    outputs: [("Out", "Vec3")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("cross", inputs)
+);
+
+// ============================================================================
+// Length Node - Length of a vector
+// ============================================================================
+
+define_node!(
+    LengthNode,
+    id: "umbra.math.length",
+    name: "Length",
+    aliases: ["Length"],
+    keywords: ["magnitude"],
+    category: "Math/Vector",
+    inputs: [("In", "Vector")],
     outputs: [("Out", "Float")],
     properties: [],
-    execute: |inputs, _properties| {
-        format!("fract({})", inputs[0])
-    }
+    generate: |ctx, inputs, _properties| ctx.call("length", inputs)
 );
 
 // ============================================================================
-// Abs Node - Absolute value
+// Distance Node - Distance between two points
 // ============================================================================
 
 define_node!(
-    AbsNode,
-    name: "Abs",
-    category: "Math",
-    inputs: [("In", "Float")],
+    DistanceNode,
+    id: "umbra.math.distance",
+    name: "Distance",
+    aliases: ["Distance"],
+    category: "Math/Vector",
+    inputs: [("A", "Vector"), ("B", "Vector")],
     outputs: [("Out", "Float")],
     properties: [],
-    execute: |inputs, _properties| {
-        format!("abs({})", inputs[0])
-    }
+    generate: |ctx, inputs, _properties| ctx.call("distance", inputs)
+);
+
+// ============================================================================
+// Normalize Node - Scales a vector to unit length
+// ============================================================================
+
+define_node!(
+    NormalizeNode,
+    id: "umbra.math.normalize",
+    name: "Normalize",
+    aliases: ["Normalize"],
+    keywords: ["unit", "direction"],
+    category: "Math/Vector",
+    inputs: [("In", "Vector")],
+    outputs: [("Out", "Vector")],
+    properties: [],
+    generate: |ctx, inputs, _properties| ctx.call("normalize", inputs)
 );
@@ -0,0 +1,248 @@
+//! Matrix and space transformation nodes for shader graphs
+//!
+//! The Transform node converts positions and directions between object,
+//! world, view and clip space using the model, view and projection matrices,
+//! which map onto the engine's own matrices in the Godot and Unity exports.
+//! Matrix products go through helpers because HLSL multiplies with `mul`.
+
+use crate::common::PropertyValue;
+use crate::nodes::registry::ShaderFunction;
+use std::collections::HashMap;
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+/// Transforms a point, dividing by `w` so projections yield NDC.
+pub static TRANSFORM_POSITION: ShaderFunction = ShaderFunction {
+    name: "umbra_transform_position",
+    deps: &[],
+    wgsl: "fn umbra_transform_position(m: mat4x4<f32>, p: vec3<f32>) -> vec3<f32> {
+  let h = m * vec4<f32>(p, 1.0);
+  return h.xyz / h.w;
+}
+",
+    glsl: TRANSFORM_POSITION_GLSL,
+    hlsl: "float3 umbra_transform_position(float4x4 m, float3 p) {
+  float4 h = mul(m, float4(p, 1.0));
+  return h.xyz / h.w;
+}
+",
+    godot: TRANSFORM_POSITION_GLSL,
+};
+
+const TRANSFORM_POSITION_GLSL: &str = "vec3 umbra_transform_position(mat4 m, vec3 p) {
+  vec4 h = m * vec4(p, 1.0);
+  return h.xyz / h.w;
+}
+";
+
+/// Transforms a direction, ignoring translation.
+pub static TRANSFORM_DIRECTION: ShaderFunction = ShaderFunction {
+    name: "umbra_transform_direction",
+    deps: &[],
+    wgsl: "fn umbra_transform_direction(m: mat4x4<f32>, d: vec3<f32>) -> vec3<f32> {
+  return (m * vec4<f32>(d, 0.0)).xyz;
+}
+",
+    glsl: TRANSFORM_DIRECTION_GLSL,
+    hlsl: "float3 umbra_transform_direction(float4x4 m, float3 d) {
+  return mul(m, float4(d, 0.0)).xyz;
+}
+",
+    godot: TRANSFORM_DIRECTION_GLSL,
+};
+
+const TRANSFORM_DIRECTION_GLSL: &str = "vec3 umbra_transform_direction(mat4 m, vec3 d) {
+  return (m * vec4(d, 0.0)).xyz;
+}
+";
+
+pub static MUL_MAT4: ShaderFunction = ShaderFunction {
+    name: "umbra_mul_mat4",
+    deps: &[],
+    wgsl: "fn umbra_mul_mat4(m: mat4x4<f32>, v: vec4<f32>) -> vec4<f32> {
+  return m * v;
+}
+",
+    glsl: "vec4 umbra_mul_mat4(mat4 m, vec4 v) {
+  return m * v;
+}
+",
+    hlsl: "float4 umbra_mul_mat4(float4x4 m, float4 v) {
+  return mul(m, v);
+}
+",
+    godot: "vec4 umbra_mul_mat4(mat4 m, vec4 v) {
+  return m * v;
+}
+",
+};
+
+pub static MUL_MAT3: ShaderFunction = ShaderFunction {
+    name: "umbra_mul_mat3",
+    deps: &[],
+    wgsl: "fn umbra_mul_mat3(m: mat3x3<f32>, v: vec3<f32>) -> vec3<f32> {
+  return m * v;
+}
+",
+    glsl: "vec3 umbra_mul_mat3(mat3 m, vec3 v) {
+  return m * v;
+}
+",
+    hlsl: "float3 umbra_mul_mat3(float3x3 m, float3 v) {
+  return mul(m, v);
+}
+",
+    godot: "vec3 umbra_mul_mat3(mat3 m, vec3 v) {
+  return m * v;
+}
+",
+};
+
+const SPACES: &[&str] = &["Object", "World", "View", "Clip"];
+
+/// Matrices taking a space to world space, applied in order.
+fn to_world(space: &str) -> &'static [&'static str] {
+    match space {
+        "Object" => &["uniforms.model"],
+        "View" => &["uniforms.inverse_view"],
+        "Clip" => &["uniforms.inverse_projection", "uniforms.inverse_view"],
+        _ => &[],
+    }
+}
+
+/// Matrices taking world space to a space, applied in order.
+fn from_world(space: &str) -> &'static [&'static str] {
+    match space {
+        "Object" => &["uniforms.inverse_model"],
+        "View" => &["uniforms.view"],
+        "Clip" => &["uniforms.view", "uniforms.projection"],
+        _ => &[],
+    }
+}
+
+fn selected<'a>(properties: &'a HashMap<String, PropertyValue>, name: &str) -> &'a str {
+    properties
+        .get(name)
+        .and_then(|p| p.selected_option())
+        .unwrap_or_default()
+}
+
+fn is_direction(properties: &HashMap<String, PropertyValue>) -> bool {
+    selected(properties, "type") == "Direction"
+}
+
+// ============================================================================
+// Transform Node - Converts a position or direction between spaces
+// ============================================================================
+
+define_node!(
+    TransformNode,
+    id: "umbra.transform.transform",
+    name: "Transform",
+    aliases: ["Transform"],
+    category: "Transform",
+    inputs: [("In", "Vec3")],
+    outputs: [("Out", "Vec3")],
+    properties: [
+        ("from", PropertyValue::enumeration(0, SPACES)),
+        ("to", PropertyValue::enumeration(1, SPACES)),
+        ("type", PropertyValue::enumeration(0, &["Position", "Direction"])),
+    ],
+    generate: |ctx, inputs, properties| {
+        let (from, to) = (selected(properties, "from"), selected(properties, "to"));
+        if from == to {
+            return inputs[0].clone();
+        }
+        let function = if is_direction(properties) {
+            ctx.helper(&TRANSFORM_DIRECTION)
+        } else {
+            ctx.helper(&TRANSFORM_POSITION)
+        };
+        let result = to_world(from)
+            .iter()
+            .chain(from_world(to))
+            .fold(inputs[0].clone(), |expr, matrix| {
+                format!("{}({}, {})", function, ctx.builtin(matrix), expr)
+            });
+        if is_direction(properties) {
+            ctx.call("normalize", &[result])
+        } else {
+            result
+        }
+    }
+);
+
+// ============================================================================
+// Transformation Matrix Node - Model, view and projection matrices
+// ============================================================================
+
+define_node!(
+    TransformationMatrixNode,
+    id: "umbra.math.transformation_matrix",
+    name: "Transformation Matrix",
+    aliases: ["Transformation Matrix"],
+    category: "Math/Matrix",
+    inputs: [],
+    outputs: [("Out", "Mat4")],
+    properties: [(
+        "matrix",
+        PropertyValue::enumeration(
+            0,
+            &[
+                "Model",
+                "Inverse Model",
+                "View",
+                "Inverse View",
+                "Projection",
+                "Inverse Projection",
+                "View Projection",
+            ],
+        )
+    )],
+    generate: |ctx, _inputs, properties| {
+        let matrix = match selected(properties, "matrix") {
+            "Inverse Model" => "uniforms.inverse_model",
+            "View" => "uniforms.view",
+            "Inverse View" => "uniforms.inverse_view",
+            "Projection" => "uniforms.projection",
+            "Inverse Projection" => "uniforms.inverse_projection",
+            "View Projection" => "uniforms.view_proj",
+            _ => "uniforms.model",
+        };
+        ctx.builtin(matrix)
+    }
+);
+
+// ============================================================================
+// Multiply Matrix Nodes - Matrix times column vector
+// ============================================================================
+
+define_node!(
+    MultiplyMat4Node,
+    id: "umbra.math.multiply_mat4",
+    name: "Multiply Mat4",
+    aliases: ["Multiply Mat4"],
+    category: "Math/Matrix",
+    inputs: [("Matrix", "Mat4"), ("Vector", "Vec4")],
+    outputs: [("Out", "Vec4")],
+    properties: [],
+    generate: |ctx, inputs, _properties| {
+        format!("{}({}, {})", ctx.helper(&MUL_MAT4), inputs[0], inputs[1])
+    }
+);
+
+define_node!(
+    MultiplyMat3Node,
+    id: "umbra.math.multiply_mat3",
+    name: "Multiply Mat3",
+    aliases: ["Multiply Mat3"],
+    category: "Math/Matrix",
+    inputs: [("Matrix", "Mat3"), ("Vector", "Vec3")],
+    outputs: [("Out", "Vec3")],
+    properties: [],
+    generate: |ctx, inputs, _properties| {
+        format!("{}({}, {})", ctx.helper(&MUL_MAT3), inputs[0], inputs[1])
+    }
+);
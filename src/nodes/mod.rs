@@ -1,22 +1,120 @@
+pub mod channel;
+pub mod codegen;
+pub mod color;
 pub mod input;
+pub mod library;
+pub mod lighting;
 #[macro_use]
 pub mod macros;
 pub mod math;
+pub mod matrix;
+pub mod noise;
+pub mod normal;
+pub mod output;
 pub mod registry;
+pub mod uv;
 
 // Re-export common types for convenience
 pub use crate::common::{Property, PropertyValue};
 
+/// Registers the builtin nodes. Later calls do nothing.
 pub fn register_builtins() {
+    static REGISTERED: std::sync::Once = std::sync::Once::new();
+    REGISTERED.call_once(register_all);
+}
+
+fn register_all() {
     let mut registry = registry::global_registry().write().unwrap();
     registry.register(math::AddNode);
+    registry.register(math::SubtractNode);
     registry.register(math::MultiplyNode);
+    registry.register(math::DivideNode);
+    registry.register(math::ModNode);
+    registry.register(math::NegateNode);
+    registry.register(math::OneMinusNode);
+    registry.register(math::PowerNode);
+    registry.register(math::SqrtNode);
+    registry.register(math::ExpNode);
+    registry.register(math::LogNode);
+    registry.register(math::AbsNode);
+    registry.register(math::SignNode);
+    registry.register(math::MinNode);
+    registry.register(math::MaxNode);
+    registry.register(math::ClampNode);
+    registry.register(math::SaturateNode);
+    registry.register(math::RemapNode);
+    registry.register(math::FloorNode);
+    registry.register(math::CeilNode);
+    registry.register(math::RoundNode);
+    registry.register(math::FractNode);
+    registry.register(math::LerpNode);
+    registry.register(math::SmoothstepNode);
+    registry.register(math::StepNode);
     registry.register(math::SinNode);
     registry.register(math::CosNode);
-    registry.register(math::FractNode);
-    registry.register(math::AbsNode);
+    registry.register(math::TanNode);
+    registry.register(math::Atan2Node);
+    registry.register(math::DotNode);
+    registry.register(math::CrossNode);
+    registry.register(math::LengthNode);
+    registry.register(math::DistanceNode);
+    registry.register(math::NormalizeNode);
+    registry.register(channel::CombineVec2Node);
+    registry.register(channel::CombineVec3Node);
+    registry.register(channel::CombineVec4Node);
+    registry.register(channel::SplitNode);
+    registry.register(channel::SwizzleNode);
+    registry.register(color::RgbToHsvNode);
+    registry.register(color::HsvToRgbNode);
+    registry.register(color::SrgbToLinearNode);
+    registry.register(color::LinearToSrgbNode);
+    registry.register(color::HueShiftNode);
+    registry.register(color::SaturationNode);
+    registry.register(color::ContrastNode);
+    registry.register(color::BrightnessNode);
+    registry.register(color::PosterizeNode);
+    registry.register(color::InvertNode);
+    registry.register(color::BlendNode);
+    registry.register(matrix::TransformNode);
+    registry.register(matrix::TransformationMatrixNode);
+    registry.register(matrix::MultiplyMat4Node);
+    registry.register(matrix::MultiplyMat3Node);
     registry.register(input::TimeNode);
     registry.register(input::UVNode);
     registry.register(input::FloatNode);
     registry.register(input::ColorNode);
+    registry.register(input::Float4Node);
+    registry.register(input::PropertyNode);
+    registry.register(input::PositionNode);
+    registry.register(input::NormalNode);
+    registry.register(input::TangentNode);
+    registry.register(input::BitangentNode);
+    registry.register(input::ScreenPositionNode);
+    registry.register(input::PixelCoordinateNode);
+    registry.register(input::ResolutionNode);
+    registry.register(input::MouseNode);
+    registry.register(input::CameraPositionNode);
+    registry.register(input::ViewDirectionNode);
+    registry.register(input::DeltaTimeNode);
+    registry.register(input::FrameCountNode);
+    registry.register(normal::NormalMapNode);
+    registry.register(lighting::FresnelNode);
+    registry.register(lighting::ReflectNode);
+    registry.register(lighting::RefractNode);
+    registry.register(lighting::RimLightNode);
+    registry.register(noise::ValueNoiseNode);
+    registry.register(noise::GradientNoiseNode);
+    registry.register(noise::SimplexNoiseNode);
+    registry.register(noise::WorleyNoiseNode);
+    registry.register(noise::FbmNode);
+    registry.register(uv::TilingAndOffsetNode);
+    registry.register(uv::RotateUVNode);
+    registry.register(uv::PolarCoordinatesNode);
+    registry.register(uv::TwirlNode);
+    registry.register(uv::SpherizeNode);
+    registry.register(uv::TriplanarNode);
+    registry.register(uv::TriplanarWeightsNode);
+    registry.register(uv::FlipbookNode);
+    registry.register(output::VertexOutputNode);
+    registry.register(output::SurfaceOutputNode);
 }
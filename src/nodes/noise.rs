@@ -0,0 +1,823 @@
+//! Procedural noise nodes for shader graphs
+//!
+//! Value, gradient (Perlin), simplex and Worley noise in 2D or 3D, plus FBM.
+//! The noise functions are emitted once per shader as helpers; the nodes only
+//! generate the calls, layering octaves at increasing frequency.
+
+use crate::common::PropertyValue;
+use crate::nodes::codegen::CodegenContext;
+use crate::nodes::registry::ShaderFunction;
+use std::collections::HashMap;
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+pub static HASH21: ShaderFunction = ShaderFunction {
+    name: "umbra_hash21",
+    deps: &[],
+    wgsl: "fn umbra_hash21(p: vec2<f32>) -> f32 {
+  return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+",
+    glsl: HASH21_GLSL,
+    hlsl: "float umbra_hash21(float2 p) {
+  return frac(sin(dot(p, float2(127.1, 311.7))) * 43758.5453);
+}
+",
+    godot: HASH21_GLSL,
+};
+
+const HASH21_GLSL: &str = "float umbra_hash21(vec2 p) {
+  return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+";
+
+pub static HASH22: ShaderFunction = ShaderFunction {
+    name: "umbra_hash22",
+    deps: &[],
+    wgsl: "fn umbra_hash22(p: vec2<f32>) -> vec2<f32> {
+  let q = vec2<f32>(dot(p, vec2<f32>(127.1, 311.7)), dot(p, vec2<f32>(269.5, 183.3)));
+  return fract(sin(q) * 43758.5453);
+}
+",
+    glsl: HASH22_GLSL,
+    hlsl: "float2 umbra_hash22(float2 p) {
+  float2 q = float2(dot(p, float2(127.1, 311.7)), dot(p, float2(269.5, 183.3)));
+  return frac(sin(q) * 43758.5453);
+}
+",
+    godot: HASH22_GLSL,
+};
+
+const HASH22_GLSL: &str = "vec2 umbra_hash22(vec2 p) {
+  vec2 q = vec2(dot(p, vec2(127.1, 311.7)), dot(p, vec2(269.5, 183.3)));
+  return fract(sin(q) * 43758.5453);
+}
+";
+
+pub static HASH31: ShaderFunction = ShaderFunction {
+    name: "umbra_hash31",
+    deps: &[],
+    wgsl: "fn umbra_hash31(p: vec3<f32>) -> f32 {
+  return fract(sin(dot(p, vec3<f32>(127.1, 311.7, 74.7))) * 43758.5453);
+}
+",
+    glsl: HASH31_GLSL,
+    hlsl: "float umbra_hash31(float3 p) {
+  return frac(sin(dot(p, float3(127.1, 311.7, 74.7))) * 43758.5453);
+}
+",
+    godot: HASH31_GLSL,
+};
+
+const HASH31_GLSL: &str = "float umbra_hash31(vec3 p) {
+  return fract(sin(dot(p, vec3(127.1, 311.7, 74.7))) * 43758.5453);
+}
+";
+
+pub static HASH33: ShaderFunction = ShaderFunction {
+    name: "umbra_hash33",
+    deps: &[],
+    wgsl: "fn umbra_hash33(p: vec3<f32>) -> vec3<f32> {
+  let q = vec3<f32>(
+    dot(p, vec3<f32>(127.1, 311.7, 74.7)),
+    dot(p, vec3<f32>(269.5, 183.3, 246.1)),
+    dot(p, vec3<f32>(113.5, 271.9, 124.6))
+  );
+  return fract(sin(q) * 43758.5453);
+}
+",
+    glsl: HASH33_GLSL,
+    hlsl: "float3 umbra_hash33(float3 p) {
+  float3 q = float3(
+    dot(p, float3(127.1, 311.7, 74.7)),
+    dot(p, float3(269.5, 183.3, 246.1)),
+    dot(p, float3(113.5, 271.9, 124.6))
+  );
+  return frac(sin(q) * 43758.5453);
+}
+",
+    godot: HASH33_GLSL,
+};
+
+const HASH33_GLSL: &str = "vec3 umbra_hash33(vec3 p) {
+  vec3 q = vec3(
+    dot(p, vec3(127.1, 311.7, 74.7)),
+    dot(p, vec3(269.5, 183.3, 246.1)),
+    dot(p, vec3(113.5, 271.9, 124.6))
+  );
+  return fract(sin(q) * 43758.5453);
+}
+";
+
+/// Unit gradient for a 2D lattice cell.
+pub static GRAD_2D: ShaderFunction = ShaderFunction {
+    name: "umbra_grad_2d",
+    deps: &[&HASH21],
+    wgsl: "fn umbra_grad_2d(cell: vec2<f32>) -> vec2<f32> {
+  let a = umbra_hash21(cell) * 6.2831853;
+  return vec2<f32>(cos(a), sin(a));
+}
+",
+    glsl: GRAD_2D_GLSL,
+    hlsl: "float2 umbra_grad_2d(float2 cell) {
+  float a = umbra_hash21(cell) * 6.2831853;
+  return float2(cos(a), sin(a));
+}
+",
+    godot: GRAD_2D_GLSL,
+};
+
+const GRAD_2D_GLSL: &str = "vec2 umbra_grad_2d(vec2 cell) {
+  float a = umbra_hash21(cell) * 6.2831853;
+  return vec2(cos(a), sin(a));
+}
+";
+
+/// Random gradient in [-1, 1]^3 for a 3D lattice cell.
+pub static GRAD_3D: ShaderFunction = ShaderFunction {
+    name: "umbra_grad_3d",
+    deps: &[&HASH33],
+    wgsl: "fn umbra_grad_3d(cell: vec3<f32>) -> vec3<f32> {
+  return umbra_hash33(cell) * 2.0 - 1.0;
+}
+",
+    glsl: GRAD_3D_GLSL,
+    hlsl: "float3 umbra_grad_3d(float3 cell) {
+  return umbra_hash33(cell) * 2.0 - 1.0;
+}
+",
+    godot: GRAD_3D_GLSL,
+};
+
+const GRAD_3D_GLSL: &str = "vec3 umbra_grad_3d(vec3 cell) {
+  return umbra_hash33(cell) * 2.0 - 1.0;
+}
+";
+
+pub static VALUE_NOISE_2D: ShaderFunction = ShaderFunction {
+    name: "umbra_value_noise_2d",
+    deps: &[&HASH21],
+    wgsl: "fn umbra_value_noise_2d(p: vec2<f32>) -> f32 {
+  let i = floor(p);
+  let f = fract(p);
+  let u = f * f * (3.0 - 2.0 * f);
+  let a = umbra_hash21(i);
+  let b = umbra_hash21(i + vec2<f32>(1.0, 0.0));
+  let c = umbra_hash21(i + vec2<f32>(0.0, 1.0));
+  let d = umbra_hash21(i + vec2<f32>(1.0, 1.0));
+  return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+",
+    glsl: VALUE_NOISE_2D_GLSL,
+    hlsl: "float umbra_value_noise_2d(float2 p) {
+  float2 i = floor(p);
+  float2 f = frac(p);
+  float2 u = f * f * (3.0 - 2.0 * f);
+  float a = umbra_hash21(i);
+  float b = umbra_hash21(i + float2(1.0, 0.0));
+  float c = umbra_hash21(i + float2(0.0, 1.0));
+  float d = umbra_hash21(i + float2(1.0, 1.0));
+  return lerp(lerp(a, b, u.x), lerp(c, d, u.x), u.y);
+}
+",
+    godot: VALUE_NOISE_2D_GLSL,
+};
+
+const VALUE_NOISE_2D_GLSL: &str = "float umbra_value_noise_2d(vec2 p) {
+  vec2 i = floor(p);
+  vec2 f = fract(p);
+  vec2 u = f * f * (3.0 - 2.0 * f);
+  float a = umbra_hash21(i);
+  float b = umbra_hash21(i + vec2(1.0, 0.0));
+  float c = umbra_hash21(i + vec2(0.0, 1.0));
+  float d = umbra_hash21(i + vec2(1.0, 1.0));
+  return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+";
+
+pub static VALUE_NOISE_3D: ShaderFunction = ShaderFunction {
+    name: "umbra_value_noise_3d",
+    deps: &[&HASH31],
+    wgsl: "fn umbra_value_noise_3d(p: vec3<f32>) -> f32 {
+  let i = floor(p);
+  let f = fract(p);
+  let u = f * f * (3.0 - 2.0 * f);
+  let x00 = mix(umbra_hash31(i), umbra_hash31(i + vec3<f32>(1.0, 0.0, 0.0)), u.x);
+  let x10 = mix(umbra_hash31(i + vec3<f32>(0.0, 1.0, 0.0)), umbra_hash31(i + vec3<f32>(1.0, 1.0, 0.0)), u.x);
+  let x01 = mix(umbra_hash31(i + vec3<f32>(0.0, 0.0, 1.0)), umbra_hash31(i + vec3<f32>(1.0, 0.0, 1.0)), u.x);
+  let x11 = mix(umbra_hash31(i + vec3<f32>(0.0, 1.0, 1.0)), umbra_hash31(i + vec3<f32>(1.0, 1.0, 1.0)), u.x);
+  return mix(mix(x00, x10, u.y), mix(x01, x11, u.y), u.z);
+}
+",
+    glsl: VALUE_NOISE_3D_GLSL,
+    hlsl: "float umbra_value_noise_3d(float3 p) {
+  float3 i = floor(p);
+  float3 f = frac(p);
+  float3 u = f * f * (3.0 - 2.0 * f);
+  float x00 = lerp(umbra_hash31(i), umbra_hash31(i + float3(1.0, 0.0, 0.0)), u.x);
+  float x10 = lerp(umbra_hash31(i + float3(0.0, 1.0, 0.0)), umbra_hash31(i + float3(1.0, 1.0, 0.0)), u.x);
+  float x01 = lerp(umbra_hash31(i + float3(0.0, 0.0, 1.0)), umbra_hash31(i + float3(1.0, 0.0, 1.0)), u.x);
+  float x11 = lerp(umbra_hash31(i + float3(0.0, 1.0, 1.0)), umbra_hash31(i + float3(1.0, 1.0, 1.0)), u.x);
+  return lerp(lerp(x00, x10, u.y), lerp(x01, x11, u.y), u.z);
+}
+",
+    godot: VALUE_NOISE_3D_GLSL,
+};
+
+const VALUE_NOISE_3D_GLSL: &str = "float umbra_value_noise_3d(vec3 p) {
+  vec3 i = floor(p);
+  vec3 f = fract(p);
+  vec3 u = f * f * (3.0 - 2.0 * f);
+  float x00 = mix(umbra_hash31(i), umbra_hash31(i + vec3(1.0, 0.0, 0.0)), u.x);
+  float x10 = mix(umbra_hash31(i + vec3(0.0, 1.0, 0.0)), umbra_hash31(i + vec3(1.0, 1.0, 0.0)), u.x);
+  float x01 = mix(umbra_hash31(i + vec3(0.0, 0.0, 1.0)), umbra_hash31(i + vec3(1.0, 0.0, 1.0)), u.x);
+  float x11 = mix(umbra_hash31(i + vec3(0.0, 1.0, 1.0)), umbra_hash31(i + vec3(1.0, 1.0, 1.0)), u.x);
+  return mix(mix(x00, x10, u.y), mix(x01, x11, u.y), u.z);
+}
+";
+
+pub static GRADIENT_NOISE_2D: ShaderFunction = ShaderFunction {
+    name: "umbra_gradient_noise_2d",
+    deps: &[&GRAD_2D],
+    wgsl: "fn umbra_gradient_noise_2d(p: vec2<f32>) -> f32 {
+  let i = floor(p);
+  let f = fract(p);
+  let u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+  let a = dot(umbra_grad_2d(i), f);
+  let b = dot(umbra_grad_2d(i + vec2<f32>(1.0, 0.0)), f - vec2<f32>(1.0, 0.0));
+  let c = dot(umbra_grad_2d(i + vec2<f32>(0.0, 1.0)), f - vec2<f32>(0.0, 1.0));
+  let d = dot(umbra_grad_2d(i + vec2<f32>(1.0, 1.0)), f - vec2<f32>(1.0, 1.0));
+  return mix(mix(a, b, u.x), mix(c, d, u.x), u.y) * 0.7071 + 0.5;
+}
+",
+    glsl: GRADIENT_NOISE_2D_GLSL,
+    hlsl: "float umbra_gradient_noise_2d(float2 p) {
+  float2 i = floor(p);
+  float2 f = frac(p);
+  float2 u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+  float a = dot(umbra_grad_2d(i), f);
+  float b = dot(umbra_grad_2d(i + float2(1.0, 0.0)), f - float2(1.0, 0.0));
+  float c = dot(umbra_grad_2d(i + float2(0.0, 1.0)), f - float2(0.0, 1.0));
+  float d = dot(umbra_grad_2d(i + float2(1.0, 1.0)), f - float2(1.0, 1.0));
+  return lerp(lerp(a, b, u.x), lerp(c, d, u.x), u.y) * 0.7071 + 0.5;
+}
+",
+    godot: GRADIENT_NOISE_2D_GLSL,
+};
+
+const GRADIENT_NOISE_2D_GLSL: &str = "float umbra_gradient_noise_2d(vec2 p) {
+  vec2 i = floor(p);
+  vec2 f = fract(p);
+  vec2 u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+  float a = dot(umbra_grad_2d(i), f);
+  float b = dot(umbra_grad_2d(i + vec2(1.0, 0.0)), f - vec2(1.0, 0.0));
+  float c = dot(umbra_grad_2d(i + vec2(0.0, 1.0)), f - vec2(0.0, 1.0));
+  float d = dot(umbra_grad_2d(i + vec2(1.0, 1.0)), f - vec2(1.0, 1.0));
+  return mix(mix(a, b, u.x), mix(c, d, u.x), u.y) * 0.7071 + 0.5;
+}
+";
+
+/// Gradient contribution of one corner of a 3D lattice cell.
+pub static GRADIENT_CORNER_3D: ShaderFunction = ShaderFunction {
+    name: "umbra_gradient_corner_3d",
+    deps: &[&GRAD_3D],
+    wgsl: "fn umbra_gradient_corner_3d(i: vec3<f32>, f: vec3<f32>, corner: vec3<f32>) -> f32 {
+  return dot(umbra_grad_3d(i + corner), f - corner);
+}
+",
+    glsl: GRADIENT_CORNER_3D_GLSL,
+    hlsl: "float umbra_gradient_corner_3d(float3 i, float3 f, float3 corner) {
+  return dot(umbra_grad_3d(i + corner), f - corner);
+}
+",
+    godot: GRADIENT_CORNER_3D_GLSL,
+};
+
+const GRADIENT_CORNER_3D_GLSL: &str =
+    "float umbra_gradient_corner_3d(vec3 i, vec3 f, vec3 corner) {
+  return dot(umbra_grad_3d(i + corner), f - corner);
+}
+";
+
+pub static GRADIENT_NOISE_3D: ShaderFunction = ShaderFunction {
+    name: "umbra_gradient_noise_3d",
+    deps: &[&GRADIENT_CORNER_3D],
+    wgsl: "fn umbra_gradient_noise_3d(p: vec3<f32>) -> f32 {
+  let i = floor(p);
+  let f = fract(p);
+  let u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+  let x00 = mix(umbra_gradient_corner_3d(i, f, vec3<f32>(0.0, 0.0, 0.0)), umbra_gradient_corner_3d(i, f, vec3<f32>(1.0, 0.0, 0.0)), u.x);
+  let x10 = mix(umbra_gradient_corner_3d(i, f, vec3<f32>(0.0, 1.0, 0.0)), umbra_gradient_corner_3d(i, f, vec3<f32>(1.0, 1.0, 0.0)), u.x);
+  let x01 = mix(umbra_gradient_corner_3d(i, f, vec3<f32>(0.0, 0.0, 1.0)), umbra_gradient_corner_3d(i, f, vec3<f32>(1.0, 0.0, 1.0)), u.x);
+  let x11 = mix(umbra_gradient_corner_3d(i, f, vec3<f32>(0.0, 1.0, 1.0)), umbra_gradient_corner_3d(i, f, vec3<f32>(1.0, 1.0, 1.0)), u.x);
+  return mix(mix(x00, x10, u.y), mix(x01, x11, u.y), u.z) * 0.5 + 0.5;
+}
+",
+    glsl: GRADIENT_NOISE_3D_GLSL,
+    hlsl: "float umbra_gradient_noise_3d(float3 p) {
+  float3 i = floor(p);
+  float3 f = frac(p);
+  float3 u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+  float x00 = lerp(umbra_gradient_corner_3d(i, f, float3(0.0, 0.0, 0.0)), umbra_gradient_corner_3d(i, f, float3(1.0, 0.0, 0.0)), u.x);
+  float x10 = lerp(umbra_gradient_corner_3d(i, f, float3(0.0, 1.0, 0.0)), umbra_gradient_corner_3d(i, f, float3(1.0, 1.0, 0.0)), u.x);
+  float x01 = lerp(umbra_gradient_corner_3d(i, f, float3(0.0, 0.0, 1.0)), umbra_gradient_corner_3d(i, f, float3(1.0, 0.0, 1.0)), u.x);
+  float x11 = lerp(umbra_gradient_corner_3d(i, f, float3(0.0, 1.0, 1.0)), umbra_gradient_corner_3d(i, f, float3(1.0, 1.0, 1.0)), u.x);
+  return lerp(lerp(x00, x10, u.y), lerp(x01, x11, u.y), u.z) * 0.5 + 0.5;
+}
+",
+    godot: GRADIENT_NOISE_3D_GLSL,
+};
+
+const GRADIENT_NOISE_3D_GLSL: &str = "float umbra_gradient_noise_3d(vec3 p) {
+  vec3 i = floor(p);
+  vec3 f = fract(p);
+  vec3 u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+  float x00 = mix(umbra_gradient_corner_3d(i, f, vec3(0.0, 0.0, 0.0)), umbra_gradient_corner_3d(i, f, vec3(1.0, 0.0, 0.0)), u.x);
+  float x10 = mix(umbra_gradient_corner_3d(i, f, vec3(0.0, 1.0, 0.0)), umbra_gradient_corner_3d(i, f, vec3(1.0, 1.0, 0.0)), u.x);
+  float x01 = mix(umbra_gradient_corner_3d(i, f, vec3(0.0, 0.0, 1.0)), umbra_gradient_corner_3d(i, f, vec3(1.0, 0.0, 1.0)), u.x);
+  float x11 = mix(umbra_gradient_corner_3d(i, f, vec3(0.0, 1.0, 1.0)), umbra_gradient_corner_3d(i, f, vec3(1.0, 1.0, 1.0)), u.x);
+  return mix(mix(x00, x10, u.y), mix(x01, x11, u.y), u.z) * 0.5 + 0.5;
+}
+";
+
+pub static SIMPLEX_NOISE_2D: ShaderFunction = ShaderFunction {
+    name: "umbra_simplex_noise_2d",
+    deps: &[&GRAD_2D],
+    wgsl: "fn umbra_simplex_noise_2d(p: vec2<f32>) -> f32 {
+  let k1 = 0.366025404;
+  let k2 = 0.211324865;
+  let i = floor(p + (p.x + p.y) * k1);
+  let a = p - i + (i.x + i.y) * k2;
+  let m = step(a.y, a.x);
+  let o = vec2<f32>(m, 1.0 - m);
+  let b = a - o + k2;
+  let c = a - 1.0 + 2.0 * k2;
+  let h = max(0.5 - vec3<f32>(dot(a, a), dot(b, b), dot(c, c)), vec3<f32>(0.0));
+  let n = h * h * h * h * vec3<f32>(dot(a, umbra_grad_2d(i)), dot(b, umbra_grad_2d(i + o)), dot(c, umbra_grad_2d(i + 1.0)));
+  return dot(n, vec3<f32>(70.0)) * 0.5 + 0.5;
+}
+",
+    glsl: SIMPLEX_NOISE_2D_GLSL,
+    hlsl: "float umbra_simplex_noise_2d(float2 p) {
+  const float k1 = 0.366025404;
+  const float k2 = 0.211324865;
+  float2 i = floor(p + (p.x + p.y) * k1);
+  float2 a = p - i + (i.x + i.y) * k2;
+  float m = step(a.y, a.x);
+  float2 o = float2(m, 1.0 - m);
+  float2 b = a - o + k2;
+  float2 c = a - 1.0 + 2.0 * k2;
+  float3 h = max(0.5 - float3(dot(a, a), dot(b, b), dot(c, c)), 0.0);
+  float3 n = h * h * h * h * float3(dot(a, umbra_grad_2d(i)), dot(b, umbra_grad_2d(i + o)), dot(c, umbra_grad_2d(i + 1.0)));
+  return dot(n, float3(70.0, 70.0, 70.0)) * 0.5 + 0.5;
+}
+",
+    godot: SIMPLEX_NOISE_2D_GLSL,
+};
+
+const SIMPLEX_NOISE_2D_GLSL: &str = "float umbra_simplex_noise_2d(vec2 p) {
+  const float k1 = 0.366025404;
+  const float k2 = 0.211324865;
+  vec2 i = floor(p + (p.x + p.y) * k1);
+  vec2 a = p - i + (i.x + i.y) * k2;
+  float m = step(a.y, a.x);
+  vec2 o = vec2(m, 1.0 - m);
+  vec2 b = a - o + k2;
+  vec2 c = a - 1.0 + 2.0 * k2;
+  vec3 h = max(0.5 - vec3(dot(a, a), dot(b, b), dot(c, c)), vec3(0.0));
+  vec3 n = h * h * h * h * vec3(dot(a, umbra_grad_2d(i)), dot(b, umbra_grad_2d(i + o)), dot(c, umbra_grad_2d(i + 1.0)));
+  return dot(n, vec3(70.0)) * 0.5 + 0.5;
+}
+";
+
+pub static SIMPLEX_NOISE_3D: ShaderFunction = ShaderFunction {
+    name: "umbra_simplex_noise_3d",
+    deps: &[&GRAD_3D],
+    wgsl: "fn umbra_simplex_noise_3d(p: vec3<f32>) -> f32 {
+  let f3 = 0.333333333;
+  let g3 = 0.166666667;
+  let s = floor(p + dot(p, vec3<f32>(f3)));
+  let x0 = p - s + dot(s, vec3<f32>(g3));
+  let e = step(vec3<f32>(0.0), x0 - x0.yzx);
+  let i1 = e * (1.0 - e.zxy);
+  let i2 = 1.0 - e.zxy * (1.0 - e);
+  let x1 = x0 - i1 + g3;
+  let x2 = x0 - i2 + 2.0 * g3;
+  let x3 = x0 - 1.0 + 3.0 * g3;
+  var w = max(0.6 - vec4<f32>(dot(x0, x0), dot(x1, x1), dot(x2, x2), dot(x3, x3)), vec4<f32>(0.0));
+  w = w * w;
+  w = w * w;
+  let d = vec4<f32>(
+    dot(x0, umbra_grad_3d(s)),
+    dot(x1, umbra_grad_3d(s + i1)),
+    dot(x2, umbra_grad_3d(s + i2)),
+    dot(x3, umbra_grad_3d(s + 1.0))
+  );
+  return dot(d * w, vec4<f32>(26.0)) * 0.5 + 0.5;
+}
+",
+    glsl: SIMPLEX_NOISE_3D_GLSL,
+    hlsl: "float umbra_simplex_noise_3d(float3 p) {
+  const float f3 = 0.333333333;
+  const float g3 = 0.166666667;
+  float3 s = floor(p + dot(p, float3(f3, f3, f3)));
+  float3 x0 = p - s + dot(s, float3(g3, g3, g3));
+  float3 e = step(0.0, x0 - x0.yzx);
+  float3 i1 = e * (1.0 - e.zxy);
+  float3 i2 = 1.0 - e.zxy * (1.0 - e);
+  float3 x1 = x0 - i1 + g3;
+  float3 x2 = x0 - i2 + 2.0 * g3;
+  float3 x3 = x0 - 1.0 + 3.0 * g3;
+  float4 w = max(0.6 - float4(dot(x0, x0), dot(x1, x1), dot(x2, x2), dot(x3, x3)), 0.0);
+  w = w * w;
+  w = w * w;
+  float4 d = float4(
+    dot(x0, umbra_grad_3d(s)),
+    dot(x1, umbra_grad_3d(s + i1)),
+    dot(x2, umbra_grad_3d(s + i2)),
+    dot(x3, umbra_grad_3d(s + 1.0))
+  );
+  return dot(d * w, float4(26.0, 26.0, 26.0, 26.0)) * 0.5 + 0.5;
+}
+",
+    godot: SIMPLEX_NOISE_3D_GLSL,
+};
+
+const SIMPLEX_NOISE_3D_GLSL: &str = "float umbra_simplex_noise_3d(vec3 p) {
+  const float f3 = 0.333333333;
+  const float g3 = 0.166666667;
+  vec3 s = floor(p + dot(p, vec3(f3)));
+  vec3 x0 = p - s + dot(s, vec3(g3));
+  vec3 e = step(vec3(0.0), x0 - x0.yzx);
+  vec3 i1 = e * (1.0 - e.zxy);
+  vec3 i2 = 1.0 - e.zxy * (1.0 - e);
+  vec3 x1 = x0 - i1 + g3;
+  vec3 x2 = x0 - i2 + 2.0 * g3;
+  vec3 x3 = x0 - 1.0 + 3.0 * g3;
+  vec4 w = max(0.6 - vec4(dot(x0, x0), dot(x1, x1), dot(x2, x2), dot(x3, x3)), vec4(0.0));
+  w = w * w;
+  w = w * w;
+  vec4 d = vec4(
+    dot(x0, umbra_grad_3d(s)),
+    dot(x1, umbra_grad_3d(s + i1)),
+    dot(x2, umbra_grad_3d(s + i2)),
+    dot(x3, umbra_grad_3d(s + 1.0))
+  );
+  return dot(d * w, vec4(26.0)) * 0.5 + 0.5;
+}
+";
+
+/// Distance to the nearest feature point in `x` and that cell's random ID
+/// in `y`.
+pub static WORLEY_2D: ShaderFunction = ShaderFunction {
+    name: "umbra_worley_2d",
+    deps: &[&HASH21, &HASH22],
+    wgsl: "fn umbra_worley_2d(p: vec2<f32>) -> vec2<f32> {
+  let i = floor(p);
+  let f = fract(p);
+  var nearest = 8.0;
+  var cell_id = 0.0;
+  for (var y = -1; y <= 1; y++) {
+    for (var x = -1; x <= 1; x++) {
+      let neighbor = vec2<f32>(f32(x), f32(y));
+      let feature = neighbor + umbra_hash22(i + neighbor);
+      let dist = length(feature - f);
+      if (dist < nearest) {
+        nearest = dist;
+        cell_id = umbra_hash21(i + neighbor);
+      }
+    }
+  }
+  return vec2<f32>(nearest, cell_id);
+}
+",
+    glsl: WORLEY_2D_GLSL,
+    hlsl: "float2 umbra_worley_2d(float2 p) {
+  float2 i = floor(p);
+  float2 f = frac(p);
+  float nearest = 8.0;
+  float cell_id = 0.0;
+  for (int y = -1; y <= 1; y++) {
+    for (int x = -1; x <= 1; x++) {
+      float2 neighbor = float2(float(x), float(y));
+      float2 feature = neighbor + umbra_hash22(i + neighbor);
+      float dist = length(feature - f);
+      if (dist < nearest) {
+        nearest = dist;
+        cell_id = umbra_hash21(i + neighbor);
+      }
+    }
+  }
+  return float2(nearest, cell_id);
+}
+",
+    godot: WORLEY_2D_GLSL,
+};
+
+const WORLEY_2D_GLSL: &str = "vec2 umbra_worley_2d(vec2 p) {
+  vec2 i = floor(p);
+  vec2 f = fract(p);
+  float nearest = 8.0;
+  float cell_id = 0.0;
+  for (int y = -1; y <= 1; y++) {
+    for (int x = -1; x <= 1; x++) {
+      vec2 neighbor = vec2(float(x), float(y));
+      vec2 feature = neighbor + umbra_hash22(i + neighbor);
+      float dist = length(feature - f);
+      if (dist < nearest) {
+        nearest = dist;
+        cell_id = umbra_hash21(i + neighbor);
+      }
+    }
+  }
+  return vec2(nearest, cell_id);
+}
+";
+
+pub static WORLEY_3D: ShaderFunction = ShaderFunction {
+    name: "umbra_worley_3d",
+    deps: &[&HASH31, &HASH33],
+    wgsl: "fn umbra_worley_3d(p: vec3<f32>) -> vec2<f32> {
+  let i = floor(p);
+  let f = fract(p);
+  var nearest = 8.0;
+  var cell_id = 0.0;
+  for (var z = -1; z <= 1; z++) {
+    for (var y = -1; y <= 1; y++) {
+      for (var x = -1; x <= 1; x++) {
+        let neighbor = vec3<f32>(f32(x), f32(y), f32(z));
+        let feature = neighbor + umbra_hash33(i + neighbor);
+        let dist = length(feature - f);
+        if (dist < nearest) {
+          nearest = dist;
+          cell_id = umbra_hash31(i + neighbor);
+        }
+      }
+    }
+  }
+  return vec2<f32>(nearest, cell_id);
+}
+",
+    glsl: WORLEY_3D_GLSL,
+    hlsl: "float2 umbra_worley_3d(float3 p) {
+  float3 i = floor(p);
+  float3 f = frac(p);
+  float nearest = 8.0;
+  float cell_id = 0.0;
+  for (int z = -1; z <= 1; z++) {
+    for (int y = -1; y <= 1; y++) {
+      for (int x = -1; x <= 1; x++) {
+        float3 neighbor = float3(float(x), float(y), float(z));
+        float3 feature = neighbor + umbra_hash33(i + neighbor);
+        float dist = length(feature - f);
+        if (dist < nearest) {
+          nearest = dist;
+          cell_id = umbra_hash31(i + neighbor);
+        }
+      }
+    }
+  }
+  return float2(nearest, cell_id);
+}
+",
+    godot: WORLEY_3D_GLSL,
+};
+
+const WORLEY_3D_GLSL: &str = "vec2 umbra_worley_3d(vec3 p) {
+  vec3 i = floor(p);
+  vec3 f = fract(p);
+  float nearest = 8.0;
+  float cell_id = 0.0;
+  for (int z = -1; z <= 1; z++) {
+    for (int y = -1; y <= 1; y++) {
+      for (int x = -1; x <= 1; x++) {
+        vec3 neighbor = vec3(float(x), float(y), float(z));
+        vec3 feature = neighbor + umbra_hash33(i + neighbor);
+        float dist = length(feature - f);
+        if (dist < nearest) {
+          nearest = dist;
+          cell_id = umbra_hash31(i + neighbor);
+        }
+      }
+    }
+  }
+  return vec2(nearest, cell_id);
+}
+";
+
+// ============================================================================
+// Code generation
+// ============================================================================
+
+const DEFAULT_COORD: &str = "vec3<f32>(in.uv, 0.0)";
+const MAX_OCTAVES: usize = 8;
+
+fn dimensions() -> PropertyValue {
+    PropertyValue::enumeration(0, &["2D", "3D"])
+}
+
+fn is_3d(properties: &HashMap<String, PropertyValue>) -> bool {
+    properties
+        .get("dimensions")
+        .and_then(|p| p.selected_option())
+        == Some("3D")
+}
+
+fn float_property(properties: &HashMap<String, PropertyValue>, name: &str, default: f32) -> f32 {
+    properties
+        .get(name)
+        .and_then(|p| p.as_float())
+        .unwrap_or(default)
+}
+
+/// Domain offset decorrelating seeds and octaves, in [0, 100).
+fn seed_offset(seed: f32, octave: usize) -> [f32; 3] {
+    let s = seed.round() + octave as f32 * 17.0;
+    [12.9898, 78.233, 37.719].map(|k| ((s * k).sin() * 43758.547).fract().abs() * 100.0)
+}
+
+/// Calls the 2D or 3D noise helper for every octave and sums the layers,
+/// normalized so the result keeps the range of a single layer.
+fn layered_noise(
+    ctx: &mut CodegenContext,
+    inputs: &[String],
+    properties: &HashMap<String, PropertyValue>,
+    noise: [&'static ShaderFunction; 2],
+    default_scale: f32,
+    default_octaves: f32,
+) -> String {
+    let scale = float_property(properties, "scale", default_scale);
+    let seed = float_property(properties, "seed", 0.0);
+    let octaves = float_property(properties, "octaves", default_octaves)
+        .round()
+        .clamp(1.0, MAX_OCTAVES as f32) as usize;
+    let lacunarity = float_property(properties, "lacunarity", 2.0);
+    let gain = float_property(properties, "gain", 0.5);
+
+    let (coord, width, function) = if is_3d(properties) {
+        (format!("({})", inputs[0]), 3, noise[1])
+    } else {
+        (format!("({}).xy", inputs[0]), 2, noise[0])
+    };
+    let name = ctx.helper(function);
+
+    let mut layers = Vec::new();
+    let mut total = 0.0;
+    let (mut frequency, mut amplitude) = (scale, 1.0);
+    for octave in 0..octaves {
+        let offset = ctx.literal(&seed_offset(seed, octave)[..width]);
+        let call = format!("{}({} * {:.3} + {})", name, coord, frequency, offset);
+        layers.push(if octave == 0 && octaves == 1 {
+            call
+        } else {
+            format!("{} * {:.4}", call, amplitude)
+        });
+        total += amplitude;
+        frequency *= lacunarity;
+        amplitude *= gain;
+    }
+
+    if octaves == 1 {
+        layers.remove(0)
+    } else {
+        format!("(({}) / {:.4})", layers.join(" + "), total)
+    }
+}
+
+// ============================================================================
+// Value Noise Node - Interpolated random values on a lattice
+// ============================================================================
+
+define_node!(
+    ValueNoiseNode,
+    id: "umbra.noise.value_noise",
+    name: "Value Noise",
+    aliases: ["Value Noise"],
+    category: "Noise",
+    inputs: [("Coord", "Vec3", DEFAULT_COORD)],
+    outputs: [("Out", "Float")],
+    properties: [
+        ("dimensions", dimensions()),
+        ("scale", PropertyValue::Float(10.0)),
+        ("seed", PropertyValue::Float(0.0)),
+        ("octaves", PropertyValue::Float(1.0)),
+    ],
+    generate: |ctx, inputs, properties| {
+        layered_noise(ctx, inputs, properties, [&VALUE_NOISE_2D, &VALUE_NOISE_3D], 10.0, 1.0)
+    }
+);
+
+// ============================================================================
+// Gradient Noise Node - Perlin noise
+// ============================================================================
+
+define_node!(
+    GradientNoiseNode,
+    id: "umbra.noise.gradient_noise",
+    name: "Gradient Noise",
+    aliases: ["Gradient Noise"],
+    category: "Noise",
+    inputs: [("Coord", "Vec3", DEFAULT_COORD)],
+    outputs: [("Out", "Float")],
+    properties: [
+        ("dimensions", dimensions()),
+        ("scale", PropertyValue::Float(10.0)),
+        ("seed", PropertyValue::Float(0.0)),
+        ("octaves", PropertyValue::Float(1.0)),
+    ],
+    generate: |ctx, inputs, properties| {
+        layered_noise(ctx, inputs, properties, [&GRADIENT_NOISE_2D, &GRADIENT_NOISE_3D], 10.0, 1.0)
+    }
+);
+
+// ============================================================================
+// Simplex Noise Node - Gradient noise on a simplex grid
+// ============================================================================
+
+define_node!(
+    SimplexNoiseNode,
+    id: "umbra.noise.simplex_noise",
+    name: "Simplex Noise",
+    aliases: ["Simplex Noise"],
+    category: "Noise",
+    inputs: [("Coord", "Vec3", DEFAULT_COORD)],
+    outputs: [("Out", "Float")],
+    properties: [
+        ("dimensions", dimensions()),
+        ("scale", PropertyValue::Float(10.0)),
+        ("seed", PropertyValue::Float(0.0)),
+        ("octaves", PropertyValue::Float(1.0)),
+    ],
+    generate: |ctx, inputs, properties| {
+        layered_noise(ctx, inputs, properties, [&SIMPLEX_NOISE_2D, &SIMPLEX_NOISE_3D], 10.0, 1.0)
+    }
+);
+
+// ============================================================================
+// Worley Noise Node - Cellular (Voronoi) noise
+// ============================================================================
+
+define_node!(
+    WorleyNoiseNode,
+    id: "umbra.noise.worley_noise",
+    name: "Worley Noise",
+    aliases: ["Worley Noise"],
+    keywords: ["voronoi", "cellular"],
+    category: "Noise",
+    inputs: [("Coord", "Vec3", DEFAULT_COORD)],
+    outputs: [("Distance", "Float", "x"), ("Cell ID", "Float", "y")],
+    properties: [
+        ("dimensions", dimensions()),
+        ("scale", PropertyValue::Float(5.0)),
+        ("seed", PropertyValue::Float(0.0)),
+        ("octaves", PropertyValue::Float(1.0)),
+    ],
+    generate: |ctx, inputs, properties| {
+        // Octaves layer the distance; the cell ID always comes from the
+        // base octave so it stays constant within a cell.
+        let layered = layered_noise(ctx, inputs, properties, [&WORLEY_2D, &WORLEY_3D], 5.0, 1.0);
+        let octaves = float_property(properties, "octaves", 1.0).round();
+        if octaves <= 1.0 {
+            layered
+        } else {
+            let mut base = properties.clone();
+            base.insert("octaves".to_string(), PropertyValue::Float(1.0));
+            let cell = layered_noise(ctx, inputs, &base, [&WORLEY_2D, &WORLEY_3D], 5.0, 1.0);
+            ctx.vector(&[format!("{}.x", layered), format!("{}.y", cell)])
+        }
+    }
+);
+
+// ============================================================================
+// FBM Node - Fractal sum of noise octaves with adjustable falloff
+// ============================================================================
+
+define_node!(
+    FbmNode,
+    id: "umbra.noise.fbm",
+    name: "FBM",
+    aliases: ["FBM"],
+    keywords: ["fractal", "octaves", "turbulence"],
+    category: "Noise",
+    inputs: [("Coord", "Vec3", DEFAULT_COORD)],
+    outputs: [("Out", "Float")],
+    properties: [
+        ("noise", PropertyValue::enumeration(1, &["Value", "Gradient", "Simplex"])),
+        ("dimensions", dimensions()),
+        ("scale", PropertyValue::Float(4.0)),
+        ("seed", PropertyValue::Float(0.0)),
+        ("octaves", PropertyValue::Float(5.0)),
+        ("lacunarity", PropertyValue::Float(2.0)),
+        ("gain", PropertyValue::Float(0.5)),
+    ],
+    generate: |ctx, inputs, properties| {
+        let noise = match properties.get("noise").and_then(|p| p.selected_option()) {
+            Some("Value") => [&VALUE_NOISE_2D, &VALUE_NOISE_3D],
+            Some("Simplex") => [&SIMPLEX_NOISE_2D, &SIMPLEX_NOISE_3D],
+            _ => [&GRADIENT_NOISE_2D, &GRADIENT_NOISE_3D],
+        };
+        layered_noise(ctx, inputs, properties, noise, 4.0, 5.0)
+    }
+);
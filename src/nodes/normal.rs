@@ -0,0 +1,137 @@
+//! Normal mapping nodes for shader graphs
+//!
+//! These nodes work with tangent-space normals, e.g. from normal map textures.
+
+use crate::common::PropertyValue;
+use crate::nodes::codegen::vector_type;
+
+// ============================================================================
+// Normal Map Node - Unpacks a tangent space sample, optionally in world space
+// ============================================================================
+
+define_node!(
+    NormalMapNode,
+    id: "umbra.normal.normal_map",
+    name: "Normal Map",
+    aliases: ["Normal Map"],
+    category: "Normal",
+    // Unconnected, a flat sample keeps the geometric normal
+    inputs: [("Sample", "Vec3", "vec3<f32>(0.5, 0.5, 1.0)")],
+    outputs: [("Normal", "Vec3"), ("World Normal", "Vec3")],
+    properties: [("strength", PropertyValue::Float(1.0))],
+    generate: |ctx, inputs, properties| {
+        let strength = properties
+            .get("strength")
+            .and_then(|p| p.as_float())
+            .unwrap_or(1.0);
+        // Unpack from [0, 1] texture encoding and scale the tangential part
+        // by strength. This is what the surface's Normal input takes.
+        let unpacked = format!(
+            "{}(({s}.xy * 2.0 - 1.0) * {k}, {s}.z * 2.0 - 1.0)",
+            vector_type(3, ctx.format),
+            s = inputs[0],
+            k = ctx.float(strength)
+        );
+        ctx.call("normalize", &[unpacked])
+    },
+    read_output: |ctx, output, result| {
+        // Lighting nodes work in world space: rotate by the tangent frame
+        (output == 1).then(|| {
+            let tangent = ctx.builtin("in.world_tangent");
+            let bitangent = ctx.builtin("in.world_bitangent");
+            let normal = ctx.builtin("in.world_normal");
+            let world = format!(
+                "{t} * {r}.x + {b} * {r}.y + {n} * {r}.z",
+                t = tangent,
+                b = bitangent,
+                n = normal,
+                r = result
+            );
+            ctx.call("normalize", &[world])
+        })
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::common::PropertyValue;
+    use crate::file::UmbraProject;
+    use crate::file::export::TargetFormat;
+    use crate::graph::eval::Evaluator;
+    use crate::graph::{UmbraNode, surface};
+    use crate::nodes::registry::NodeRole;
+    use egui_snarl::{InPinId, OutPinId};
+
+    /// Project with a Normal Map of strength 0.5 whose `output` feeds the
+    /// surface's `input`.
+    fn project(output: usize, input: usize) -> UmbraProject {
+        crate::nodes::register_builtins();
+        let mut project = UmbraProject::new();
+        let snarl = &mut project.graph.snarl;
+        let (surface_output, _) = snarl
+            .node_ids()
+            .find(|(_, node)| node.role() == NodeRole::SurfaceOutput)
+            .unwrap();
+        let mut node = UmbraNode::new("umbra.normal.normal_map");
+        node.properties
+            .insert("strength".to_string(), PropertyValue::Float(0.5));
+        let node = snarl.insert_node(egui::pos2(0.0, 0.0), node);
+        snarl.connect(
+            OutPinId { node, output },
+            InPinId {
+                node: surface_output,
+                input,
+            },
+        );
+        project
+    }
+
+    #[test]
+    fn surface_normal_gets_the_tangent_space_sample() {
+        let project = project(0, surface::NORMAL);
+        let cases = [
+            (
+                TargetFormat::Wgsl,
+                "normalize(vec3<f32>((vec3<f32>(0.500, 0.500, 1.000).xy * 2.0 - 1.0) * 0.500, vec3<f32>(0.500, 0.500, 1.000).z * 2.0 - 1.0))",
+            ),
+            (
+                TargetFormat::Godot,
+                "normalize(vec3((vec3(0.500, 0.500, 1.000).xy * 2.0 - 1.0) * 0.500, vec3(0.500, 0.500, 1.000).z * 2.0 - 1.0))",
+            ),
+            (
+                TargetFormat::Unity,
+                "normalize(float3((float3(0.500, 0.500, 1.000).xy * 2.0 - 1.0) * 0.500, float3(0.500, 0.500, 1.000).z * 2.0 - 1.0))",
+            ),
+        ];
+        for (format, expr) in cases {
+            let shader = Evaluator::evaluate(&project, format);
+            assert!(shader.contains(expr), "{:?}:\n{}", format, shader);
+            // The tangent frame is applied once, by the surface
+            assert!(
+                !shader.contains("world_tangent * "),
+                "{:?}:\n{}",
+                format,
+                shader
+            );
+        }
+    }
+
+    #[test]
+    fn world_normal_rotates_by_the_tangent_frame() {
+        let project = project(1, surface::EMISSION);
+        let godot = Evaluator::evaluate(&project, TargetFormat::Godot);
+        assert!(
+            godot.contains(
+                "normalize((INV_VIEW_MATRIX * vec4(TANGENT, 0.0)).xyz * node_2.x + (INV_VIEW_MATRIX * vec4(BINORMAL, 0.0)).xyz * node_2.y + (INV_VIEW_MATRIX * vec4(NORMAL, 0.0)).xyz * node_2.z)"
+            ),
+            "{}",
+            godot
+        );
+        let wgsl = Evaluator::evaluate(&project, TargetFormat::Wgsl);
+        assert!(
+            wgsl.contains("normalize(in.world_tangent * node_2.x + in.world_bitangent * node_2.y + in.world_normal * node_2.z)"),
+            "{}",
+            wgsl
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Output nodes for shader graphs
+//!
+//! Every graph has one of each. Their inputs are what the generated shader
+//! stages write: the vertex position offset and the surface (PBR) inputs.
+
+use super::codegen::CodegenContext;
+use super::registry::{InputDefinition, NodeImpl, NodeRole, OutputDefinition};
+use crate::common::{Property, PropertyValue};
+use crate::graph::surface::SURFACE_INPUTS;
+use std::collections::HashMap;
+
+// ============================================================================
+// Vertex Output Node - Offset added to the mesh position
+// ============================================================================
+
+define_node!(
+    VertexOutputNode,
+    id: "umbra.output.vertex_output",
+    name: "Vertex Output",
+    aliases: ["Vertex Output"],
+    category: "Output",
+    inputs: [("Position Offset", "Vec3")],
+    outputs: [],
+    properties: [],
+    generate: |_ctx, inputs, _properties| inputs[0].clone(),
+    role: NodeRole::VertexOutput
+);
+
+// ============================================================================
+// Surface Output Node - PBR inputs of the lighting model
+// ============================================================================
+
+/// Its pins follow [`SURFACE_INPUTS`], which the evaluator maps onto each
+/// target's lighting model.
+pub struct SurfaceOutputNode;
+
+impl NodeImpl for SurfaceOutputNode {
+    fn id(&self) -> &str {
+        "umbra.output.surface_output"
+    }
+
+    fn name(&self) -> &str {
+        "Surface Output"
+    }
+
+    fn aliases(&self) -> Vec<String> {
+        vec!["Surface Output".to_string()]
+    }
+
+    fn category(&self) -> Vec<String> {
+        vec!["Output".to_string()]
+    }
+
+    fn inputs(&self) -> Vec<InputDefinition> {
+        SURFACE_INPUTS
+            .iter()
+            .map(|input| InputDefinition {
+                value: Some(input.default_value()),
+                ..InputDefinition::new(input.name, input.type_name, None)
+            })
+            .collect()
+    }
+
+    fn outputs(&self) -> Vec<OutputDefinition> {
+        Vec::new()
+    }
+
+    fn generate(
+        &self,
+        _ctx: &mut CodegenContext,
+        inputs: &[String],
+        _properties: &HashMap<String, PropertyValue>,
+    ) -> String {
+        inputs[0].clone()
+    }
+
+    fn define_properties(&self) -> Vec<Property> {
+        Vec::new()
+    }
+
+    fn role(&self) -> NodeRole {
+        NodeRole::SurfaceOutput
+    }
+}
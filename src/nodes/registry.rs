@@ -1,4 +1,6 @@
+use super::codegen::CodegenContext;
 use super::{Property, PropertyValue};
+use crate::file::export::TargetFormat;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -6,49 +8,364 @@ use std::sync::Arc;
 pub struct InputDefinition {
     pub name: String,
     pub type_name: String, // "Float", "Vec3", etc.
+    /// WGSL expression used when the input is left unconnected, e.g. the
+    /// surface normal for lighting nodes.
+    pub default: Option<String>,
+    /// Value used when the input is left unconnected. The graph shows an
+    /// editor for it next to the pin, and each node saves its own edits,
+    /// which replace the `default` expression. `None` for inputs without an
+    /// editor, e.g. matrices, which default to identity.
+    pub value: Option<PropertyValue>,
+}
+
+impl InputDefinition {
+    /// Input of a pin type. A numeric `default`, or a vector of numbers
+    /// such as `vec3<f32>(0.5, 0.5, 1.0)`, becomes the input's value; any
+    /// other default is kept as an expression and the value starts at zero.
+    pub fn new(name: &str, type_name: &str, default: Option<&str>) -> Self {
+        let constant = default.and_then(|d| constant_value(type_name, d));
+        let expression = default.filter(|_| constant.is_none());
+        Self {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            default: expression.map(str::to_string),
+            value: constant.or_else(|| splat_value(type_name, 0.0)),
+        }
+    }
+
+    /// Value of a property promoted from this input while unconnected: the
+    /// input's value, otherwise zero, or identity for matrices. `None` for
+    /// inputs no property type matches.
+    pub fn property_value(&self) -> Option<PropertyValue> {
+        if let Some(value) = &self.value {
+            return Some(value.clone());
+        }
+        match self.type_name.as_str() {
+            "Mat3" => Some(PropertyValue::Mat3(glam::Mat3::IDENTITY.to_cols_array_2d())),
+            "Mat4" => Some(PropertyValue::Mat4(glam::Mat4::IDENTITY.to_cols_array_2d())),
+            type_name => splat_value(type_name, 0.0),
+        }
+    }
+}
+
+/// Value of a pin type with every component set to `scalar`. Dynamic pins
+/// take a scalar, which is splatted to the width they resolve to; alpha
+/// stays opaque.
+fn splat_value(type_name: &str, scalar: f32) -> Option<PropertyValue> {
+    match type_name {
+        "Float" | "Dynamic" | "Vector" => Some(PropertyValue::Float(scalar)),
+        "Vec2" => Some(PropertyValue::Vec2([scalar; 2])),
+        "Vec3" => Some(PropertyValue::Vec3([scalar; 3])),
+        "Vec4" => Some(PropertyValue::Vec4([scalar; 4])),
+        "Color" => Some(PropertyValue::Color([scalar, scalar, scalar, 1.0])),
+        _ => None,
+    }
+}
+
+/// Value of a constant `default`: a number, splatted to the pin type, or a
+/// WGSL or GLSL constructor of the pin's vector type taking one number or
+/// one per component, e.g. `vec3<f32>(0.5, 0.5, 1.0)`.
+fn constant_value(type_name: &str, default: &str) -> Option<PropertyValue> {
+    let default = default.trim();
+    if let Ok(scalar) = default.parse::<f32>() {
+        return splat_value(type_name, scalar);
+    }
+    let (constructor, args) = default.strip_suffix(')')?.split_once('(')?;
+    let constructor = constructor.trim();
+    let width = constructor
+        .strip_suffix("<f32>")
+        .unwrap_or(constructor)
+        .strip_prefix("vec")?
+        .parse::<usize>()
+        .ok()?;
+    let components = args
+        .split(',')
+        .map(|a| a.trim().parse::<f32>().ok())
+        .collect::<Option<Vec<f32>>>()?;
+    let value = match components[..] {
+        [scalar] => splat_value(type_name, scalar)?,
+        _ => match (type_name, &components[..]) {
+            ("Vec2", &[x, y]) => PropertyValue::Vec2([x, y]),
+            ("Vec3", &[x, y, z]) => PropertyValue::Vec3([x, y, z]),
+            ("Vec4", &[x, y, z, w]) => PropertyValue::Vec4([x, y, z, w]),
+            ("Color", &[r, g, b, a]) => PropertyValue::Color([r, g, b, a]),
+            _ => return None,
+        },
+    };
+    // A constructor of another width is an expression, e.g. a conversion
+    (value.components()?.len() == width).then_some(value)
 }
 
 #[derive(Debug, Clone)]
 pub struct OutputDefinition {
     pub name: String,
     pub type_name: String,
+    /// Components of the node's result this output reads, e.g. `y`, for
+    /// nodes with several outputs. `None` outputs the whole result.
+    pub swizzle: Option<String>,
+}
+
+/// A helper function emitted once at the top of a shader, before the stage
+/// entry points, when any node in the graph requires it.
+pub struct ShaderFunction {
+    /// Function name, also used to deduplicate helpers.
+    pub name: &'static str,
+    /// Helpers this one calls; they are emitted first.
+    pub deps: &'static [&'static ShaderFunction],
+    pub wgsl: &'static str,
+    pub glsl: &'static str,
+    pub hlsl: &'static str,
+    pub godot: &'static str,
+}
+
+impl ShaderFunction {
+    pub fn source(&self, format: TargetFormat) -> &'static str {
+        match format {
+            TargetFormat::Wgsl => self.wgsl,
+            TargetFormat::Godot => self.godot,
+            TargetFormat::Unity => self.hlsl,
+            TargetFormat::Generic => self.glsl,
+        }
+    }
+}
+
+/// Shader stage an expression is generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+}
+
+/// How the evaluator and the editor treat a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeRole {
+    /// Computes a value from its inputs and properties.
+    Expression,
+    /// Reads the project property named by its `property` setting, which
+    /// also gives the node its output type.
+    Property,
+    /// Receives the vertex position offset. Graphs have exactly one, so it
+    /// is not offered in the add-node menu.
+    VertexOutput,
+    /// Receives the surface inputs, like [`NodeRole::VertexOutput`].
+    SurfaceOutput,
+}
+
+impl NodeRole {
+    pub fn is_output(self) -> bool {
+        matches!(self, NodeRole::VertexOutput | NodeRole::SurfaceOutput)
+    }
 }
 
 pub trait NodeImpl: Send + Sync + 'static {
+    /// Stable namespaced ID the node is registered and saved under, e.g.
+    /// `umbra.math.add`. Unlike the display name it must never change.
+    fn id(&self) -> &str;
+    /// Display name in English.
     fn name(&self) -> &str;
     fn category(&self) -> Vec<String>;
     fn inputs(&self) -> Vec<InputDefinition>;
     fn outputs(&self) -> Vec<OutputDefinition>;
-    fn execute(&self, inputs: &[String], properties: &HashMap<String, PropertyValue>) -> String;
+    /// Generates the node's expression for the context's target and stage,
+    /// from the expressions of its inputs.
+    fn generate(
+        &self,
+        ctx: &mut CodegenContext,
+        inputs: &[String],
+        properties: &HashMap<String, PropertyValue>,
+    ) -> String;
+    /// Expression of an output that derives a value from the node's
+    /// `result`, where a swizzle is not enough. `None` reads the result,
+    /// through the output's swizzle if it has one.
+    fn read_output(
+        &self,
+        _ctx: &mut CodegenContext,
+        _output: usize,
+        _result: &str,
+    ) -> Option<String> {
+        None
+    }
     fn define_properties(&self) -> Vec<Property>;
+
+    fn role(&self) -> NodeRole {
+        NodeRole::Expression
+    }
+
+    /// Version of the node's pins and properties, saved with each node and
+    /// bumped when they change incompatibly.
+    fn version(&self) -> u32 {
+        1
+    }
+
+    /// IDs the node was saved under before, e.g. the display name that
+    /// projects written before node IDs existed refer to it by.
+    fn aliases(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Other words the node is found by in the add-node palette, e.g.
+    /// `plus` for Add.
+    fn keywords(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Display name in a language, given as an ISO 639-1 code such as `de`,
+    /// or `None` if the node is not translated to it.
+    fn localized_name(&self, _language: &str) -> Option<String> {
+        None
+    }
+
+    /// Draws the node's settings in its body in place of the default
+    /// editors, e.g. a single color button. Returns `false` to fall back to
+    /// the default editors.
+    fn show_ui(
+        &self,
+        _ui: &mut egui::Ui,
+        _properties: &mut HashMap<String, PropertyValue>,
+    ) -> bool {
+        false
+    }
+
+    /// Number of components of the result, for nodes whose width depends on
+    /// their properties. `dynamic_width` is the width the node's dynamic
+    /// pins resolved to. `None` uses the declared output type.
+    fn result_width(
+        &self,
+        _properties: &HashMap<String, PropertyValue>,
+        _dynamic_width: usize,
+    ) -> Option<usize> {
+        None
+    }
+
+    /// Checks the node's properties against the width its dynamic pins
+    /// resolved to. Invalid nodes output zero instead of their expression.
+    fn validate(
+        &self,
+        _properties: &HashMap<String, PropertyValue>,
+        _dynamic_width: usize,
+    ) -> Result<(), String> {
+        Ok(())
+    }
 }
 
 pub struct NodeRegistry {
     nodes: HashMap<String, Arc<dyn NodeImpl>>,
+    /// Old IDs and the ID they refer to now.
+    aliases: HashMap<String, String>,
+    /// Language display names are shown in, e.g. `en`.
+    language: String,
 }
 
 impl NodeRegistry {
     pub fn new() -> Self {
         Self {
             nodes: HashMap::new(),
+            aliases: HashMap::new(),
+            language: system_language(),
         }
     }
 
+    /// Registers a builtin node. Panics if its ID or an alias is already
+    /// taken, as builtin IDs must be unique.
     pub fn register(&mut self, node: impl NodeImpl) {
-        self.nodes.insert(node.name().to_string(), Arc::new(node));
+        if let Err(e) = self.try_register(node) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Registers a node unless its ID is malformed or its ID or one of its
+    /// aliases is already taken by another node.
+    pub fn try_register(&mut self, node: impl NodeImpl) -> Result<(), String> {
+        validate_id(node.id())?;
+        let id = node.id().to_string();
+        let aliases = node.aliases();
+        for key in std::iter::once(&id).chain(&aliases) {
+            if let Some(existing) = self.get(key) {
+                return Err(format!(
+                    "'{}' is already used by {} ({})",
+                    key,
+                    existing.name(),
+                    existing.id()
+                ));
+            }
+        }
+        for alias in aliases {
+            if alias != id {
+                self.aliases.insert(alias, id.clone());
+            }
+        }
+        self.nodes.insert(id, Arc::new(node));
+        Ok(())
+    }
+
+    /// Removes a node and its aliases.
+    pub fn unregister(&mut self, id: &str) {
+        self.nodes.remove(id);
+        self.aliases.retain(|_, target| target != id);
     }
 
-    pub fn get(&self, name: &str) -> Option<Arc<dyn NodeImpl>> {
-        self.nodes.get(name).cloned()
+    /// Node registered under an ID or one of its aliases.
+    pub fn get(&self, id: &str) -> Option<Arc<dyn NodeImpl>> {
+        let id = self.aliases.get(id).map_or(id, String::as_str);
+        self.nodes.get(id).cloned()
     }
 
+    /// Current ID of a node saved under `id`, which may be an alias.
+    pub fn resolve<'a>(&'a self, id: &'a str) -> &'a str {
+        self.aliases.get(id).map_or(id, String::as_str)
+    }
+
+    /// Name of a node in the display language, falling back to English.
+    pub fn display_name(&self, node: &dyn NodeImpl) -> String {
+        node.localized_name(&self.language)
+            .unwrap_or_else(|| node.name().to_string())
+    }
+
+    /// Registered nodes sorted by display name.
     pub fn list(&self) -> Vec<Arc<dyn NodeImpl>> {
         let mut list: Vec<_> = self.nodes.values().cloned().collect();
-        list.sort_by(|a, b| a.name().cmp(b.name()));
+        list.sort_by_cached_key(|n| self.display_name(n.as_ref()));
         list
     }
 }
 
+/// Checks that a node ID is namespaced: two or more dot-separated segments
+/// of lowercase letters, digits and underscores, e.g. `umbra.math.add`.
+pub fn validate_id(id: &str) -> Result<(), String> {
+    let segments: Vec<&str> = id.split('.').collect();
+    let valid = segments.len() >= 2
+        && segments.iter().all(|s| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid node ID '{}', expected a namespaced ID such as 'umbra.math.add'",
+            id
+        ))
+    }
+}
+
+/// Language of the user's locale, e.g. `de` for `de_DE.UTF-8`.
+fn system_language() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| {
+            let language: String = value
+                .chars()
+                .take_while(|c| c.is_ascii_alphabetic())
+                .collect();
+            (!language.is_empty() && language != "C" && language != "POSIX")
+                .then(|| language.to_lowercase())
+        })
+        .unwrap_or_else(|| "en".to_string())
+}
+
 // Global registry instance
 use std::sync::OnceLock;
 
@@ -56,3 +373,179 @@ pub fn global_registry() -> &'static std::sync::RwLock<NodeRegistry> {
     static REGISTRY: OnceLock<std::sync::RwLock<NodeRegistry>> = OnceLock::new();
     REGISTRY.get_or_init(|| std::sync::RwLock::new(NodeRegistry::new()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestNode {
+        id: &'static str,
+        aliases: &'static [&'static str],
+    }
+
+    impl NodeImpl for TestNode {
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn name(&self) -> &str {
+            "Test"
+        }
+
+        fn category(&self) -> Vec<String> {
+            vec!["Test".to_string()]
+        }
+
+        fn inputs(&self) -> Vec<InputDefinition> {
+            Vec::new()
+        }
+
+        fn outputs(&self) -> Vec<OutputDefinition> {
+            Vec::new()
+        }
+
+        fn generate(
+            &self,
+            _: &mut CodegenContext,
+            _: &[String],
+            _: &HashMap<String, PropertyValue>,
+        ) -> String {
+            String::new()
+        }
+
+        fn define_properties(&self) -> Vec<Property> {
+            Vec::new()
+        }
+
+        fn aliases(&self) -> Vec<String> {
+            self.aliases.iter().map(|a| a.to_string()).collect()
+        }
+    }
+
+    fn node(id: &'static str, aliases: &'static [&'static str]) -> TestNode {
+        TestNode { id, aliases }
+    }
+
+    #[test]
+    fn ids_must_be_namespaced() {
+        for id in ["umbra.math.add", "studio.noise_2d", "a.b1.c_d"] {
+            assert_eq!(validate_id(id), Ok(()), "{}", id);
+        }
+        for id in [
+            "add",
+            "",
+            "umbra..add",
+            "umbra.Math.add",
+            "umbra.math.",
+            "umbra.math-add",
+        ] {
+            assert!(validate_id(id).is_err(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn aliases_resolve_to_the_current_id() {
+        let mut registry = NodeRegistry::new();
+        registry
+            .try_register(node("test.math.add", &["test.add", "test.math.add"]))
+            .unwrap();
+        assert_eq!(registry.resolve("test.add"), "test.math.add");
+        assert_eq!(registry.resolve("test.math.add"), "test.math.add");
+        assert_eq!(registry.resolve("test.other"), "test.other");
+        assert_eq!(registry.get("test.add").unwrap().id(), "test.math.add");
+    }
+
+    #[test]
+    fn taken_ids_and_aliases_are_rejected() {
+        let mut registry = NodeRegistry::new();
+        registry
+            .try_register(node("test.math.add", &["test.add"]))
+            .unwrap();
+        // The same ID, an ID that is an alias, or an alias that is an ID
+        assert!(registry.try_register(node("test.math.add", &[])).is_err());
+        assert!(registry.try_register(node("test.add", &[])).is_err());
+        assert!(
+            registry
+                .try_register(node("test.math.sum", &["test.math.add"]))
+                .is_err()
+        );
+        assert!(
+            registry
+                .try_register(node("test.math.sum", &["test.add"]))
+                .is_err()
+        );
+        // A rejected node leaves no aliases behind
+        assert!(
+            registry
+                .try_register(node("test.math.sum", &["test.sum", "test.add"]))
+                .is_err()
+        );
+        assert!(registry.get("test.sum").is_none());
+        assert!(registry.try_register(node("Test.Sum", &[])).is_err());
+    }
+
+    #[test]
+    fn unregister_removes_aliases() {
+        let mut registry = NodeRegistry::new();
+        registry
+            .try_register(node("test.math.add", &["test.add"]))
+            .unwrap();
+        registry.unregister("test.math.add");
+        assert!(registry.get("test.add").is_none());
+        assert_eq!(registry.resolve("test.add"), "test.add");
+        registry.try_register(node("test.add", &[])).unwrap();
+    }
+
+    /// Type and components of an input's value.
+    fn value(input: &InputDefinition) -> Option<(&'static str, Vec<f32>)> {
+        let value = input.value.as_ref()?;
+        Some((value.type_name(), value.components()?))
+    }
+
+    #[test]
+    fn numeric_defaults_become_values() {
+        let input = InputDefinition::new("Scale", "Float", Some(" 8.0 "));
+        assert_eq!(input.default, None);
+        assert_eq!(value(&input), Some(("Float", vec![8.0])));
+        let input = InputDefinition::new("Tint", "Color", Some("0.5"));
+        assert_eq!(value(&input), Some(("Color", vec![0.5, 0.5, 0.5, 1.0])));
+        let input = InputDefinition::new("In", "Vec3", None);
+        assert_eq!(value(&input), Some(("Vec3", vec![0.0; 3])));
+    }
+
+    #[test]
+    fn vector_constants_become_values() {
+        let input = InputDefinition::new("Sample", "Vec3", Some("vec3<f32>(0.5, 0.5, 1.0)"));
+        assert_eq!(input.default, None);
+        assert_eq!(value(&input), Some(("Vec3", vec![0.5, 0.5, 1.0])));
+        let input = InputDefinition::new("Offset", "Vec2", Some("vec2(0.25)"));
+        assert_eq!(value(&input), Some(("Vec2", vec![0.25; 2])));
+    }
+
+    #[test]
+    fn expressions_stay_defaults_with_a_zero_value() {
+        for (type_name, width, default) in [
+            ("Vec3", 3, "normalize(in.world_normal)"),
+            ("Vec2", 2, "in.uv"),
+            // Vectors of another width are conversions
+            ("Vec3", 3, "vec2<f32>(1.0, 0.0)"),
+            ("Vec3", 3, "vec3<f32>(1.0, x, 0.0)"),
+        ] {
+            let input = InputDefinition::new("In", type_name, Some(default));
+            assert_eq!(input.default.as_deref(), Some(default));
+            assert_eq!(
+                value(&input),
+                Some((type_name, vec![0.0; width])),
+                "{}",
+                default
+            );
+        }
+        // Matrices have no editor
+        let input = InputDefinition::new("Matrix", "Mat3", None);
+        assert!(input.value.is_none());
+        assert!(matches!(
+            input.property_value(),
+            Some(PropertyValue::Mat3(_))
+        ));
+    }
+}
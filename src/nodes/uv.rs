@@ -0,0 +1,501 @@
+//! UV manipulation nodes for shader graphs
+//!
+//! These nodes remap texture coordinates: tiling, rotation, polar and
+//! distortion effects, triplanar projection and sprite sheet flipbooks.
+//! Unconnected UV inputs default to the mesh UVs.
+
+use crate::common::PropertyValue;
+use crate::nodes::codegen::CodegenContext;
+use crate::nodes::registry::ShaderFunction;
+use std::collections::HashMap;
+
+const DEFAULT_UV: &str = "in.uv";
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+pub static ROTATE_UV: ShaderFunction = ShaderFunction {
+    name: "umbra_rotate_uv",
+    deps: &[],
+    wgsl: "fn umbra_rotate_uv(uv: vec2<f32>, center: vec2<f32>, angle: f32) -> vec2<f32> {
+  let d = uv - center;
+  let s = sin(angle);
+  let c = cos(angle);
+  return vec2<f32>(d.x * c - d.y * s, d.x * s + d.y * c) + center;
+}
+",
+    glsl: ROTATE_UV_GLSL,
+    hlsl: "float2 umbra_rotate_uv(float2 uv, float2 center, float angle) {
+  float2 d = uv - center;
+  float s = sin(angle);
+  float c = cos(angle);
+  return float2(d.x * c - d.y * s, d.x * s + d.y * c) + center;
+}
+",
+    godot: ROTATE_UV_GLSL,
+};
+
+const ROTATE_UV_GLSL: &str = "vec2 umbra_rotate_uv(vec2 uv, vec2 center, float angle) {
+  vec2 d = uv - center;
+  float s = sin(angle);
+  float c = cos(angle);
+  return vec2(d.x * c - d.y * s, d.x * s + d.y * c) + center;
+}
+";
+
+/// Radius and angle about `center`; the angle is in turns, in [0, 1).
+pub static POLAR_UV: ShaderFunction = ShaderFunction {
+    name: "umbra_polar_uv",
+    deps: &[],
+    wgsl: "fn umbra_polar_uv(uv: vec2<f32>, center: vec2<f32>, radial_scale: f32, length_scale: f32) -> vec2<f32> {
+  let d = uv - center;
+  let radius = length(d) * 2.0 * radial_scale;
+  let angle = (atan2(d.y, d.x) / 6.2831853 + 0.5) * length_scale;
+  return vec2<f32>(radius, angle);
+}
+",
+    glsl: POLAR_UV_GLSL,
+    hlsl: "float2 umbra_polar_uv(float2 uv, float2 center, float radial_scale, float length_scale) {
+  float2 d = uv - center;
+  float radius = length(d) * 2.0 * radial_scale;
+  float angle = (atan2(d.y, d.x) / 6.2831853 + 0.5) * length_scale;
+  return float2(radius, angle);
+}
+",
+    godot: POLAR_UV_GLSL,
+};
+
+const POLAR_UV_GLSL: &str =
+    "vec2 umbra_polar_uv(vec2 uv, vec2 center, float radial_scale, float length_scale) {
+  vec2 d = uv - center;
+  float radius = length(d) * 2.0 * radial_scale;
+  float angle = (atan(d.y, d.x) / 6.2831853 + 0.5) * length_scale;
+  return vec2(radius, angle);
+}
+";
+
+/// Rotates UVs by an angle that grows with the distance from `center`.
+pub static TWIRL_UV: ShaderFunction = ShaderFunction {
+    name: "umbra_twirl_uv",
+    deps: &[],
+    wgsl: "fn umbra_twirl_uv(uv: vec2<f32>, center: vec2<f32>, strength: f32) -> vec2<f32> {
+  let d = uv - center;
+  let angle = strength * length(d);
+  let s = sin(angle);
+  let c = cos(angle);
+  return vec2<f32>(d.x * c - d.y * s, d.x * s + d.y * c) + center;
+}
+",
+    glsl: TWIRL_UV_GLSL,
+    hlsl: "float2 umbra_twirl_uv(float2 uv, float2 center, float strength) {
+  float2 d = uv - center;
+  float angle = strength * length(d);
+  float s = sin(angle);
+  float c = cos(angle);
+  return float2(d.x * c - d.y * s, d.x * s + d.y * c) + center;
+}
+",
+    godot: TWIRL_UV_GLSL,
+};
+
+const TWIRL_UV_GLSL: &str = "vec2 umbra_twirl_uv(vec2 uv, vec2 center, float strength) {
+  vec2 d = uv - center;
+  float angle = strength * length(d);
+  float s = sin(angle);
+  float c = cos(angle);
+  return vec2(d.x * c - d.y * s, d.x * s + d.y * c) + center;
+}
+";
+
+/// Fisheye distortion pushing UVs outwards with the fourth power of the
+/// distance from `center`.
+pub static SPHERIZE_UV: ShaderFunction = ShaderFunction {
+    name: "umbra_spherize_uv",
+    deps: &[],
+    wgsl: "fn umbra_spherize_uv(uv: vec2<f32>, center: vec2<f32>, strength: f32) -> vec2<f32> {
+  let d = uv - center;
+  let d2 = dot(d, d);
+  return uv + d * d2 * d2 * strength;
+}
+",
+    glsl: SPHERIZE_UV_GLSL,
+    hlsl: "float2 umbra_spherize_uv(float2 uv, float2 center, float strength) {
+  float2 d = uv - center;
+  float d2 = dot(d, d);
+  return uv + d * d2 * d2 * strength;
+}
+",
+    godot: SPHERIZE_UV_GLSL,
+};
+
+const SPHERIZE_UV_GLSL: &str = "vec2 umbra_spherize_uv(vec2 uv, vec2 center, float strength) {
+  vec2 d = uv - center;
+  float d2 = dot(d, d);
+  return uv + d * d2 * d2 * strength;
+}
+";
+
+/// Blend weights of the three projection planes, summing to one.
+pub static TRIPLANAR_WEIGHTS: ShaderFunction = ShaderFunction {
+    name: "umbra_triplanar_weights",
+    deps: &[],
+    wgsl: "fn umbra_triplanar_weights(normal: vec3<f32>, sharpness: f32) -> vec3<f32> {
+  let w = pow(abs(normal), vec3<f32>(sharpness));
+  return w / max(w.x + w.y + w.z, 1.0e-5);
+}
+",
+    glsl: TRIPLANAR_WEIGHTS_GLSL,
+    hlsl: "float3 umbra_triplanar_weights(float3 normal, float sharpness) {
+  float3 w = pow(abs(normal), float3(sharpness, sharpness, sharpness));
+  return w / max(w.x + w.y + w.z, 1.0e-5);
+}
+",
+    godot: TRIPLANAR_WEIGHTS_GLSL,
+};
+
+const TRIPLANAR_WEIGHTS_GLSL: &str = "vec3 umbra_triplanar_weights(vec3 normal, float sharpness) {
+  vec3 w = pow(abs(normal), vec3(sharpness));
+  return w / max(w.x + w.y + w.z, 1.0e-5);
+}
+";
+
+/// Sub-rect of a sprite sheet cell. Frames run left to right, then top to
+/// bottom, and wrap after the last cell.
+pub static FLIPBOOK_UV: ShaderFunction = ShaderFunction {
+    name: "umbra_flipbook_uv",
+    deps: &[],
+    wgsl: "fn umbra_flipbook_uv(uv: vec2<f32>, columns: f32, rows: f32, frame: f32) -> vec2<f32> {
+  let count = columns * rows;
+  let index = floor(frame - count * floor(frame / count));
+  let row = floor(index / columns);
+  let column = index - row * columns;
+  return (fract(uv) + vec2<f32>(column, row)) / vec2<f32>(columns, rows);
+}
+",
+    glsl: FLIPBOOK_UV_GLSL,
+    hlsl: "float2 umbra_flipbook_uv(float2 uv, float columns, float rows, float frame) {
+  float count = columns * rows;
+  float index = floor(frame - count * floor(frame / count));
+  float row = floor(index / columns);
+  float column = index - row * columns;
+  return (frac(uv) + float2(column, row)) / float2(columns, rows);
+}
+",
+    godot: FLIPBOOK_UV_GLSL,
+};
+
+const FLIPBOOK_UV_GLSL: &str =
+    "vec2 umbra_flipbook_uv(vec2 uv, float columns, float rows, float frame) {
+  float count = columns * rows;
+  float index = floor(frame - count * floor(frame / count));
+  float row = floor(index / columns);
+  float column = index - row * columns;
+  return (fract(uv) + vec2(column, row)) / vec2(columns, rows);
+}
+";
+
+fn float(properties: &HashMap<String, PropertyValue>, name: &str, default: f32) -> f32 {
+    properties
+        .get(name)
+        .and_then(|p| p.as_float())
+        .unwrap_or(default)
+}
+
+/// Formats a `Vec2` property as a constant of the target.
+fn vec2(
+    ctx: &CodegenContext,
+    properties: &HashMap<String, PropertyValue>,
+    name: &str,
+    default: [f32; 2],
+) -> String {
+    match properties.get(name) {
+        Some(PropertyValue::Vec2(v)) => ctx.literal(v),
+        _ => ctx.literal(&default),
+    }
+}
+
+// ============================================================================
+// Tiling And Offset Node - Scales and shifts UVs
+// ============================================================================
+
+define_node!(
+    TilingAndOffsetNode,
+    id: "umbra.uv.tiling_and_offset",
+    name: "Tiling And Offset",
+    aliases: ["Tiling And Offset"],
+    keywords: ["scale", "repeat", "tile"],
+    category: "UV",
+    inputs: [("UV", "Vec2", DEFAULT_UV)],
+    outputs: [("Out", "Vec2")],
+    properties: [
+        ("tiling", PropertyValue::Vec2([1.0, 1.0])),
+        ("offset", PropertyValue::Vec2([0.0, 0.0])),
+    ],
+    generate: |ctx, inputs, properties| {
+        format!(
+            "({} * {} + {})",
+            inputs[0],
+            vec2(ctx, properties, "tiling", [1.0, 1.0]),
+            vec2(ctx, properties, "offset", [0.0, 0.0])
+        )
+    }
+);
+
+// ============================================================================
+// Rotate Node - Rotates UVs around a center, in radians
+// ============================================================================
+
+define_node!(
+    RotateUVNode,
+    id: "umbra.uv.rotate",
+    name: "Rotate",
+    aliases: ["Rotate"],
+    category: "UV",
+    inputs: [("UV", "Vec2", DEFAULT_UV), ("Rotation", "Float")],
+    outputs: [("Out", "Vec2")],
+    properties: [("center", PropertyValue::Vec2([0.5, 0.5]))],
+    generate: |ctx, inputs, properties| {
+        format!(
+            "{}({}, {}, {})",
+            ctx.helper(&ROTATE_UV),
+            inputs[0],
+            vec2(ctx, properties, "center", [0.5, 0.5]),
+            inputs[1]
+        )
+    }
+);
+
+// ============================================================================
+// Polar Coordinates Node - Radius and angle around a center
+// ============================================================================
+
+define_node!(
+    PolarCoordinatesNode,
+    id: "umbra.uv.polar_coordinates",
+    name: "Polar Coordinates",
+    aliases: ["Polar Coordinates"],
+    category: "UV",
+    inputs: [("UV", "Vec2", DEFAULT_UV)],
+    outputs: [("Out", "Vec2")],
+    properties: [
+        ("center", PropertyValue::Vec2([0.5, 0.5])),
+        ("radial_scale", PropertyValue::Float(1.0)),
+        ("length_scale", PropertyValue::Float(1.0)),
+    ],
+    generate: |ctx, inputs, properties| {
+        format!(
+            "{}({}, {}, {:.3}, {:.3})",
+            ctx.helper(&POLAR_UV),
+            inputs[0],
+            vec2(ctx, properties, "center", [0.5, 0.5]),
+            float(properties, "radial_scale", 1.0),
+            float(properties, "length_scale", 1.0)
+        )
+    }
+);
+
+// ============================================================================
+// Twirl Node - Swirls UVs around a center
+// ============================================================================
+
+define_node!(
+    TwirlNode,
+    id: "umbra.uv.twirl",
+    name: "Twirl",
+    aliases: ["Twirl"],
+    category: "UV",
+    inputs: [("UV", "Vec2", DEFAULT_UV)],
+    outputs: [("Out", "Vec2")],
+    properties: [
+        ("center", PropertyValue::Vec2([0.5, 0.5])),
+        ("strength", PropertyValue::Float(10.0)),
+    ],
+    generate: |ctx, inputs, properties| {
+        format!(
+            "{}({}, {}, {:.3})",
+            ctx.helper(&TWIRL_UV),
+            inputs[0],
+            vec2(ctx, properties, "center", [0.5, 0.5]),
+            float(properties, "strength", 10.0)
+        )
+    }
+);
+
+// ============================================================================
+// Spherize Node - Fisheye distortion around a center
+// ============================================================================
+
+define_node!(
+    SpherizeNode,
+    id: "umbra.uv.spherize",
+    name: "Spherize",
+    aliases: ["Spherize"],
+    category: "UV",
+    inputs: [("UV", "Vec2", DEFAULT_UV)],
+    outputs: [("Out", "Vec2")],
+    properties: [
+        ("center", PropertyValue::Vec2([0.5, 0.5])),
+        ("strength", PropertyValue::Float(10.0)),
+    ],
+    generate: |ctx, inputs, properties| {
+        format!(
+            "{}({}, {}, {:.3})",
+            ctx.helper(&SPHERIZE_UV),
+            inputs[0],
+            vec2(ctx, properties, "center", [0.5, 0.5]),
+            float(properties, "strength", 10.0)
+        )
+    }
+);
+
+// ============================================================================
+// Triplanar Node - Planar projections of a position along each axis
+// ============================================================================
+
+define_node!(
+    TriplanarNode,
+    id: "umbra.uv.triplanar",
+    name: "Triplanar",
+    aliases: ["Triplanar"],
+    keywords: ["projection", "box"],
+    category: "UV",
+    inputs: [("Position", "Vec3", "in.world_position")],
+    outputs: [
+        ("UV X", "Vec2", "zy"),
+        ("UV Y", "Vec2", "xz"),
+        ("UV Z", "Vec2", "xy"),
+    ],
+    properties: [("tiling", PropertyValue::Float(1.0))],
+    generate: |_ctx, inputs, properties| {
+        format!("({} * {:.3})", inputs[0], float(properties, "tiling", 1.0))
+    }
+);
+
+// ============================================================================
+// Triplanar Weights Node - How much each projection contributes
+// ============================================================================
+
+define_node!(
+    TriplanarWeightsNode,
+    id: "umbra.uv.triplanar_weights",
+    name: "Triplanar Weights",
+    aliases: ["Triplanar Weights"],
+    category: "UV",
+    inputs: [("Normal", "Vec3", "normalize(in.world_normal)")],
+    outputs: [("Weights", "Vec3")],
+    properties: [("sharpness", PropertyValue::Float(4.0))],
+    generate: |ctx, inputs, properties| {
+        format!(
+            "{}({}, {:.3})",
+            ctx.helper(&TRIPLANAR_WEIGHTS),
+            inputs[0],
+            float(properties, "sharpness", 4.0)
+        )
+    }
+);
+
+// ============================================================================
+// Flipbook Node - Sprite sheet cell for a frame index; connect Time to animate
+// ============================================================================
+
+define_node!(
+    FlipbookNode,
+    id: "umbra.uv.flipbook",
+    name: "Flipbook",
+    aliases: ["Flipbook"],
+    category: "UV",
+    inputs: [("UV", "Vec2", DEFAULT_UV), ("Frame", "Float")],
+    outputs: [("Out", "Vec2")],
+    properties: [
+        ("columns", PropertyValue::Float(4.0)),
+        ("rows", PropertyValue::Float(4.0)),
+    ],
+    generate: |ctx, inputs, properties| {
+        format!(
+            "{}({}, {:.1}, {:.1}, {})",
+            ctx.helper(&FLIPBOOK_UV),
+            inputs[0],
+            float(properties, "columns", 4.0).round().max(1.0),
+            float(properties, "rows", 4.0).round().max(1.0),
+            inputs[1]
+        )
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::UmbraProject;
+    use crate::file::export::TargetFormat;
+    use crate::graph::eval::Evaluator;
+    use crate::graph::{UmbraNode, surface};
+    use crate::nodes::registry::{NodeImpl, NodeRole, ShaderStage};
+    use egui_snarl::{InPinId, OutPinId};
+
+    #[test]
+    fn flipbook_rounds_the_grid_size() {
+        let properties = HashMap::from([
+            ("columns".to_string(), PropertyValue::Float(3.4)),
+            ("rows".to_string(), PropertyValue::Float(0.0)),
+        ]);
+        let inputs = ["uv".to_string(), "frame".to_string()];
+        let mut ctx = CodegenContext::new(TargetFormat::Unity, ShaderStage::Fragment, &[]);
+        assert_eq!(
+            FlipbookNode.generate(&mut ctx, &inputs, &properties),
+            "umbra_flipbook_uv(uv, 3.0, 1.0, frame)"
+        );
+        let (helpers, _) = ctx.into_parts();
+        assert_eq!(helpers.len(), 1);
+        assert!(helpers[0].hlsl.starts_with("float2 umbra_flipbook_uv("));
+    }
+
+    /// Evaluates Time -> Flipbook and Triplanar -> surface for a target.
+    fn evaluate(format: TargetFormat) -> String {
+        crate::nodes::register_builtins();
+        let mut project = UmbraProject::new();
+        let snarl = &mut project.graph.snarl;
+        let (output, _) = snarl
+            .node_ids()
+            .find(|(_, node)| node.role() == NodeRole::SurfaceOutput)
+            .unwrap();
+        let time = snarl.insert_node(egui::pos2(0.0, 0.0), UmbraNode::new("umbra.input.time"));
+        let flipbook = snarl.insert_node(egui::pos2(0.0, 0.0), UmbraNode::new("umbra.uv.flipbook"));
+        let triplanar =
+            snarl.insert_node(egui::pos2(0.0, 0.0), UmbraNode::new("umbra.uv.triplanar"));
+        let wires = [
+            ((time, 0), (flipbook, 1)),
+            ((flipbook, 0), (output, surface::EMISSION)),
+            ((triplanar, 1), (output, surface::ALBEDO)),
+        ];
+        for ((from, output), (to, input)) in wires {
+            snarl.connect(OutPinId { node: from, output }, InPinId { node: to, input });
+        }
+        Evaluator::evaluate(&project, format)
+    }
+
+    #[test]
+    fn uv_nodes_read_the_target_uv() {
+        let godot = evaluate(TargetFormat::Godot);
+        assert!(
+            godot.contains("vec2 node_3 = umbra_flipbook_uv(UV, 4.0, 4.0, node_2);"),
+            "{}",
+            godot
+        );
+        assert!(godot.contains("float node_2 = TIME;"), "{}", godot);
+        // Each triplanar output reads its projection plane
+        assert!(godot.contains("node_4.xz"), "{}", godot);
+
+        let unity = evaluate(TargetFormat::Unity);
+        assert!(
+            unity.contains("umbra_flipbook_uv(IN.texcoord0, 4.0, 4.0, node_2)"),
+            "{}",
+            unity
+        );
+        assert!(
+            unity.contains("float3 node_4 = (IN.worldPos * 1.000);"),
+            "{}",
+            unity
+        );
+    }
+}
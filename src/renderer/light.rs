@@ -0,0 +1,23 @@
+/// Directional light used to shade the preview mesh.
+///
+/// Only affects the in-editor preview; exported shaders use the target
+/// engine's own lights.
+#[derive(Debug, Clone, Copy)]
+pub struct PreviewLight {
+    /// Direction the light travels in, in world space.
+    pub direction: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub ambient: f32,
+}
+
+impl Default for PreviewLight {
+    fn default() -> Self {
+        Self {
+            direction: [-0.4, -0.8, -0.45],
+            color: [1.0, 1.0, 1.0],
+            intensity: 3.0,
+            ambient: 0.03,
+        }
+    }
+}
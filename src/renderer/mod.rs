@@ -1,17 +1,21 @@
-use crate::common::PropertyValue;
-use crate::file::UmbraProject;
+use crate::common::UniformLayout;
+use crate::file::history::{Edit, REDO_SHORTCUT, UNDO_SHORTCUT};
+use crate::file::{History, UmbraProject};
+use crate::nodes::library::NodeLibrary;
 use std::sync::Arc;
 use wgpu::{Device, Queue, Surface, SurfaceConfiguration, TextureView};
 use winit::window::Window;
 
 mod camera;
 mod gui;
+mod light;
 mod pipeline;
 mod primitives;
 mod uniforms;
 
 use camera::{Camera, CameraController};
 use gui::Gui;
+pub use light::PreviewLight;
 use pipeline::Pipeline;
 use primitives::create_uv_sphere;
 use uniforms::Uniforms;
@@ -26,10 +30,13 @@ pub struct Renderer {
     pub gui: Gui,
     pub ui_manager: crate::ui::UiManager,
     pub project: UmbraProject,
+    pub history: History,
+    pub node_library: NodeLibrary,
     pub generated_shader: String,
     pub camera: Camera,
     pub camera_controller: CameraController,
     pub uniforms: Uniforms,
+    pub preview_light: PreviewLight,
     pub depth_texture_view: TextureView,
 
     // Texture-based preview
@@ -145,7 +152,7 @@ impl Renderer {
         let camera = Camera::new(Self::PREVIEW_SIZE.0, Self::PREVIEW_SIZE.1);
         let camera_controller = CameraController::new(4.0, 0.005);
         let mut uniforms = Uniforms::new();
-        uniforms.update_view_proj(&camera);
+        uniforms.update_camera(&camera);
         uniforms.resolution = [Self::PREVIEW_SIZE.0 as f32, Self::PREVIEW_SIZE.1 as f32];
 
         let default_shader = "
@@ -211,6 +218,11 @@ impl Renderer {
             Box::new(crate::ui::InfoPanel),
             false, // Default closed
         );
+        ui_manager.register_view(
+            "node_library",
+            Box::new(crate::ui::NodeLibraryPanel),
+            false, // Default closed
+        );
 
         Self {
             surface,
@@ -222,10 +234,13 @@ impl Renderer {
             gui,
             ui_manager,
             project,
+            history: History::new(),
+            node_library: NodeLibrary::new(),
             generated_shader,
             camera,
             camera_controller,
             uniforms,
+            preview_light: PreviewLight::default(),
             depth_texture_view,
             preview_view,
             preview_id,
@@ -247,21 +262,10 @@ impl Renderer {
             return;
         }
 
-        // Calculate uniform size: 16-byte aligned base + properties
-        // Base Uniforms size is already 96 bytes (multiple of 16)
-        let base_size = 96;
-        let mut total_size = base_size;
-        for prop in &self.project.properties {
-            match prop.value {
-                PropertyValue::Float(_) => total_size += 16, // Align each to 16 for safety
-                PropertyValue::Vec2(_) => total_size += 16,
-                PropertyValue::Vec3(_) => total_size += 16,
-                PropertyValue::Vec4(_) => total_size += 16,
-                PropertyValue::Color(_) => total_size += 16,
-                PropertyValue::Int(_) => total_size += 16,
-                PropertyValue::Bool(_) => total_size += 16,
-            }
-        }
+        // Base Uniforms size is already a multiple of 16, so the property
+        // block starts aligned
+        let base_size = std::mem::size_of::<Uniforms>() as u64;
+        let total_size = base_size + UniformLayout::new(&self.project.properties).size as u64;
 
         let pipeline_config = wgpu::SurfaceConfiguration {
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
@@ -287,33 +291,19 @@ impl Renderer {
 
     pub fn update(&mut self, dt: std::time::Duration) {
         self.uniforms.time += dt.as_secs_f32();
+        self.uniforms.delta_time = dt.as_secs_f32();
+        self.uniforms.frame_count = self.uniforms.frame_count.wrapping_add(1);
         self.camera_controller.update_camera(&mut self.camera);
-        self.uniforms.update_view_proj(&self.camera);
+        self.uniforms.update_camera(&self.camera);
+        self.uniforms.update_light(&self.preview_light);
+        self.node_library.poll(self.project.path.as_deref());
 
         // Build dynamic uniform buffer
         let mut data = Vec::new();
         data.extend_from_slice(bytemuck::cast_slice(&[self.uniforms]));
 
-        // Pad properties to 16-byte alignment
-        for prop in &self.project.properties {
-            match prop.value {
-                PropertyValue::Float(v) => {
-                    data.extend_from_slice(bytemuck::bytes_of(&v));
-                    data.extend_from_slice(&[0u8; 12]); // Pad to 16
-                }
-                PropertyValue::Vec2(v) => {
-                    data.extend_from_slice(bytemuck::bytes_of(&v));
-                    data.extend_from_slice(&[0u8; 8]); // Pad to 16
-                }
-                PropertyValue::Color(v) => {
-                    data.extend_from_slice(bytemuck::bytes_of(&v));
-                }
-                PropertyValue::Vec4(v) => {
-                    data.extend_from_slice(bytemuck::bytes_of(&v));
-                }
-                _ => {} // Other types not handled
-            }
-        }
+        let properties = &self.project.properties;
+        data.extend_from_slice(&UniformLayout::new(properties).pack(properties));
 
         let buffer_size = self.pipeline.uniform_buffer.size() as usize;
         let write_len = data.len().min(buffer_size);
@@ -392,9 +382,12 @@ impl Renderer {
         }
         // 2. Render GUI
         let project = &mut self.project;
+        let history = &mut self.history;
         let generated_shader = &mut self.generated_shader;
         let preview_id = self.preview_id;
+        let preview_light = &mut self.preview_light;
         let ui_manager = &mut self.ui_manager;
+        let node_library = &mut self.node_library;
 
         let mut apply_shader = false;
 
@@ -409,23 +402,48 @@ impl Renderer {
                     crate::ui::theme::apply_theme(ctx);
                 }
 
+                // Text fields undo their own edits while focused
+                if ctx.memory(|m| m.focused().is_none()) {
+                    // Redo first, as the undo shortcut also matches with Shift held
+                    let (redo, undo) = ctx.input_mut(|i| {
+                        (
+                            i.consume_shortcut(&REDO_SHORTCUT),
+                            i.consume_shortcut(&UNDO_SHORTCUT),
+                        )
+                    });
+                    if redo {
+                        history.redo(project);
+                    } else if undo {
+                        history.undo(project);
+                    }
+                }
+
                 egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-                    egui::menu::bar(ui, |ui| {
+                    egui::MenuBar::new().ui(ui, |ui| {
                         ui.menu_button("File", |ui| {
                             if ui.button("New").clicked() {
-                                *project = crate::file::UmbraProject::new();
+                                let before =
+                                    std::mem::replace(project, crate::file::UmbraProject::new());
+                                history.push(
+                                    "New Project",
+                                    vec![Edit::ReplaceProject(Box::new(before))],
+                                );
                                 ui.close();
                             }
                             if ui.button("Save").clicked() {
-                                if let Some(_path) = project.save_as_dialog() {
-                                    // Project name logic could be updated here
+                                if let Some(path) = project.save_as_dialog() {
+                                    project.path = Some(path);
                                 }
                                 ui.close();
                             }
                             if ui.button("Load").clicked() {
                                 if let Some(new_project) = crate::file::UmbraProject::load_dialog()
                                 {
-                                    *project = new_project;
+                                    let before = std::mem::replace(project, new_project);
+                                    history.push(
+                                        "Load Project",
+                                        vec![Edit::ReplaceProject(Box::new(before))],
+                                    );
                                 }
                                 ui.close();
                             }
@@ -443,6 +461,18 @@ impl Renderer {
                                     );
                                     ui.close();
                                 }
+                                if ui.button("Unity (.shader)").clicked() {
+                                    let code = crate::graph::eval::Evaluator::evaluate(
+                                        project,
+                                        crate::file::export::TargetFormat::Unity,
+                                    );
+                                    crate::file::export::export_shader_dialog(
+                                        &code,
+                                        &project.name,
+                                        crate::file::export::TargetFormat::Unity,
+                                    );
+                                    ui.close();
+                                }
                                 if ui.button("Generic (.shader)").clicked() {
                                     let code = crate::graph::eval::Evaluator::evaluate(
                                         project,
@@ -470,9 +500,36 @@ impl Renderer {
                             });
                         });
 
+                        ui.menu_button("Edit", |ui| {
+                            let undo = history.undo_name();
+                            let button = egui::Button::new(undo.map_or_else(
+                                || "Undo".to_string(),
+                                |name| format!("Undo {}", name),
+                            ))
+                            .shortcut_text(ctx.format_shortcut(&UNDO_SHORTCUT));
+                            if ui.add_enabled(undo.is_some(), button).clicked() {
+                                history.undo(project);
+                                ui.close();
+                            }
+                            let redo = history.redo_name();
+                            let button = egui::Button::new(redo.map_or_else(
+                                || "Redo".to_string(),
+                                |name| format!("Redo {}", name),
+                            ))
+                            .shortcut_text(ctx.format_shortcut(&REDO_SHORTCUT));
+                            if ui.add_enabled(redo.is_some(), button).clicked() {
+                                history.redo(project);
+                                ui.close();
+                            }
+                        });
+
                         ui.menu_button("Node", |ui| {
                             let pos = ui.cursor().min + egui::vec2(0.0, 20.0);
-                            crate::graph::show_add_node_menu(ui, pos, &mut project.graph.snarl);
+                            if let Some(node) =
+                                crate::graph::show_add_node_menu(ui, pos, &mut project.graph.snarl)
+                            {
+                                history.push("Add Node", vec![Edit::AddNode(node)]);
+                            }
                         });
 
                         ui.menu_button("Window", |ui| {
@@ -480,6 +537,10 @@ impl Renderer {
                             if ui.checkbox(&mut properties_open, "Properties").clicked() {
                                 ui_manager.toggle("properties");
                             }
+                            let mut library_open = ui_manager.is_open("node_library");
+                            if ui.checkbox(&mut library_open, "Node Library").clicked() {
+                                ui_manager.toggle("node_library");
+                            }
                         });
 
                         ui.menu_button("Help", |ui| {
@@ -488,6 +549,17 @@ impl Renderer {
                                 ui.close();
                             }
                         });
+
+                        if !node_library.errors.is_empty() {
+                            let warning = egui::RichText::new(format!(
+                                "⚠ {} node definition error(s)",
+                                node_library.errors.len()
+                            ))
+                            .color(ui.visuals().warn_fg_color);
+                            if ui.button(warning).clicked() && !ui_manager.is_open("node_library") {
+                                ui_manager.toggle("node_library");
+                            }
+                        }
                     });
                 });
 
@@ -498,6 +570,9 @@ impl Renderer {
                     generated_shader,
                     apply_shader: &mut apply_shader,
                     preview_texture_id: preview_id,
+                    preview_light,
+                    node_library,
+                    history: &mut *history,
                     time: self.uniforms.time,
                     close_requested: &mut close_requested,
                 };
@@ -506,7 +581,18 @@ impl Renderer {
                 ui_manager.show(ctx, &mut app_context);
 
                 egui::CentralPanel::default().show(ctx, |ui| {
-                    project.graph.draw(ui, "umbra_node_graph");
+                    let mut edits = Vec::new();
+                    let action =
+                        project
+                            .graph
+                            .draw(ui, "umbra_node_graph", &project.properties, &mut edits);
+                    if let Some(edit) = edits.first() {
+                        history.push(edit.name(), edits);
+                    }
+                    if let Some(action) = action {
+                        let name = action.name();
+                        history.push(name, project.apply_graph_action(action));
+                    }
                 });
             },
         );
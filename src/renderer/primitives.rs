@@ -6,6 +6,8 @@ pub struct Vertex {
     pub position: [f32; 3],
     pub normal: [f32; 3],
     pub uv: [f32; 2],
+    /// Tangent in `xyz`, bitangent handedness (+1/-1) in `w`.
+    pub tangent: [f32; 4],
 }
 
 impl Vertex {
@@ -29,6 +31,11 @@ impl Vertex {
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x2,
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ],
         }
     }
@@ -39,6 +46,56 @@ pub struct Mesh {
     pub indices: Vec<u32>,
 }
 
+impl Mesh {
+    /// Generates per-vertex tangents from positions and UVs.
+    ///
+    /// Triangle tangents are accumulated per vertex, then orthogonalized
+    /// against the normal (Gram-Schmidt). Any mesh loader should call this
+    /// after filling in normals and UVs so normal mapping works.
+    pub fn compute_tangents(&mut self) {
+        use glam::{Vec2, Vec3};
+
+        let mut tangents = vec![Vec3::ZERO; self.vertices.len()];
+        let mut bitangents = vec![Vec3::ZERO; self.vertices.len()];
+
+        for tri in self.indices.chunks_exact(3) {
+            let [i0, i1, i2] = [tri[0] as usize, tri[1] as usize, tri[2] as usize];
+            let (v0, v1, v2) = (&self.vertices[i0], &self.vertices[i1], &self.vertices[i2]);
+
+            let e1 = Vec3::from(v1.position) - Vec3::from(v0.position);
+            let e2 = Vec3::from(v2.position) - Vec3::from(v0.position);
+            let d1 = Vec2::from(v1.uv) - Vec2::from(v0.uv);
+            let d2 = Vec2::from(v2.uv) - Vec2::from(v0.uv);
+
+            let det = d1.x * d2.y - d2.x * d1.y;
+            if det.abs() < f32::EPSILON {
+                continue; // Degenerate UVs, e.g. at the sphere poles
+            }
+            let r = 1.0 / det;
+            let tangent = (e1 * d2.y - e2 * d1.y) * r;
+            let bitangent = (e2 * d1.x - e1 * d2.x) * r;
+
+            for i in [i0, i1, i2] {
+                tangents[i] += tangent;
+                bitangents[i] += bitangent;
+            }
+        }
+
+        for (i, vertex) in self.vertices.iter_mut().enumerate() {
+            let n = Vec3::from(vertex.normal);
+            let t = (tangents[i] - n * n.dot(tangents[i]))
+                .try_normalize()
+                .unwrap_or_else(|| n.any_orthonormal_vector());
+            let w = if n.cross(t).dot(bitangents[i]) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            vertex.tangent = [t.x, t.y, t.z, w];
+        }
+    }
+}
+
 pub fn create_uv_sphere(radius: f32, sectors: u32, stacks: u32) -> Mesh {
     let mut vertices = Vec::new();
     let mut indices = Vec::new();
@@ -68,15 +125,18 @@ pub fn create_uv_sphere(radius: f32, sectors: u32, stacks: u32) -> Mesh {
                 position: [x, y, z],
                 normal: [nx, ny, nz],
                 uv: [s, t],
+                tangent: [0.0; 4],
             });
         }
     }
 
     for i in 0..stacks {
-        let mut k1 = i * (sectors + 1);
-        let mut k2 = k1 + sectors + 1;
+        let row = i * (sectors + 1);
+
+        for j in 0..sectors {
+            let k1 = row + j;
+            let k2 = k1 + sectors + 1;
 
-        for _ in 0..sectors {
             if i != 0 {
                 indices.push(k1);
                 indices.push(k2);
@@ -88,11 +148,10 @@ pub fn create_uv_sphere(radius: f32, sectors: u32, stacks: u32) -> Mesh {
                 indices.push(k2);
                 indices.push(k2 + 1);
             }
-
-            k1 += 1;
-            k2 += 1;
         }
     }
 
-    Mesh { vertices, indices }
+    let mut mesh = Mesh { vertices, indices };
+    mesh.compute_tangents();
+    mesh
 }
@@ -4,28 +4,69 @@ use bytemuck::{Pod, Zeroable};
 #[derive(Debug, Copy, Clone, Pod, Zeroable)]
 pub struct Uniforms {
     pub view_proj: [[f32; 4]; 4],
+    pub view: [[f32; 4]; 4],
+    pub inverse_view: [[f32; 4]; 4],
+    pub model: [[f32; 4]; 4],
+    pub inverse_model: [[f32; 4]; 4],
+    pub projection: [[f32; 4]; 4],
+    pub inverse_projection: [[f32; 4]; 4],
     pub time: f32,
-    pub p1: f32,
-    pub p2: f32,
+    pub delta_time: f32,
+    pub frame_count: u32,
     pub p3: f32,
     pub resolution: [f32; 2],
     pub mouse: [f32; 2],
+    pub camera_position: [f32; 3],
+    pub p4: f32,
+    pub light_direction: [f32; 3],
+    pub light_intensity: f32,
+    pub light_color: [f32; 3],
+    pub ambient_intensity: f32,
 }
 
 impl Uniforms {
     pub fn new() -> Self {
         Self {
             view_proj: glam::Mat4::IDENTITY.to_cols_array_2d(),
+            view: glam::Mat4::IDENTITY.to_cols_array_2d(),
+            inverse_view: glam::Mat4::IDENTITY.to_cols_array_2d(),
+            model: glam::Mat4::IDENTITY.to_cols_array_2d(),
+            inverse_model: glam::Mat4::IDENTITY.to_cols_array_2d(),
+            projection: glam::Mat4::IDENTITY.to_cols_array_2d(),
+            inverse_projection: glam::Mat4::IDENTITY.to_cols_array_2d(),
             time: 0.0,
-            p1: 0.0,
-            p2: 0.0,
+            delta_time: 0.0,
+            frame_count: 0,
             p3: 0.0,
             resolution: [0.0, 0.0],
             mouse: [0.0, 0.0],
+            camera_position: [0.0, 0.0, 0.0],
+            p4: 0.0,
+            light_direction: [0.0, -1.0, 0.0],
+            light_intensity: 1.0,
+            light_color: [1.0, 1.0, 1.0],
+            ambient_intensity: 0.0,
         }
     }
 
-    pub fn update_view_proj(&mut self, camera: &super::camera::Camera) {
+    pub fn update_camera(&mut self, camera: &super::camera::Camera) {
+        let view = camera.build_view_matrix();
+        let projection = camera.build_projection_matrix();
         self.view_proj = camera.build_view_projection_matrix().to_cols_array_2d();
+        self.view = view.to_cols_array_2d();
+        self.inverse_view = view.inverse().to_cols_array_2d();
+        self.projection = projection.to_cols_array_2d();
+        self.inverse_projection = projection.inverse().to_cols_array_2d();
+        self.camera_position = camera.eye.to_array();
+    }
+
+    pub fn update_light(&mut self, light: &super::light::PreviewLight) {
+        self.light_direction = glam::Vec3::from(light.direction)
+            .try_normalize()
+            .unwrap_or(glam::Vec3::NEG_Y)
+            .to_array();
+        self.light_intensity = light.intensity;
+        self.light_color = light.color;
+        self.ambient_intensity = light.ambient;
     }
 }
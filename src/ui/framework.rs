@@ -7,6 +7,9 @@ pub struct AppContext<'a> {
     pub generated_shader: &'a mut String,
     pub apply_shader: &'a mut bool,
     pub preview_texture_id: egui::TextureId,
+    pub preview_light: &'a mut crate::renderer::PreviewLight,
+    pub node_library: &'a mut crate::nodes::library::NodeLibrary,
+    pub history: &'a mut crate::file::History,
     #[allow(dead_code)]
     pub time: f32,
     pub close_requested: &'a mut Option<String>,
@@ -0,0 +1,69 @@
+use crate::nodes::registry::global_registry;
+use crate::ui::framework::AppContext;
+use crate::ui::window::{WindowConfig, WindowContent, WindowKind};
+use egui::Ui;
+
+/// Lists the node directories, the nodes loaded from them and the
+/// definition files that failed to load.
+pub struct NodeLibraryPanel;
+
+impl WindowContent for NodeLibraryPanel {
+    fn config(&self) -> WindowConfig {
+        WindowConfig {
+            title: "Node Library".to_string(),
+            kind: WindowKind::Floating,
+            default_width: 360.0,
+            default_height: 280.0,
+            ..Default::default()
+        }
+    }
+
+    fn show(&mut self, ui: &mut Ui, ctx: &mut AppContext) {
+        let library = &mut *ctx.node_library;
+
+        ui.label("Node definitions are loaded from:");
+        if library.dirs().is_empty() {
+            ui.weak("No node directory");
+        }
+        for dir in library.dirs() {
+            ui.monospace(dir.display().to_string());
+        }
+        if ui.button("Reload").clicked() {
+            library.reload();
+        }
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            if !library.errors.is_empty() {
+                ui.strong(format!("Errors ({})", library.errors.len()));
+                for error in &library.errors {
+                    let file = error
+                        .path
+                        .file_name()
+                        .map(|f| f.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    ui.colored_label(ui.visuals().error_fg_color, file)
+                        .on_hover_text(error.path.display().to_string());
+                    ui.label(&error.message);
+                    ui.add_space(4.0);
+                }
+                ui.separator();
+            }
+
+            ui.strong(format!("Loaded nodes ({})", library.loaded().len()));
+            if library.loaded().is_empty() {
+                ui.weak("None");
+            }
+            let registry = global_registry().read().unwrap();
+            for id in library.loaded() {
+                let Some(node) = registry.get(id) else {
+                    continue;
+                };
+                ui.horizontal(|ui| {
+                    ui.label(registry.display_name(node.as_ref()));
+                    ui.weak(id);
+                });
+            }
+        });
+    }
+}
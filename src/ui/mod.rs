@@ -1,5 +1,6 @@
 pub mod framework;
 pub mod info;
+pub mod library;
 pub mod properties;
 pub mod theme;
 pub mod widgets;
@@ -8,6 +9,7 @@ pub mod window;
 // Re-export properties and framework
 pub use framework::{AppContext, UiManager};
 pub use info::InfoPanel;
+pub use library::NodeLibraryPanel;
 pub use properties::PropertiesPanel;
 #[allow(unused_imports)]
 pub use window::{WindowConfig, WindowContent, WindowKind};
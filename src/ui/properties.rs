@@ -1,4 +1,7 @@
-use crate::common::PropertyValue;
+use crate::common::{PropertyValue, sanitize_identifier};
+use crate::file::history::Edit;
+use crate::file::project::ShaderProperty;
+use crate::file::{History, UmbraProject};
 use crate::ui::framework::AppContext;
 use egui::Ui;
 
@@ -6,6 +9,15 @@ use crate::ui::window::{WindowConfig, WindowContent, WindowKind};
 
 pub struct PropertiesPanel;
 
+/// Change to the property list requested while drawing it, applied once the
+/// list has been drawn.
+enum PropertyAction {
+    Rename(String, String),
+    Remove(usize),
+    Duplicate(usize),
+    Move(usize, usize),
+}
+
 impl WindowContent for PropertiesPanel {
     fn config(&self) -> WindowConfig {
         WindowConfig {
@@ -28,6 +40,9 @@ impl PropertiesPanel {
             generated_shader,
             apply_shader,
             preview_texture_id,
+            preview_light,
+            node_library: _,
+            history,
             time: _,
             close_requested: _,
         } = app_context;
@@ -47,43 +62,109 @@ impl PropertiesPanel {
                 egui::vec2(width, height),
             ));
 
+            ui.collapsing("Preview Light", |ui| {
+                egui::Grid::new("preview_light_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Direction");
+                        crate::ui::widgets::vec3_editor(ui, &mut preview_light.direction);
+                        ui.end_row();
+
+                        ui.label("Color");
+                        ui.color_edit_button_rgb(&mut preview_light.color);
+                        ui.end_row();
+
+                        ui.label("Intensity");
+                        ui.add(
+                            egui::DragValue::new(&mut preview_light.intensity)
+                                .speed(0.05)
+                                .range(0.0..=100.0),
+                        );
+                        ui.end_row();
+
+                        ui.label("Ambient");
+                        ui.add(
+                            egui::DragValue::new(&mut preview_light.ambient)
+                                .speed(0.01)
+                                .range(0.0..=10.0),
+                        );
+                        ui.end_row();
+                    });
+            });
+
             ui.add_space(10.0);
             ui.separator();
 
             ui.collapsing("Shader Properties", |ui| {
-                ui.horizontal(|ui| {
-                    if ui.button("Add Float").clicked() {
-                        project.add_property("new_float", PropertyValue::Float(0.0));
-                    }
-                    if ui.button("Add Vec4").clicked() {
-                        project.add_property("new_vec4", PropertyValue::Vec4([0.0; 4]));
-                    }
-                    if ui.button("Add Color").clicked() {
-                        project
-                            .add_property("new_color", PropertyValue::Color([1.0, 1.0, 1.0, 1.0]));
+                ui.horizontal_wrapped(|ui| {
+                    let new_properties = [
+                        ("Add Float", "new_float", PropertyValue::Float(0.0)),
+                        ("Add Int", "new_int", PropertyValue::Int(0)),
+                        ("Add Bool", "new_bool", PropertyValue::Bool(false)),
+                        ("Add Vec3", "new_vec3", PropertyValue::Vec3([0.0; 3])),
+                        ("Add Vec4", "new_vec4", PropertyValue::Vec4([0.0; 4])),
+                        (
+                            "Add Color",
+                            "new_color",
+                            PropertyValue::Color([1.0, 1.0, 1.0, 1.0]),
+                        ),
+                        (
+                            "Add Mat4",
+                            "new_mat4",
+                            PropertyValue::Mat4(glam::Mat4::IDENTITY.to_cols_array_2d()),
+                        ),
+                    ];
+                    for (label, name, value) in new_properties {
+                        if ui.button(label).clicked() {
+                            let edit = project.add_property(name, value);
+                            history.push("Add Property", vec![edit]);
+                        }
                     }
                 });
 
-                for prop in project.properties.iter_mut() {
-                    ui.horizontal(|ui| {
-                        ui.label(&prop.name);
-                        match &mut prop.value {
-                            PropertyValue::Float(v) => {
-                                ui.add(egui::DragValue::new(v).speed(0.1));
-                            }
-                            PropertyValue::Color(c) => {
-                                crate::ui::widgets::color_picker(ui, c);
-                            }
-                            PropertyValue::Vec4(v) => {
-                                crate::ui::widgets::vec4_editor(ui, v);
-                            }
-                            PropertyValue::Vec2(v) => {
-                                crate::ui::widgets::vec2_editor(ui, v);
-                            }
-                            _ => {} // Other types not handled
+                let mut action = None;
+                let groups: Vec<(String, Vec<usize>)> = project
+                    .property_groups()
+                    .into_iter()
+                    .map(|(group, indices)| (group.to_string(), indices))
+                    .collect();
+                for (group, indices) in groups {
+                    if group.is_empty() {
+                        for index in indices {
+                            Self::show_property(ui, project, history, index, &mut action);
                         }
-                    });
+                    } else {
+                        egui::CollapsingHeader::new(group.as_str())
+                            .id_salt(("property_group", group.as_str()))
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                for index in indices {
+                                    Self::show_property(ui, project, history, index, &mut action);
+                                }
+                            });
+                    }
                 }
+
+                let (name, edits) = match action {
+                    Some(PropertyAction::Rename(old_name, new_name)) => (
+                        "Rename Property",
+                        project.rename_property(&old_name, &new_name),
+                    ),
+                    Some(PropertyAction::Remove(index)) => (
+                        "Delete Property",
+                        project.remove_property(index).into_iter().collect(),
+                    ),
+                    Some(PropertyAction::Duplicate(index)) => (
+                        "Duplicate Property",
+                        project.duplicate_property(index).into_iter().collect(),
+                    ),
+                    Some(PropertyAction::Move(from, to)) => (
+                        "Reorder Properties",
+                        project.move_property(from, to).into_iter().collect(),
+                    ),
+                    None => ("", Vec::new()),
+                };
+                history.push(name, edits);
             });
 
             ui.separator();
@@ -115,4 +196,193 @@ impl PropertiesPanel {
             }
         });
     }
+
+    /// Draws one property row: drag handle, name, value editor and the
+    /// metadata editor when it is open.
+    fn show_property(
+        ui: &mut Ui,
+        project: &mut UmbraProject,
+        history: &mut History,
+        index: usize,
+        action: &mut Option<PropertyAction>,
+    ) {
+        let before = project.properties[index].clone();
+        let meta_id = ui.id().with(("property_meta", index));
+        let mut meta_open = ui.data(|d| d.get_temp::<bool>(meta_id)).unwrap_or(false);
+
+        let row = ui.horizontal(|ui| {
+            // Drag the handle into the graph to add a node bound to the
+            // property, or onto another row to reorder
+            let handle_id = ui.id().with(("property_drag", index));
+            let property = &project.properties[index];
+            let name = property.name.clone();
+            let handle =
+                ui.dnd_drag_source(handle_id, crate::graph::PropertyDrag(name.clone()), |ui| {
+                    ui.label("⠿")
+                });
+            if !property.metadata.description.is_empty() {
+                handle
+                    .response
+                    .clone()
+                    .on_hover_text(&property.metadata.description);
+            }
+            handle.response.context_menu(|ui| {
+                if ui.button("Duplicate").clicked() {
+                    *action = Some(PropertyAction::Duplicate(index));
+                    ui.close();
+                }
+                if ui.button("Delete").clicked() {
+                    *action = Some(PropertyAction::Remove(index));
+                    ui.close();
+                }
+            });
+            if !property.metadata.display_name.is_empty() {
+                ui.label(&property.metadata.display_name);
+            }
+
+            // Names are edited in a buffer and applied once editing ends,
+            // so Property nodes are rebound a single time
+            let name_id = ui.id().with(("property_name", index));
+            let mut edited = ui
+                .data(|d| d.get_temp::<String>(name_id))
+                .unwrap_or_else(|| name.clone());
+            let response = ui.add(egui::TextEdit::singleline(&mut edited).desired_width(100.0));
+            let sanitized = sanitize_identifier(&edited);
+            let check = project.check_property_name(index, &sanitized);
+            if response.lost_focus() {
+                ui.data_mut(|d| d.remove::<String>(name_id));
+                if check.is_ok() && sanitized != name {
+                    *action = Some(PropertyAction::Rename(name, sanitized));
+                }
+            } else if response.has_focus() {
+                ui.data_mut(|d| d.insert_temp(name_id, edited));
+            }
+
+            Self::value_editor(ui, &mut project.properties[index]);
+
+            ui.toggle_value(&mut meta_open, "⚙")
+                .on_hover_text("Display options");
+            if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                *action = Some(PropertyAction::Remove(index));
+            }
+
+            // Shown while editing; an invalid name is discarded when
+            // editing ends
+            if response.has_focus() {
+                check.err()
+            } else {
+                None
+            }
+        });
+
+        if let Some(error) = row.inner {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        ui.data_mut(|d| d.insert_temp(meta_id, meta_open));
+        if meta_open {
+            Self::metadata_editor(ui, &mut project.properties[index], index);
+        }
+        if project.properties[index] != before {
+            history.push(
+                "Edit Property",
+                vec![Edit::ChangeProperty { index, before }],
+            );
+        }
+
+        // Dropping another property's handle on this row moves it here
+        let dragged = row
+            .response
+            .dnd_hover_payload::<crate::graph::PropertyDrag>();
+        if dragged.is_some() {
+            let rect = row.response.rect;
+            ui.painter()
+                .hline(rect.x_range(), rect.top(), ui.visuals().selection.stroke);
+        }
+        if let Some(payload) = row
+            .response
+            .dnd_release_payload::<crate::graph::PropertyDrag>()
+            && let Some(from) = project.properties.iter().position(|p| p.name == payload.0)
+        {
+            *action = Some(PropertyAction::Move(from, index));
+        }
+    }
+
+    /// Draws the widget editing a property's value, following its metadata.
+    fn value_editor(ui: &mut Ui, property: &mut ShaderProperty) {
+        let metadata = &property.metadata;
+        let options = crate::ui::widgets::EditorOptions {
+            range: metadata.range(),
+            step: metadata.step,
+            slider: metadata.slider,
+            hdr: metadata.hdr,
+        };
+        crate::ui::widgets::property_editor(ui, &property.name, &mut property.value, options);
+    }
+
+    /// Draws the editor of a property's display name, description, range and
+    /// other presentation options.
+    fn metadata_editor(ui: &mut Ui, property: &mut ShaderProperty, index: usize) {
+        let is_number = matches!(
+            property.value,
+            PropertyValue::Float(_) | PropertyValue::Int(_)
+        );
+        let is_color = matches!(property.value, PropertyValue::Color(_));
+        let metadata = &mut property.metadata;
+
+        egui::Grid::new(("property_metadata", index))
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Display name");
+                ui.text_edit_singleline(&mut metadata.display_name);
+                ui.end_row();
+
+                ui.label("Description");
+                ui.add(egui::TextEdit::multiline(&mut metadata.description).desired_rows(2));
+                ui.end_row();
+
+                ui.label("Group");
+                ui.text_edit_singleline(&mut metadata.group);
+                ui.end_row();
+
+                if is_number {
+                    for (label, bound, default) in [
+                        ("Min", &mut metadata.min, 0.0),
+                        ("Max", &mut metadata.max, 1.0),
+                        ("Step", &mut metadata.step, 0.01),
+                    ] {
+                        ui.label(label);
+                        optional_number(ui, bound, default);
+                        ui.end_row();
+                    }
+
+                    ui.label("Slider");
+                    ui.add_enabled(
+                        metadata.range().is_some(),
+                        egui::Checkbox::without_text(&mut metadata.slider),
+                    )
+                    .on_disabled_hover_text("Set a min and max to use a slider");
+                    ui.end_row();
+                }
+
+                if is_color {
+                    ui.label("HDR");
+                    ui.checkbox(&mut metadata.hdr, "");
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// Draws a checkbox enabling an optional value, starting at `default`, and
+/// its editor when set.
+fn optional_number(ui: &mut Ui, value: &mut Option<f32>, default: f32) {
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        if ui.checkbox(&mut enabled, "").changed() {
+            *value = enabled.then_some(default);
+        }
+        if let Some(v) = value {
+            ui.add(egui::DragValue::new(v).speed(0.1));
+        }
+    });
 }
@@ -2,6 +2,7 @@
 //!
 //! These helpers reduce code duplication across the UI.
 
+use crate::common::PropertyValue;
 use egui::Ui;
 
 /// Displays a color picker widget for an RGBA color array.
@@ -29,32 +30,307 @@ pub fn color_picker(ui: &mut Ui, rgba: &mut [f32; 4]) -> bool {
     }
 }
 
-/// Displays a labeled color picker widget.
+/// Displays a Vec4 editor with 4 drag values.
+///
+/// Returns `true` if the value was changed.
+pub fn vec4_editor(ui: &mut Ui, value: &mut [f32; 4]) -> bool {
+    ui.horizontal(|ui| {
+        let mut changed = false;
+        for v in value.iter_mut() {
+            changed |= ui.add(egui::DragValue::new(v).speed(0.1)).changed();
+        }
+        changed
+    })
+    .inner
+}
+
+/// Displays a Vec3 editor with 3 drag values.
+///
+/// Returns `true` if the value was changed.
+pub fn vec3_editor(ui: &mut Ui, value: &mut [f32; 3]) -> bool {
+    ui.horizontal(|ui| {
+        let mut changed = false;
+        for v in value.iter_mut() {
+            changed |= ui.add(egui::DragValue::new(v).speed(0.1)).changed();
+        }
+        changed
+    })
+    .inner
+}
+
+/// Displays a Vec2 editor with 2 drag values.
+///
+/// Returns `true` if the value was changed.
+pub fn vec2_editor(ui: &mut Ui, value: &mut [f32; 2]) -> bool {
+    ui.horizontal(|ui| {
+        let mut changed = false;
+        for v in value.iter_mut() {
+            changed |= ui.add(egui::DragValue::new(v).speed(0.1)).changed();
+        }
+        changed
+    })
+    .inner
+}
+
+/// Displays a column-major 3x3 matrix editor, one row per line.
+///
+/// Returns `true` if the matrix was changed.
+pub fn mat3_editor(ui: &mut Ui, columns: &mut [[f32; 3]; 3]) -> bool {
+    ui.vertical(|ui| {
+        let mut changed = false;
+        for row in 0..3 {
+            ui.horizontal(|ui| {
+                for column in columns.iter_mut() {
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut column[row]).speed(0.1))
+                        .changed();
+                }
+            });
+        }
+        changed
+    })
+    .inner
+}
+
+/// Displays a column-major 4x4 matrix editor, one row per line.
+///
+/// Returns `true` if the matrix was changed.
+pub fn mat4_editor(ui: &mut Ui, columns: &mut [[f32; 4]; 4]) -> bool {
+    ui.vertical(|ui| {
+        let mut changed = false;
+        for row in 0..4 {
+            ui.horizontal(|ui| {
+                for column in columns.iter_mut() {
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut column[row]).speed(0.1))
+                        .changed();
+                }
+            });
+        }
+        changed
+    })
+    .inner
+}
+
+/// Displays a dropdown for choosing one of a fixed set of options.
+///
+/// Returns `true` if the selection was changed.
+pub fn enum_combo(
+    ui: &mut Ui,
+    id_salt: impl std::hash::Hash,
+    selected: &mut usize,
+    options: &[String],
+) -> bool {
+    let before = *selected;
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(options.get(*selected).map(String::as_str).unwrap_or("-"))
+        .show_ui(ui, |ui| {
+            for (i, option) in options.iter().enumerate() {
+                ui.selectable_value(selected, i, option);
+            }
+        });
+    *selected != before
+}
+
+/// Displays a number editor, as a slider if requested and a range is given,
+/// otherwise as a drag value clamped to the range.
+///
+/// Returns `true` if the value was changed.
+pub fn number_editor<N: egui::emath::Numeric>(
+    ui: &mut Ui,
+    value: &mut N,
+    range: Option<(f32, f32)>,
+    step: Option<f32>,
+    slider: bool,
+) -> bool {
+    let response = match range {
+        Some((min, max)) if slider => {
+            let mut widget =
+                egui::Slider::new(value, N::from_f64(min as f64)..=N::from_f64(max as f64));
+            if let Some(step) = step {
+                widget = widget.step_by(step as f64);
+            }
+            ui.add(widget)
+        }
+        _ => {
+            let mut widget = egui::DragValue::new(value).speed(step.unwrap_or(0.1));
+            if let Some((min, max)) = range {
+                widget = widget.range(min..=max);
+            }
+            ui.add(widget)
+        }
+    };
+    response.changed()
+}
+
+/// Displays a color picker with an intensity for colors that may exceed 1.
 ///
 /// Returns `true` if the color was changed.
-pub fn labeled_color_picker(ui: &mut Ui, label: &str, rgba: &mut [f32; 4]) -> bool {
+pub fn hdr_color_picker(ui: &mut Ui, rgba: &mut [f32; 4]) -> bool {
+    let intensity = rgba[0].max(rgba[1]).max(rgba[2]).max(1.0);
+    let mut base = [
+        rgba[0] / intensity,
+        rgba[1] / intensity,
+        rgba[2] / intensity,
+        rgba[3],
+    ];
+    let mut new_intensity = intensity;
     let mut changed = false;
     ui.horizontal(|ui| {
-        ui.label(label);
-        changed = color_picker(ui, rgba);
+        changed |= color_picker(ui, &mut base);
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut new_intensity)
+                    .speed(0.05)
+                    .range(1.0..=f32::MAX)
+                    .prefix("× "),
+            )
+            .changed();
     });
+    if changed {
+        *rgba = [
+            base[0] * new_intensity,
+            base[1] * new_intensity,
+            base[2] * new_intensity,
+            base[3],
+        ];
+    }
     changed
 }
 
-/// Displays a Vec4 editor with 4 drag values.
-pub fn vec4_editor(ui: &mut Ui, value: &mut [f32; 4]) {
-    ui.horizontal(|ui| {
-        ui.add(egui::DragValue::new(&mut value[0]).speed(0.1));
-        ui.add(egui::DragValue::new(&mut value[1]).speed(0.1));
-        ui.add(egui::DragValue::new(&mut value[2]).speed(0.1));
-        ui.add(egui::DragValue::new(&mut value[3]).speed(0.1));
-    });
+/// How [`property_editor`] draws a value, e.g. following a project
+/// property's metadata.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EditorOptions {
+    /// Bounds of numbers.
+    pub range: Option<(f32, f32)>,
+    /// Drag speed or slider step of numbers.
+    pub step: Option<f32>,
+    /// Draws numbers with a range as a slider.
+    pub slider: bool,
+    /// Lets colors exceed 1 with an intensity.
+    pub hdr: bool,
 }
 
-/// Displays a Vec2 editor with 2 drag values.
-pub fn vec2_editor(ui: &mut Ui, value: &mut [f32; 2]) {
+/// Displays the editor of any property value: numbers, vectors, colors,
+/// matrices, checkboxes, texture paths, dropdowns and text. Used for node
+/// settings and project properties alike. `id_salt` tells apart the
+/// dropdowns of different values.
+///
+/// Returns `true` if the value was changed.
+pub fn property_editor(
+    ui: &mut Ui,
+    id_salt: impl std::hash::Hash,
+    value: &mut PropertyValue,
+    options: EditorOptions,
+) -> bool {
+    let EditorOptions {
+        range,
+        step,
+        slider,
+        hdr,
+    } = options;
+    match value {
+        PropertyValue::Float(v) => number_editor(ui, v, range, step, slider),
+        PropertyValue::Int(v) => {
+            let step = step.map(|s| s.max(1.0));
+            number_editor(ui, v, range, step, slider)
+        }
+        PropertyValue::Bool(v) => ui.checkbox(v, "").changed(),
+        PropertyValue::Vec2(v) => vec2_editor(ui, v),
+        PropertyValue::Vec3(v) => vec3_editor(ui, v),
+        PropertyValue::Vec4(v) => vec4_editor(ui, v),
+        PropertyValue::Color(c) if hdr => hdr_color_picker(ui, c),
+        PropertyValue::Color(c) => color_picker(ui, c),
+        PropertyValue::Mat3(m) => mat3_editor(ui, m),
+        PropertyValue::Mat4(m) => mat4_editor(ui, m),
+        PropertyValue::Texture(path) => texture_picker(ui, path),
+        PropertyValue::Enum { selected, options } => enum_combo(ui, id_salt, selected, options),
+        PropertyValue::Text(text) => ui
+            .add(egui::TextEdit::singleline(text).desired_width(64.0))
+            .changed(),
+    }
+}
+
+/// Displays the file name of a texture, a button choosing an image file and
+/// one clearing it.
+///
+/// Returns `true` if the texture was changed.
+pub fn texture_picker(ui: &mut Ui, path: &mut Option<std::path::PathBuf>) -> bool {
     ui.horizontal(|ui| {
-        ui.add(egui::DragValue::new(&mut value[0]).speed(0.1));
-        ui.add(egui::DragValue::new(&mut value[1]).speed(0.1));
-    });
+        let name = path
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map_or("None".to_string(), |f| f.to_string_lossy().to_string());
+        let mut button = ui.button(name);
+        if let Some(p) = path.as_ref() {
+            button = button.on_hover_text(p.display().to_string());
+        }
+        if button.clicked()
+            && let Some(picked) = rfd::FileDialog::new()
+                .add_filter("Image", &["png", "jpg", "jpeg", "bmp", "tga", "hdr", "exr"])
+                .pick_file()
+        {
+            *path = Some(picked);
+            return true;
+        }
+        if path.is_some() && ui.small_button("✖").on_hover_text("Clear").clicked() {
+            *path = None;
+            return true;
+        }
+        false
+    })
+    .inner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every kind of value, with a range for the number editors.
+    fn values() -> Vec<PropertyValue> {
+        vec![
+            PropertyValue::Float(0.5),
+            PropertyValue::Int(3),
+            PropertyValue::Bool(true),
+            PropertyValue::Vec2([0.0; 2]),
+            PropertyValue::Vec3([0.0; 3]),
+            PropertyValue::Vec4([0.0; 4]),
+            PropertyValue::Color([1.0, 0.5, 0.0, 1.0]),
+            PropertyValue::Mat3([[0.0; 3]; 3]),
+            PropertyValue::Mat4([[0.0; 4]; 4]),
+            PropertyValue::Texture(None),
+            PropertyValue::enumeration(1, &["Multiply", "Screen"]),
+            PropertyValue::Text("yx".to_string()),
+        ]
+    }
+
+    #[test]
+    fn every_value_kind_gets_an_editor() {
+        let ctx = egui::Context::default();
+        let options = [
+            EditorOptions::default(),
+            EditorOptions {
+                range: Some((0.0, 10.0)),
+                step: Some(0.5),
+                slider: true,
+                hdr: true,
+            },
+        ];
+        for options in options {
+            for mut value in values() {
+                let before = value.clone();
+                let _ = ctx.run(egui::RawInput::default(), |ctx| {
+                    egui::CentralPanel::default().show(ctx, |ui| {
+                        let start = ui.cursor().min;
+                        let changed = property_editor(ui, "value", &mut value, options);
+                        assert!(!changed, "{:?}", before);
+                        // Something was drawn for the value
+                        assert!(ui.min_rect().max.y > start.y, "{:?}", before);
+                    });
+                });
+                assert_eq!(value, before);
+            }
+        }
+    }
 }